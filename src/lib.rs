@@ -3,26 +3,153 @@
 //! tracking for `clemp update`, and CLI parsing.
 
 use anyhow::{bail, Context, Result};
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use minijinja::Environment;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use sha2::{Digest, Sha256};
-use std::collections::{BTreeMap, HashSet};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::{Mutex, OnceLock, PoisonError};
 use std::{env, fs};
 
+/// Name of the template checkout inside the per-run `ScratchDir`.
 pub const CLONE_DIR: &str = "claude-template";
 pub const LOCKFILE_NAME: &str = ".clemp-lock.yaml";
+/// Where `build_settings` writes, relative to the project (or package) root.
+pub const SETTINGS_PATH: &str = ".claude/settings.local.json";
+/// Where `build_settings` writes the parts routed to the committed, shared
+/// settings file (see `SettingsPart`).
+pub const SHARED_SETTINGS_PATH: &str = ".claude/settings.json";
 
 // ── Config ───────────────────────────────────────────────────────────────
 
 #[derive(Serialize, Deserialize, Default)]
 pub struct Config {
-    #[serde(rename = "gh-repo")]
+    /// Legacy single-template URL from before named profiles existed. Still
+    /// honoured as the `default` profile when `templates` doesn't define one.
+    #[serde(rename = "gh-repo", default, skip_serializing_if = "Option::is_none")]
     pub gh_repo: Option<String>,
+    /// Named template sources: profile name → repo URL or local directory.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub templates: BTreeMap<String, String>,
+    /// Named stacks: stack name → profiles (or local directories) merged in
+    /// order, later entries overriding earlier ones.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub stacks: BTreeMap<String, Vec<String>>,
+    /// Profile (or stack) used when `--template` isn't passed.
+    #[serde(
+        rename = "default-template",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub default_template: Option<String>,
+}
+
+/// Profile name assigned to the legacy `gh-repo` entry and to the URL captured
+/// by the first-run prompt.
+pub const DEFAULT_PROFILE: &str = "default";
+
+impl Config {
+    /// Resolve a template profile to `(profile name, repo URL)`.
+    ///
+    /// An explicit `name` must exist in `templates` (or be `default` with a
+    /// legacy `gh-repo` set, or be a path to a local template directory).
+    /// Without a name, `default-template` wins, then the legacy `gh-repo`,
+    /// then a lone `templates` entry. Returns `Ok(None)` only when nothing is
+    /// configured at all, so the caller can prompt.
+    pub fn resolve_template(&self, name: Option<&str>) -> Result<Option<(String, String)>> {
+        if let Some(name) = name {
+            if let Some(url) = self.templates.get(name) {
+                return Ok(Some((name.to_string(), url.clone())));
+            }
+            if name == DEFAULT_PROFILE
+                && let Some(url) = &self.gh_repo
+            {
+                return Ok(Some((name.to_string(), url.clone())));
+            }
+            // Ad-hoc local template directory, e.g. `--template ../my-template`.
+            if local_template_path(name).is_some_and(|p| p.is_dir()) {
+                return Ok(Some((name.to_string(), name.to_string())));
+            }
+            bail!(
+                "Unknown template profile '{}'. Configured profiles: {:?}\n\
+                 Add it under `templates:` in {}.",
+                name,
+                self.profile_names(),
+                config_path()?.display()
+            );
+        }
+
+        if let Some(default) = &self.default_template {
+            return self.resolve_template(Some(default));
+        }
+        if let Some(url) = &self.gh_repo {
+            return Ok(Some((DEFAULT_PROFILE.to_string(), url.clone())));
+        }
+        match self.templates.len() {
+            0 => Ok(None),
+            1 => {
+                let (name, url) = self.templates.iter().next().unwrap();
+                Ok(Some((name.clone(), url.clone())))
+            }
+            _ => bail!(
+                "Multiple template profiles configured ({:?}) but no `default-template` set.\n\
+                 Pass --template <name> or set `default-template` in {}.",
+                self.profile_names(),
+                config_path()?.display()
+            ),
+        }
+    }
+
+    /// Resolve the `--template` values of one invocation to the ordered list
+    /// of `(profile name, repo URL)` sources to stack. Stack names expand to
+    /// their members; with no names the `default-template` (profile or stack)
+    /// applies, falling back to `resolve_template(None)`. Returns `Ok(None)`
+    /// only when nothing is configured at all.
+    pub fn resolve_templates(&self, names: &[String]) -> Result<Option<Vec<(String, String)>>> {
+        let names: Vec<String> = match (names.is_empty(), &self.default_template) {
+            (false, _) => names.to_vec(),
+            (true, Some(default)) => vec![default.clone()],
+            (true, None) => return Ok(self.resolve_template(None)?.map(|s| vec![s])),
+        };
+
+        let mut sources: Vec<(String, String)> = Vec::new();
+        for name in &names {
+            let members = match self.stacks.get(name) {
+                Some(members) if !self.templates.contains_key(name) => members.clone(),
+                _ => vec![name.clone()],
+            };
+            for member in &members {
+                let Some(source) = self.resolve_template(Some(member))? else {
+                    continue;
+                };
+                if sources.iter().any(|(_, url)| *url == source.1) {
+                    bail!(
+                        "Template '{}' ({}) is stacked more than once",
+                        source.0,
+                        source.1
+                    );
+                }
+                sources.push(source);
+            }
+        }
+        Ok(Some(sources))
+    }
+
+    /// Every configured profile name, including the implicit `default` backed
+    /// by a legacy `gh-repo`.
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.templates.keys().cloned().collect();
+        if self.gh_repo.is_some() && !self.templates.contains_key(DEFAULT_PROFILE) {
+            names.push(DEFAULT_PROFILE.to_string());
+            names.sort();
+        }
+        names
+    }
 }
 
 pub fn config_path() -> Result<PathBuf> {
@@ -63,22 +190,65 @@ pub fn prompt_for_repo() -> Result<String> {
     Ok(url)
 }
 
-pub fn get_repo_url() -> Result<String> {
+/// Resolve the `--template` values (or the configured default) to the ordered
+/// `(profile name, repo URL)` sources to stack. Prompts only on first run, when
+/// the config holds no template at all; the answer is saved as the `default`
+/// profile (or under the single name passed).
+pub fn get_template_sources(profiles: &[String]) -> Result<Vec<(String, String)>> {
     let mut config = load_config()?;
-    if let Some(url) = &config.gh_repo {
-        return Ok(url.clone());
+    if let Some(resolved) = config.resolve_templates(profiles)? {
+        return Ok(resolved);
     }
     let url = prompt_for_repo()?;
-    config.gh_repo = Some(url.clone());
+    let name = match profiles {
+        [] => DEFAULT_PROFILE.to_string(),
+        [name] => name.clone(),
+        _ => bail!(
+            "No template profiles configured. Add them under `templates:` in {}.",
+            config_path()?.display()
+        ),
+    };
+    config.templates.insert(name.clone(), url.clone());
+    config.default_template = Some(name.clone());
     save_config(&config)?;
     println!("Saved to {}", config_path()?.display());
-    Ok(url)
+    Ok(vec![(name, url)])
 }
 
 // ── CLI ──────────────────────────────────────────────────────────────────
 
+/// A part of the assembled settings that can be written to the shared
+/// `.claude/settings.json` instead of the per-user `.claude/settings.local.json`.
+#[derive(
+    ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum SettingsPart {
+    /// The assembled `hooks` block.
+    Hooks,
+    /// The lists from `permissions/` contributors.
+    Permissions,
+    /// `enabledMcpjsonServers`.
+    Mcp,
+    /// The clarg `PreToolUse` hook.
+    Clarg,
+}
+
+impl std::fmt::Display for SettingsPart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Hooks => "hooks",
+            Self::Permissions => "permissions",
+            Self::Mcp => "mcp",
+            Self::Clarg => "clarg",
+        })
+    }
+}
+
 /// Shared argument set used by both the default setup command and `clemp update`.
-/// Fields map 1:1 onto `OriginalCommand` fields stored in the lockfile.
+/// Fields map 1:1 onto `OriginalCommand` fields stored in the lockfile, except
+/// `template` / `git_ref` (recorded on `Lockfile` itself) and the runtime-only
+/// `force` / `offline`.
 #[derive(Args, Clone, Debug, Default)]
 pub struct SetupArgs {
     /// Language(s) for rules (e.g., ts, typescript, py, python, swift)
@@ -101,22 +271,78 @@ pub struct SetupArgs {
     #[arg(long, value_delimiter = ',', num_args = 1..)]
     pub githooks: Vec<String>,
 
+    /// Extra permission contributor names to include (comma or space separated)
+    #[arg(long, value_delimiter = ',', num_args = 1..)]
+    pub permissions: Vec<String>,
+
     /// MCP server file stems to exclude from `.mcp.json` (opts out of a default
     /// or a previously-sticky contributor). Comma or space separated.
     #[arg(long = "drop-mcp", value_delimiter = ',', num_args = 1..)]
     pub drop_mcp: Vec<String>,
 
-    /// Hook file stems to exclude from `.claude/settings.local.json`. Comma or space separated.
+    /// Hook file stems to exclude from the settings. Comma or space separated.
     #[arg(long = "drop-hooks", value_delimiter = ',', num_args = 1..)]
     pub drop_hooks: Vec<String>,
 
+    /// Permission file stems to exclude from the settings. Comma or space separated.
+    #[arg(long = "drop-permissions", value_delimiter = ',', num_args = 1..)]
+    pub drop_permissions: Vec<String>,
+
+    /// Languages to stop rendering, including ones pulled in by the template's
+    /// `implies:` table. Comma or space separated.
+    #[arg(long = "drop-lang", value_delimiter = ',', num_args = 1..)]
+    pub drop_lang: Vec<String>,
+
+    /// Framework/tooling tags to enable, e.g. `react,docker` (comma or space separated)
+    #[arg(long = "with", value_name = "TAG", value_delimiter = ',', num_args = 1..)]
+    pub tags: Vec<String>,
+
+    /// Tags to stop rendering (opts out of a previously-sticky tag). Comma or space separated.
+    #[arg(long = "without", value_name = "TAG", value_delimiter = ',', num_args = 1..)]
+    pub drop_tags: Vec<String>,
+
     /// Clarg config profile to enable (name of a YAML file in the template's clarg/ directory)
     #[arg(long)]
     pub clarg: Option<String>,
 
+    /// Settings parts to write to the shared `.claude/settings.json` instead of
+    /// `.claude/settings.local.json`, on top of the template's `shared-settings`
+    #[arg(long = "shared-settings", value_name = "PART", value_delimiter = ',', num_args = 1..)]
+    pub shared_settings: Vec<SettingsPart>,
+
+    /// Settings parts to keep in `.claude/settings.local.json` even when the
+    /// template shares them
+    #[arg(long = "local-settings", value_name = "PART", value_delimiter = ',', num_args = 1..)]
+    pub local_settings: Vec<SettingsPart>,
+
+    /// Named template profile or stack from ~/.config/clemp/clemp.yaml to render
+    /// from (defaults to `default-template`). Repeat to stack templates; later
+    /// ones override earlier ones.
+    #[arg(long, value_delimiter = ',')]
+    pub template: Vec<String>,
+
+    /// Branch, tag or commit SHA of the (first) template to render from.
+    /// Recorded in the lockfile so later updates follow the same ref.
+    #[arg(long = "ref", value_name = "REF")]
+    pub git_ref: Option<String>,
+
     /// Overwrite existing files/directories without prompting for merge
     #[arg(long)]
     pub force: bool,
+
+    /// Skip checking MCP server entries against the known `.mcp.json` shapes
+    #[arg(long)]
+    pub no_validate: bool,
+
+    /// Fail instead of warning when one MCP contributor shadows a server
+    /// defined by another
+    #[arg(long)]
+    pub strict_mcp: bool,
+
+    /// Render from the cached template copy without fetching; fails if the
+    /// template (or the requested ref) isn't cached yet
+    #[arg(long)]
+    pub offline: bool,
 }
 
 #[derive(Parser)]
@@ -134,6 +360,8 @@ pub struct Cli {
     pub setup: SetupArgs,
 }
 
+// Parsed once per process; boxing `UpdateArgs` would buy nothing.
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 pub enum CliCommand {
     /// Update an existing clemp-configured project from the template (additive).
@@ -141,10 +369,19 @@ pub enum CliCommand {
     Update(UpdateArgs),
 
     /// List available template files for a category
-    /// (mcp, hooks, commands, githooks, clarg, gitignore, languages)
+    /// (mcp, hooks, permissions, commands, githooks, clarg, gitignore, languages, tags)
     List {
         /// Category to list; omit to list every category
         category: Option<String>,
+
+        /// Named template profile or stack to list from (defaults to
+        /// `default-template`). Repeat to list a stacked template.
+        #[arg(long, value_delimiter = ',')]
+        template: Vec<String>,
+
+        /// List from the cached template copy without fetching
+        #[arg(long)]
+        offline: bool,
     },
 }
 
@@ -153,7 +390,7 @@ pub struct UpdateArgs {
     #[command(flatten)]
     pub setup: SetupArgs,
 
-    /// Delete files the template no longer produces without prompting
+    /// Delete files, contributors and tags the template no longer provides without prompting
     #[arg(long)]
     pub prune_stale: bool,
 
@@ -168,14 +405,15 @@ pub struct UpdateArgs {
     /// Apply only the additions from this invocation — don't sync upstream
     /// template changes.  Pins the template at the lockfile's SHA so only
     /// the flags you pass (e.g. --mcp, --hooks) have an effect.
-    #[arg(long)]
+    #[arg(long, conflicts_with_all = ["template", "git_ref"])]
     pub only: bool,
 }
 
 // ── Lockfile ─────────────────────────────────────────────────────────────
 
 /// Captures the invocation that produced a clemp-configured project. Mirrors the
-/// public fields of `SetupArgs` minus `force` (which is runtime-only).
+/// public fields of `SetupArgs` minus `force` / `no_validate` / `strict_mcp` /
+/// `offline` (which are runtime-only).
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
 pub struct OriginalCommand {
     #[serde(default)]
@@ -188,6 +426,8 @@ pub struct OriginalCommand {
     pub commands: Vec<String>,
     #[serde(default)]
     pub githooks: Vec<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
     /// MCP contributor stems the user has explicitly excluded. Persisted so the
     /// exclusion survives subsequent `clemp update` runs.
     #[serde(default, rename = "drop-mcp")]
@@ -195,8 +435,26 @@ pub struct OriginalCommand {
     /// Hook contributor stems the user has explicitly excluded.
     #[serde(default, rename = "drop-hooks")]
     pub drop_hooks: Vec<String>,
+    /// Permission contributor stems the user has explicitly excluded.
+    #[serde(default, rename = "drop-permissions")]
+    pub drop_permissions: Vec<String>,
+    /// Languages the user has explicitly excluded, typed or implied.
+    #[serde(default, rename = "drop-lang")]
+    pub drop_lang: Vec<String>,
+    /// Framework/tooling tags from `--with`.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Tags the user has explicitly excluded via `--without`.
+    #[serde(default, rename = "drop-tags")]
+    pub drop_tags: Vec<String>,
     #[serde(default)]
     pub clarg: Option<String>,
+    /// Settings parts routed to `.claude/settings.json` via `--shared-settings`.
+    #[serde(default, rename = "shared-settings")]
+    pub shared_settings: Vec<SettingsPart>,
+    /// Settings parts kept in `.claude/settings.local.json` via `--local-settings`.
+    #[serde(default, rename = "local-settings")]
+    pub local_settings: Vec<SettingsPart>,
 }
 
 impl OriginalCommand {
@@ -207,14 +465,23 @@ impl OriginalCommand {
             mcp: args.mcp.clone(),
             commands: args.commands.clone(),
             githooks: args.githooks.clone(),
+            permissions: args.permissions.clone(),
             drop_mcp: args.drop_mcp.clone(),
             drop_hooks: args.drop_hooks.clone(),
+            drop_permissions: args.drop_permissions.clone(),
+            drop_lang: args.drop_lang.clone(),
+            tags: args.tags.clone(),
+            drop_tags: args.drop_tags.clone(),
             clarg: args.clarg.clone(),
+            shared_settings: args.shared_settings.clone(),
+            local_settings: args.local_settings.clone(),
         }
     }
 
-    /// Produce a `SetupArgs` from this stored command. `force` is always `false`
-    /// — runtime flag, not persisted.
+    /// Produce a `SetupArgs` from this stored command. `force`,
+    /// `no_validate`, `strict_mcp` and `offline` are always `false` — runtime
+    /// flags, not persisted — and `template` / `git_ref` are left unset (they
+    /// live on `Lockfile` itself).
     pub fn into_setup(self) -> SetupArgs {
         SetupArgs {
             languages: self.languages,
@@ -222,73 +489,158 @@ impl OriginalCommand {
             mcp: self.mcp,
             commands: self.commands,
             githooks: self.githooks,
+            permissions: self.permissions,
             drop_mcp: self.drop_mcp,
             drop_hooks: self.drop_hooks,
+            drop_permissions: self.drop_permissions,
+            drop_lang: self.drop_lang,
+            tags: self.tags,
+            drop_tags: self.drop_tags,
             clarg: self.clarg,
+            shared_settings: self.shared_settings,
+            local_settings: self.local_settings,
+            template: Vec::new(),
+            git_ref: None,
             force: false,
+            no_validate: false,
+            strict_mcp: false,
+            offline: false,
         }
     }
 
     /// Additive union with another command (used by `clemp update [args]`).
     /// `clarg` is replaced only if `other.clarg` is `Some`. Vectors are unioned
     /// preserving insertion order, skipping duplicates. Languages are deduped
-    /// against their canonical form so `ts` + `typescript` don't both land in
-    /// the merged command.
+    /// against their canonical form (per `aliases`) so `ts` + `typescript`
+    /// don't both land in the merged command.
     ///
     /// Positive/negative reconciliation: for each (`<kind>`, `drop_<kind>`)
     /// pair, the **newer** invocation wins per stem. A stem in `other.mcp`
     /// clears any existing `drop_mcp` entry for that stem and then unions into
     /// `self.mcp` (and symmetrically the other way). Within a single
     /// invocation, the same stem appearing in both `<kind>` and `drop_<kind>`
-    /// is a hard error.
-    pub fn merge_additive(&mut self, other: &OriginalCommand) -> Result<()> {
-        fn union(a: &mut Vec<String>, b: &[String]) {
+    /// is a hard error. `languages` / `drop_lang` follow the same rule,
+    /// compared by canonical name, as do `tags` / `drop_tags` and
+    /// `shared_settings` / `local_settings`.
+    pub fn merge_additive(
+        &mut self,
+        other: &OriginalCommand,
+        aliases: &LanguageAliases,
+    ) -> Result<()> {
+        fn union<T: Clone + PartialEq>(a: &mut Vec<T>, b: &[T]) {
             for item in b {
                 if !a.contains(item) {
                     a.push(item.clone());
                 }
             }
         }
-        fn canonical_key(s: &str) -> String {
-            normalize_language(s)
-                .map(String::from)
-                .unwrap_or_else(|| s.to_lowercase())
-        }
-        fn union_languages(a: &mut Vec<String>, b: &[String]) {
-            let mut seen: HashSet<String> = a.iter().map(|s| canonical_key(s)).collect();
+        let union_languages = |a: &mut Vec<String>, b: &[String]| {
+            let mut seen: HashSet<String> = a.iter().map(|s| aliases.canonical(s)).collect();
             for item in b {
-                if seen.insert(canonical_key(item)) {
+                if seen.insert(aliases.canonical(item)) {
                     a.push(item.clone());
                 }
             }
-        }
-        reject_add_drop_overlap(other)?;
+        };
+        let canonical_set = |langs: &[String]| -> HashSet<String> {
+            langs.iter().map(|s| aliases.canonical(s)).collect()
+        };
+        reject_add_drop_overlap(other, aliases)?;
 
         // Newer flag clears the opposing entry before the union.
+        let added_languages = canonical_set(&other.languages);
+        let dropped_languages = canonical_set(&other.drop_lang);
+        self.drop_lang
+            .retain(|s| !added_languages.contains(&aliases.canonical(s)));
+        self.languages
+            .retain(|s| !dropped_languages.contains(&aliases.canonical(s)));
         self.drop_mcp.retain(|s| !other.mcp.contains(s));
         self.mcp.retain(|s| !other.drop_mcp.contains(s));
         self.drop_hooks.retain(|s| !other.hooks.contains(s));
         self.hooks.retain(|s| !other.drop_hooks.contains(s));
+        self.drop_permissions
+            .retain(|s| !other.permissions.contains(s));
+        self.permissions
+            .retain(|s| !other.drop_permissions.contains(s));
+        self.drop_tags.retain(|s| !other.tags.contains(s));
+        self.tags.retain(|s| !other.drop_tags.contains(s));
+        self.local_settings
+            .retain(|p| !other.shared_settings.contains(p));
+        self.shared_settings
+            .retain(|p| !other.local_settings.contains(p));
 
         union_languages(&mut self.languages, &other.languages);
         union(&mut self.hooks, &other.hooks);
         union(&mut self.mcp, &other.mcp);
         union(&mut self.commands, &other.commands);
         union(&mut self.githooks, &other.githooks);
+        union(&mut self.permissions, &other.permissions);
         union(&mut self.drop_mcp, &other.drop_mcp);
         union(&mut self.drop_hooks, &other.drop_hooks);
+        union(&mut self.drop_permissions, &other.drop_permissions);
+        union_languages(&mut self.drop_lang, &other.drop_lang);
+        union(&mut self.tags, &other.tags);
+        union(&mut self.drop_tags, &other.drop_tags);
+        union(&mut self.shared_settings, &other.shared_settings);
+        union(&mut self.local_settings, &other.local_settings);
         if other.clarg.is_some() {
             self.clarg = other.clarg.clone();
         }
         Ok(())
     }
+
+    /// Layer `defaults` (a project's `.clemp.yaml`) *under* this command: its
+    /// entries are added unless this command, being newer, says the opposite,
+    /// so a persisted `--drop-lang` / `--without` / `--drop-mcp` survives a
+    /// config that still lists the item. Returns the entries of `defaults`
+    /// that were overridden this way, as `<key>: <name>`.
+    pub fn merge_defaults(
+        &mut self,
+        defaults: &OriginalCommand,
+        aliases: &LanguageAliases,
+    ) -> Result<Vec<String>> {
+        let mut layered = defaults.clone();
+        layered.merge_additive(self, aliases)?;
+        self.merge_additive(&layered, aliases)?;
+
+        let lists = |c: &OriginalCommand| {
+            let langs = |l: &[String]| l.iter().map(|s| aliases.canonical(s)).collect::<Vec<_>>();
+            let parts = |p: &[SettingsPart]| p.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+            [
+                ("languages", langs(&c.languages)),
+                ("drop-lang", langs(&c.drop_lang)),
+                ("mcp", c.mcp.clone()),
+                ("drop-mcp", c.drop_mcp.clone()),
+                ("hooks", c.hooks.clone()),
+                ("drop-hooks", c.drop_hooks.clone()),
+                ("permissions", c.permissions.clone()),
+                ("drop-permissions", c.drop_permissions.clone()),
+                ("tags", c.tags.clone()),
+                ("drop-tags", c.drop_tags.clone()),
+                ("shared-settings", parts(&c.shared_settings)),
+                ("local-settings", parts(&c.local_settings)),
+            ]
+        };
+        Ok(lists(defaults)
+            .into_iter()
+            .zip(lists(self))
+            .flat_map(|((key, wanted), (_, kept))| {
+                wanted
+                    .into_iter()
+                    .filter(move |name| !kept.contains(name))
+                    .map(move |name| format!("{key}: {name}"))
+            })
+            .collect())
+    }
 }
 
 /// Reject invocations where the same stem appears in both `<kind>` and
 /// `drop_<kind>`. Called from both initial setup (on the `SetupArgs`-derived
 /// command) and `merge_additive` (on the incoming side of the merge) so the
 /// "same-invocation add+drop is illegal" rule holds for every entry point.
-pub fn reject_add_drop_overlap(cmd: &OriginalCommand) -> Result<()> {
+/// Languages are compared by canonical name, so `ts` + `--drop-lang
+/// typescript` is caught too.
+pub fn reject_add_drop_overlap(cmd: &OriginalCommand, aliases: &LanguageAliases) -> Result<()> {
     fn check(add: &[String], drop: &[String], label: &str) -> Result<()> {
         for s in add {
             if drop.contains(s) {
@@ -301,6 +653,29 @@ pub fn reject_add_drop_overlap(cmd: &OriginalCommand) -> Result<()> {
     }
     check(&cmd.mcp, &cmd.drop_mcp, "mcp")?;
     check(&cmd.hooks, &cmd.drop_hooks, "hooks")?;
+    check(&cmd.permissions, &cmd.drop_permissions, "permissions")?;
+    for lang in &cmd.languages {
+        let canonical = aliases.canonical(lang);
+        if cmd
+            .drop_lang
+            .iter()
+            .any(|d| aliases.canonical(d) == canonical)
+        {
+            bail!("'{lang}' is both a language and in --drop-lang in the same invocation");
+        }
+    }
+    for tag in &cmd.tags {
+        if cmd.drop_tags.contains(tag) {
+            bail!("'{tag}' appears in both --with and --without in the same invocation");
+        }
+    }
+    for part in &cmd.shared_settings {
+        if cmd.local_settings.contains(part) {
+            bail!(
+                "'{part}' appears in both --shared-settings and --local-settings in the same invocation"
+            );
+        }
+    }
     Ok(())
 }
 
@@ -316,6 +691,8 @@ pub struct Resolved {
     pub mcp: Vec<String>,
     #[serde(default)]
     pub hooks: Vec<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
 }
 
 /// Persisted at `.clemp-lock.yaml` in the project root after a successful
@@ -326,6 +703,39 @@ pub struct Lockfile {
     pub template_repo: String,
     #[serde(rename = "template-sha")]
     pub template_sha: String,
+    /// Config profile name `template_repo` was resolved from. `None` on
+    /// lockfiles written before named profiles existed.
+    #[serde(
+        default,
+        rename = "template-profile",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub template_profile: Option<String>,
+    /// Branch, tag or SHA passed via `--ref`. `None` means "follow the
+    /// template's default branch".
+    #[serde(
+        default,
+        rename = "template-ref",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub template_ref: Option<String>,
+    /// Ancestor templates the rendered tree inherited from via `extends`,
+    /// nearest parent first. A change in any of them counts as a template
+    /// change on `clemp update`.
+    #[serde(
+        default,
+        rename = "template-parents",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub template_parents: Vec<LockedSource>,
+    /// Further templates stacked on top of `template_repo` via repeated
+    /// `--template`, in merge order. Each records its own SHA and ancestors.
+    #[serde(
+        default,
+        rename = "template-overlays",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub template_overlays: Vec<TemplateRevision>,
     #[serde(rename = "original-command")]
     pub original_command: OriginalCommand,
     /// `None` on pre-snapshot lockfiles (missing field). Forces a full update
@@ -334,11 +744,38 @@ pub struct Lockfile {
     /// `run_update`.
     #[serde(default)]
     pub resolved: Option<Resolved>,
+    /// Workspace packages keyed by their directory relative to the project
+    /// root. Their files are tracked in `files` under that prefix.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub packages: BTreeMap<String, PackageLock>,
+    /// `.mcp.json` path → the `mcpServers` map clemp last rendered there,
+    /// before placeholder substitution. The base of the server-by-server merge
+    /// in `run_update`; without it `.mcp.json` is classified as a whole file.
+    #[serde(
+        default,
+        rename = "mcp-servers",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub mcp_servers: BTreeMap<String, Map<String, Value>>,
+    /// `.claude/settings.local.json` path → the settings clemp last rendered
+    /// there. The base of the key-by-key settings merge in `run_update`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub settings: BTreeMap<String, Value>,
     /// Relative path → sha256 hex digest of the file clemp wrote there.
     /// Paths are normalized to forward-slash form for cross-platform stability.
     pub files: BTreeMap<String, String>,
 }
 
+/// The command and contributor snapshot of one workspace package — the
+/// per-package counterpart of `Lockfile::original_command` / `resolved`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PackageLock {
+    #[serde(rename = "original-command")]
+    pub original_command: OriginalCommand,
+    #[serde(default)]
+    pub resolved: Option<Resolved>,
+}
+
 impl Lockfile {
     pub fn path(dest_dir: &Path) -> PathBuf {
         dest_dir.join(LOCKFILE_NAME)
@@ -363,6 +800,34 @@ impl Lockfile {
             .with_context(|| format!("Failed to write {}", path.display()))?;
         Ok(())
     }
+
+    /// Every template source the project was rendered from, base first, in
+    /// the order they were stacked.
+    pub fn sources(&self) -> Vec<TemplateRevision> {
+        let base = TemplateRevision {
+            profile: self.template_profile.clone(),
+            repo: self.template_repo.clone(),
+            sha: self.template_sha.clone(),
+            parents: self.template_parents.clone(),
+        };
+        std::iter::once(base)
+            .chain(self.template_overlays.iter().cloned())
+            .collect()
+    }
+
+    /// The template ref an update invocation should clone. An explicit
+    /// `--ref` wins; otherwise the recorded ref is followed, unless `--template`
+    /// switches the base (first) template to another profile (a ref from the
+    /// old repo means nothing in the new one).
+    pub fn followed_ref(&self, args: &SetupArgs) -> Option<String> {
+        if args.git_ref.is_some() {
+            return args.git_ref.clone();
+        }
+        match args.template.first() {
+            Some(name) if self.template_profile.as_ref() != Some(name) => None,
+            _ => self.template_ref.clone(),
+        }
+    }
 }
 
 /// SHA-256 hex digest of a file's bytes.
@@ -411,17 +876,71 @@ pub fn normalize_language(input: &str) -> Option<&'static str> {
     }
 }
 
+/// Language alias table: lowercase alias → canonical name. Entries declared by
+/// the template (`aliases:` in `clemp-template.yaml`) take precedence; anything
+/// else falls back to the built-in `normalize_language` table, then to the
+/// lowercased input itself.
+#[derive(Debug, Clone, Default)]
+pub struct LanguageAliases {
+    table: BTreeMap<String, String>,
+}
+
+impl LanguageAliases {
+    /// Alias table of the template checked out at `clone_dir`.
+    pub fn load(clone_dir: &Path) -> Result<Self> {
+        Ok(Self::from_manifest(&TemplateManifest::load(clone_dir)?))
+    }
+
+    pub fn from_manifest(manifest: &TemplateManifest) -> Self {
+        let mut table = BTreeMap::new();
+        for (canonical, aliases) in &manifest.aliases {
+            let canonical = canonical.to_lowercase();
+            for alias in aliases {
+                table.insert(alias.to_lowercase(), canonical.clone());
+            }
+            table.insert(canonical.clone(), canonical);
+        }
+        Self { table }
+    }
+
+    /// Canonical name for a user-typed language.
+    pub fn canonical(&self, input: &str) -> String {
+        let lower = input.to_lowercase();
+        if let Some(canonical) = self.table.get(&lower) {
+            return canonical.clone();
+        }
+        normalize_language(&lower)
+            .map(String::from)
+            .unwrap_or(lower)
+    }
+}
+
 pub enum LanguageResolution {
     HasRulesFile(String),
     ConditionalOnly(String),
     NoMatch,
 }
 
+/// Template directories whose `default/` and `<lang>/` (or `tags/<tag>/`)
+/// subdirectories are copied or merged only when that language or tag is active.
+const CONDITIONAL_DIRS: &[&str] = &[
+    "commands",
+    "skills",
+    "copied",
+    "mcp",
+    "hooks",
+    "permissions",
+    "settings",
+    "githooks",
+];
+
 /// Resolve a language input against the template's rules files and conditional directories.
-pub fn resolve_language(input: &str, clone_dir: &Path) -> LanguageResolution {
-    let canonical = normalize_language(input)
-        .map(String::from)
-        .unwrap_or_else(|| input.to_lowercase());
+pub fn resolve_language(
+    input: &str,
+    aliases: &LanguageAliases,
+    clone_dir: &Path,
+) -> LanguageResolution {
+    let canonical = aliases.canonical(input);
 
     let rules_file = clone_dir
         .join("claude-md/lang-rules")
@@ -431,9 +950,11 @@ pub fn resolve_language(input: &str, clone_dir: &Path) -> LanguageResolution {
         return LanguageResolution::HasRulesFile(canonical);
     }
 
-    let has_conditional_dir = ["commands", "skills", "copied", "mcp", "githooks"]
-        .iter()
-        .any(|dir| clone_dir.join(dir).join(&canonical).is_dir());
+    // `tags/` holds tag subdirectories, never a language's own files.
+    let has_conditional_dir = canonical != TAGS_DIR
+        && CONDITIONAL_DIRS
+            .iter()
+            .any(|dir| clone_dir.join(dir).join(&canonical).is_dir());
 
     let has_gitignore_fragment = clone_dir
         .join("gitignore-additions")
@@ -460,32 +981,274 @@ pub fn resolve_language(input: &str, clone_dir: &Path) -> LanguageResolution {
 
 /// Resolve all language inputs, erroring on unknown languages. Output is
 /// deduplicated by canonical name so callers never see the same language twice
-/// (e.g. `ts` and `typescript` collapse to a single `typescript` entry).
+/// (e.g. `ts` and `typescript` collapse to a single `typescript` entry), and
+/// includes every language implied by the template's `implies:` table.
 pub fn resolve_all_languages(inputs: &[String], clone_dir: &Path) -> Result<Vec<String>> {
+    Ok(resolve_languages_explained(inputs, &[], clone_dir)?.0)
+}
+
+/// A language pulled in by another one through the template's `implies:`
+/// table rather than typed by the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImpliedLanguage {
+    pub language: String,
+    pub implied_by: String,
+}
+
+/// `resolve_all_languages`, also reporting which languages were added by
+/// implication. Implications expand transitively and depth-first, so implied
+/// languages follow the language that pulled them in. A language the user
+/// typed is never reported as implied. Languages in `dropped` are skipped
+/// along with everything only they imply.
+pub fn resolve_languages_explained(
+    inputs: &[String],
+    dropped: &[String],
+    clone_dir: &Path,
+) -> Result<(Vec<String>, Vec<ImpliedLanguage>)> {
+    let manifest = TemplateManifest::load(clone_dir)?;
+    let aliases = LanguageAliases::from_manifest(&manifest);
+    let implies: BTreeMap<String, &Vec<String>> = manifest
+        .implies
+        .iter()
+        .map(|(lang, implied)| (aliases.canonical(lang), implied))
+        .collect();
+    let typed: HashSet<String> = inputs.iter().map(|lang| aliases.canonical(lang)).collect();
+    let dropped: HashSet<String> = dropped.iter().map(|lang| aliases.canonical(lang)).collect();
+
     let mut resolved = Vec::new();
+    let mut implied = Vec::new();
     let mut seen: HashSet<String> = HashSet::new();
     for lang in inputs {
-        match resolve_language(lang, clone_dir) {
-            LanguageResolution::HasRulesFile(canonical) | LanguageResolution::ConditionalOnly(canonical) => {
-                if seen.insert(canonical.clone()) {
-                    resolved.push(canonical);
+        let mut pending: Vec<(String, Option<String>)> = vec![(lang.clone(), None)];
+        while let Some((input, implied_by)) = pending.pop() {
+            if dropped.contains(&aliases.canonical(&input)) {
+                continue;
+            }
+            let canonical = match resolve_language(&input, &aliases, clone_dir) {
+                LanguageResolution::HasRulesFile(canonical)
+                | LanguageResolution::ConditionalOnly(canonical) => canonical,
+                LanguageResolution::NoMatch => {
+                    let canonical = aliases.canonical(&input);
+                    match implied_by {
+                        Some(by) => bail!(
+                            "Language '{}' implied by '{}' ({} `implies:`) has no rules file (claude-md/lang-rules/{}.md) and no conditional directories in template",
+                            input,
+                            by,
+                            TEMPLATE_MANIFEST_NAME,
+                            canonical
+                        ),
+                        None => bail!(
+                            "Unknown language '{}': no rules file (claude-md/lang-rules/{}.md) and no conditional directories in template",
+                            input,
+                            canonical
+                        ),
+                    }
                 }
+            };
+            if !seen.insert(canonical.clone()) {
+                continue;
             }
-            LanguageResolution::NoMatch => {
-                let canonical = normalize_language(lang)
-                    .map(String::from)
-                    .unwrap_or_else(|| lang.to_lowercase());
-                bail!(
-                    "Unknown language '{}': no rules file (claude-md/lang-rules/{}.md) and no conditional directories in template",
-                    lang,
-                    canonical
+            if let Some(by) = implied_by
+                && !typed.contains(&canonical)
+            {
+                implied.push(ImpliedLanguage {
+                    language: canonical.clone(),
+                    implied_by: by,
+                });
+            }
+            if let Some(next) = implies.get(&canonical) {
+                pending.extend(
+                    next.iter()
+                        .rev()
+                        .map(|n| (n.clone(), Some(canonical.clone()))),
                 );
             }
+            resolved.push(canonical);
+        }
+    }
+    Ok((resolved, implied))
+}
+
+// ── Tags ─────────────────────────────────────────────────────────────────
+
+/// Subdirectory of every conditional dir (and `gitignore-additions/`) that
+/// holds per-tag content, e.g. `commands/tags/react/` or
+/// `gitignore-additions/tags/docker.gitignore`.
+pub const TAGS_DIR: &str = "tags";
+
+/// Resolve `--with` tags against the template: each must have a
+/// `claude-md/tag-rules/<tag>.md` rules file or some `tags/<tag>` conditional
+/// content. Tags are lowercased and deduplicated; those in `dropped` are
+/// skipped.
+pub fn resolve_tags(
+    inputs: &[String],
+    dropped: &[String],
+    clone_dir: &Path,
+) -> Result<Vec<String>> {
+    let dropped: HashSet<String> = dropped.iter().map(|t| t.to_lowercase()).collect();
+    let mut resolved: Vec<String> = Vec::new();
+    for input in inputs {
+        let tag = input.to_lowercase();
+        if dropped.contains(&tag) || resolved.contains(&tag) {
+            continue;
+        }
+        let has_rules_file = clone_dir
+            .join("claude-md/tag-rules")
+            .join(format!("{tag}.md"))
+            .is_file();
+        let has_conditional_dir = CONDITIONAL_DIRS
+            .iter()
+            .any(|dir| clone_dir.join(dir).join(TAGS_DIR).join(&tag).is_dir());
+        let has_gitignore_fragment = clone_dir
+            .join("gitignore-additions")
+            .join(TAGS_DIR)
+            .join(format!("{tag}.gitignore"))
+            .is_file();
+        if !(has_rules_file || has_conditional_dir || has_gitignore_fragment) {
+            bail!(
+                "Unknown tag '{}': no rules file (claude-md/tag-rules/{}.md) and no {}/{} directories in template",
+                input,
+                tag,
+                TAGS_DIR,
+                tag
+            );
         }
+        resolved.push(tag);
     }
     Ok(resolved)
 }
 
+/// Subdirectory names consulted under each conditional dir: every resolved
+/// language, then `tags/<tag>` for every resolved tag.
+pub fn conditional_subdirs(languages: &[String], tags: &[String]) -> Vec<String> {
+    languages
+        .iter()
+        .cloned()
+        .chain(tags.iter().map(|tag| format!("{TAGS_DIR}/{tag}")))
+        .collect()
+}
+
+// ── Language detection ───────────────────────────────────────────────────
+
+/// Marker-file rule for detecting a project language in the working tree.
+/// Matches when every `markers` entry exists in the project root and none of
+/// the `unless` entries do. Entries are file names; a single `*` wildcard
+/// (e.g. `*.csproj`) matches any file name around it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DetectRule {
+    pub language: String,
+    pub markers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unless: Vec<String>,
+}
+
+/// Built-in detection rules as `(language, markers, unless)`.
+const BUILTIN_DETECT_RULES: &[(&str, &[&str], &[&str])] = &[
+    ("rust", &["Cargo.toml"], &[]),
+    ("typescript", &["package.json", "tsconfig.json"], &[]),
+    ("javascript", &["package.json"], &["tsconfig.json"]),
+    ("svelte", &["svelte.config.js"], &[]),
+    ("python", &["pyproject.toml"], &[]),
+    ("python", &["setup.py"], &[]),
+    ("python", &["requirements.txt"], &[]),
+    ("go", &["go.mod"], &[]),
+    ("ruby", &["Gemfile"], &[]),
+    ("csharp", &["*.csproj"], &[]),
+    ("csharp", &["*.sln"], &[]),
+    ("swift", &["Package.swift"], &[]),
+    ("java", &["pom.xml"], &[]),
+    ("java", &["build.gradle"], &[]),
+    ("cplusplus", &["CMakeLists.txt"], &[]),
+];
+
+/// Detection rules for a template: its own `detect:` rules from
+/// `clemp-template.yaml` first, then the built-in ones.
+pub fn detection_rules(manifest: &TemplateManifest) -> Vec<DetectRule> {
+    let builtin = BUILTIN_DETECT_RULES
+        .iter()
+        .map(|(language, markers, unless)| DetectRule {
+            language: language.to_string(),
+            markers: markers.iter().map(|m| m.to_string()).collect(),
+            unless: unless.iter().map(|m| m.to_string()).collect(),
+        });
+    manifest.detect.iter().cloned().chain(builtin).collect()
+}
+
+/// Languages whose rules match the files in `dir`, in rule order, without
+/// duplicates.
+pub fn detect_languages(dir: &Path, rules: &[DetectRule]) -> Vec<String> {
+    let names: Vec<String> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default();
+    let present = |marker: &String| match marker.split_once('*') {
+        Some((prefix, suffix)) => names.iter().any(|n| {
+            n.len() >= prefix.len() + suffix.len() && n.starts_with(prefix) && n.ends_with(suffix)
+        }),
+        None => names.contains(marker),
+    };
+
+    let mut detected: Vec<String> = Vec::new();
+    for rule in rules {
+        if !rule.markers.is_empty()
+            && rule.markers.iter().all(present)
+            && !rule.unless.iter().any(present)
+            && !detected.contains(&rule.language)
+        {
+            detected.push(rule.language.clone());
+        }
+    }
+    detected
+}
+
+/// Fill in `args.languages` from the working tree when none were given. Only
+/// detected languages the template at `clone_dir` can actually resolve are
+/// offered, and the user must confirm them; declining (or a non-interactive
+/// stdin) keeps the language list empty.
+pub fn apply_detected_languages(args: &mut SetupArgs, clone_dir: &Path, cwd: &Path) -> Result<()> {
+    if !args.languages.is_empty() {
+        return Ok(());
+    }
+    let manifest = TemplateManifest::load(clone_dir)?;
+    let aliases = LanguageAliases::from_manifest(&manifest);
+    let dropped: HashSet<String> = args
+        .drop_lang
+        .iter()
+        .map(|l| aliases.canonical(l))
+        .collect();
+    let (supported, unsupported): (Vec<String>, Vec<String>) =
+        detect_languages(cwd, &detection_rules(&manifest))
+            .into_iter()
+            .filter(|lang| !dropped.contains(lang))
+            .partition(|lang| {
+                !matches!(
+                    resolve_language(lang, &aliases, clone_dir),
+                    LanguageResolution::NoMatch
+                )
+            });
+    if !unsupported.is_empty() {
+        println!(
+            "Detected {} but the template has no rules for {}; skipping.",
+            unsupported.join(", "),
+            if unsupported.len() == 1 { "it" } else { "them" }
+        );
+    }
+    if supported.is_empty() {
+        return Ok(());
+    }
+    if confirm(&format!(
+        "Detected languages: {}. Use them?",
+        supported.join(", ")
+    ))? {
+        args.languages = supported;
+    }
+    Ok(())
+}
+
 // ── Rules building ───────────────────────────────────────────────────────
 
 pub fn build_language_rules(languages: &[String], claude_md_dir: &Path) -> Result<String> {
@@ -511,6 +1274,29 @@ pub fn build_language_rules(languages: &[String], claude_md_dir: &Path) -> Resul
     Ok(sections.join("\n\n"))
 }
 
+pub fn build_tag_rules(tags: &[String], claude_md_dir: &Path) -> Result<String> {
+    let tag_rules_dir = claude_md_dir.join("tag-rules");
+    let mut sections = Vec::new();
+
+    for tag in tags {
+        let rules_file = tag_rules_dir.join(format!("{}.md", tag));
+        if !rules_file.exists() {
+            continue; // Tags may only contribute conditional directories
+        }
+        let content = fs::read_to_string(&rules_file)
+            .with_context(|| format!("Failed to read {}", rules_file.display()))?;
+
+        sections.push(format!(
+            "<{}-tag-rules>\n{}\n</{}-tag-rules>",
+            tag,
+            content.trim(),
+            tag
+        ));
+    }
+
+    Ok(sections.join("\n\n"))
+}
+
 pub fn build_mcp_rules(active_mcps: &[String], claude_md_dir: &Path) -> Result<String> {
     let mcp_rules_dir = claude_md_dir.join("mcp-rules");
     let mut sections = Vec::new();
@@ -544,10 +1330,21 @@ pub struct LayerSpec {
     pub root: bool,
 }
 
-pub const MCP_LAYERS: LayerSpec = LayerSpec { default: true, languages: true, root: true };
-/// Hooks today have no `hooks/<lang>/` dir in the template; keep the resolver
-/// honest. If lang-dirs for hooks are added later, flip `languages` to `true`.
-pub const HOOKS_LAYERS: LayerSpec = LayerSpec { default: true, languages: false, root: true };
+pub const MCP_LAYERS: LayerSpec = LayerSpec {
+    default: true,
+    languages: true,
+    root: true,
+};
+pub const HOOKS_LAYERS: LayerSpec = LayerSpec {
+    default: true,
+    languages: true,
+    root: true,
+};
+pub const PERMISSIONS_LAYERS: LayerSpec = LayerSpec {
+    default: true,
+    languages: true,
+    root: true,
+};
 
 /// Layers consulted when validating a **fresh** positive add (a stem newly
 /// appearing in `merged.<kind>`). Default and root are allowed; language is
@@ -560,15 +1357,16 @@ pub const HOOKS_LAYERS: LayerSpec = LayerSpec { default: true, languages: false,
 /// surprise. Language is out because pinning a language-scoped stem as sticky
 /// would survive a later language drop and contradict the "language layers
 /// stay dynamic" invariant. Historical entries still use the broader
-/// `MCP_LAYERS` / `HOOKS_LAYERS` so the move-fallback story for
+/// `MCP_LAYERS` / `HOOKS_LAYERS` / `PERMISSIONS_LAYERS` so the move-fallback story for
 /// previously-persisted opt-ins keeps working.
 pub const FRESH_POSITIVE_LAYERS: LayerSpec =
     LayerSpec { default: true, languages: false, root: true };
 
 /// Locate the file that contributes `stem` under `<kind>/` for the enabled
 /// layers, in `default → lang → root` order. Returns `None` if no layer
-/// produces a hit. `kind` is the subdirectory name (`"mcp"` or `"hooks"`) and
-/// `ext` is the file extension without dot (`"json"` for both current kinds).
+/// produces a hit. `kind` is the subdirectory name (`"mcp"`, `"hooks"` or
+/// `"permissions"`) and `ext` is the file extension without dot (`"json"` for
+/// every current kind).
 pub fn resolve_contributor(
     kind: &str,
     ext: &str,
@@ -579,25 +1377,29 @@ pub fn resolve_contributor(
 ) -> Option<PathBuf> {
     let base = clone_dir.join(kind);
     let filename = format!("{}.{}", stem, ext);
-    if layers.default {
-        let p = base.join("default").join(&filename);
+    // A not-yet-rendered `<stem>.<ext>.jinja` counts as the same contributor.
+    let find = |dir: PathBuf| -> Option<PathBuf> {
+        let p = dir.join(&filename);
         if p.is_file() {
             return Some(p);
         }
+        let p = dir.join(format!("{filename}.jinja"));
+        p.is_file().then_some(p)
+    };
+    if layers.default
+        && let Some(p) = find(base.join("default"))
+    {
+        return Some(p);
     }
     if layers.languages {
         for lang in languages {
-            let p = base.join(lang).join(&filename);
-            if p.is_file() {
+            if let Some(p) = find(base.join(lang)) {
                 return Some(p);
             }
         }
     }
     if layers.root {
-        let p = base.join(&filename);
-        if p.is_file() {
-            return Some(p);
-        }
+        return find(base);
     }
     None
 }
@@ -619,11 +1421,8 @@ pub fn available_contributor_stems(
         let Ok(entries) = fs::read_dir(dir) else { return };
         let mut names: Vec<String> = entries
             .filter_map(|e| e.ok())
-            .filter(|e| {
-                let p = e.path();
-                p.is_file() && p.extension().and_then(|e| e.to_str()) == Some(ext)
-            })
-            .filter_map(|e| e.path().file_stem().map(|s| s.to_string_lossy().into_owned()))
+            .filter(|e| e.path().is_file())
+            .filter_map(|e| contributor_stem(&e.file_name().to_string_lossy(), ext))
             .collect();
         names.sort();
         for n in names {
@@ -655,10 +1454,15 @@ pub fn available_contributor_stems(
 /// For fresh positive adds: must resolve via `resolve_contributor` in the
 /// current template.  For fresh drops: must match some known stem in the
 /// current template layers, the snapshot, or the prior `original_command`.
+/// Fresh `--drop-lang` / `--without` entries must name a language or tag the
+/// template knows or one the prior command rendered.
+///
+/// `conditional` is the render's conditional subdirectories (resolved
+/// languages, then `tags/<tag>`), which the language layer of each kind walks.
 pub fn validate_fresh_additions(
     previous: &OriginalCommand,
     merged: &OriginalCommand,
-    resolved_languages: &[String],
+    conditional: &[String],
     snapshot: Option<&Resolved>,
     clone_dir: &Path,
 ) -> Result<()> {
@@ -668,6 +1472,7 @@ pub fn validate_fresh_additions(
 
     let snap_mcp: &[String] = snapshot.map(|r| r.mcp.as_slice()).unwrap_or(&[]);
     let snap_hooks: &[String] = snapshot.map(|r| r.hooks.as_slice()).unwrap_or(&[]);
+    let snap_permissions: &[String] = snapshot.map(|r| r.permissions.as_slice()).unwrap_or(&[]);
 
     // Fresh positive adds must resolve at the default or root layer — see
     // `FRESH_POSITIVE_LAYERS` for the rationale. Language-only contributors
@@ -684,7 +1489,7 @@ pub fn validate_fresh_additions(
                 ext,
                 &FRESH_POSITIVE_LAYERS,
                 stem,
-                resolved_languages,
+                conditional,
                 clone_dir,
             )
             .is_none()
@@ -693,7 +1498,7 @@ pub fn validate_fresh_additions(
                     kind,
                     ext,
                     &FRESH_POSITIVE_LAYERS,
-                    resolved_languages,
+                    conditional,
                     clone_dir,
                 );
                 bail!(
@@ -716,13 +1521,12 @@ pub fn validate_fresh_additions(
      -> Result<()> {
         for stem in stems {
             let in_template =
-                resolve_contributor(kind, ext, layers, stem, resolved_languages, clone_dir)
-                    .is_some();
+                resolve_contributor(kind, ext, layers, stem, conditional, clone_dir).is_some();
             let in_snap = snap.iter().any(|s| s == stem);
             let in_prior = prior.iter().any(|s| s == stem);
             if !(in_template || in_snap || in_prior) {
                 let mut available =
-                    available_contributor_stems(kind, ext, layers, resolved_languages, clone_dir);
+                    available_contributor_stems(kind, ext, layers, conditional, clone_dir);
                 for s in snap.iter().chain(prior.iter()) {
                     if !available.contains(s) {
                         available.push(s.clone());
@@ -744,9 +1548,12 @@ pub fn validate_fresh_additions(
     let fresh_hooks = fresh(&merged.hooks, &previous.hooks);
     let fresh_drop_mcp = fresh(&merged.drop_mcp, &previous.drop_mcp);
     let fresh_drop_hooks = fresh(&merged.drop_hooks, &previous.drop_hooks);
+    let fresh_permissions = fresh(&merged.permissions, &previous.permissions);
+    let fresh_drop_permissions = fresh(&merged.drop_permissions, &previous.drop_permissions);
 
     check_positive("MCP", "mcp", "json", &fresh_mcp)?;
     check_positive("Hook", "hooks", "json", &fresh_hooks)?;
+    check_positive("Permission", "permissions", "json", &fresh_permissions)?;
     check_drop(
         "mcp",
         "mcp",
@@ -765,14 +1572,50 @@ pub fn validate_fresh_additions(
         snap_hooks,
         &previous.hooks,
     )?;
+    check_drop(
+        "permissions",
+        "permissions",
+        "json",
+        &PERMISSIONS_LAYERS,
+        &fresh_drop_permissions,
+        snap_permissions,
+        &previous.permissions,
+    )?;
+
+    let aliases = LanguageAliases::load(clone_dir)?;
+    let prior_languages: HashSet<String> = previous
+        .languages
+        .iter()
+        .map(|l| aliases.canonical(l))
+        .collect();
+    for lang in fresh(&merged.drop_lang, &previous.drop_lang) {
+        if !prior_languages.contains(&aliases.canonical(lang))
+            && matches!(
+                resolve_language(lang, &aliases, clone_dir),
+                LanguageResolution::NoMatch
+            )
+        {
+            bail!(
+                "--drop-lang '{lang}' does not match any language in the template or in {LOCKFILE_NAME}"
+            );
+        }
+    }
+    for tag in fresh(&merged.drop_tags, &previous.drop_tags) {
+        if !previous.tags.iter().any(|t| t == tag)
+            && resolve_tags(&[tag.to_string()], &[], clone_dir).is_err()
+        {
+            bail!("--without '{tag}' does not match any tag in the template or in {LOCKFILE_NAME}");
+        }
+    }
     Ok(())
 }
 
 // ── MCP assembly ─────────────────────────────────────────────────────────
 
-/// Result of assembling an aggregation output (`.mcp.json` or
-/// `.claude/settings.local.json`'s hooks block) from layered contributors.
-#[derive(Debug, Clone)]
+/// Result of assembling an aggregation output (`.mcp.json`, or
+/// `.claude/settings.local.json`'s hooks block / permission lists) from
+/// layered contributors.
+#[derive(Debug, Clone, Default)]
 pub struct AssemblyResult {
     /// The merged JSON value (the caller decides where to write it).
     pub rendered: Value,
@@ -785,47 +1628,186 @@ pub struct AssemblyResult {
     /// implicitly drops its contributors without going through the stale
     /// prompt. Default-layer and explicit-layer stems are included.
     pub snapshottable_stems: Vec<String>,
+    /// MCP only: template-relative path of the contributor that supplied each
+    /// server key. Empty for hooks, whose buckets concatenate instead.
+    pub key_sources: BTreeMap<String, String>,
+    /// MCP only: server keys a later contributor replaced with a different
+    /// entry, in merge order.
+    pub shadowed: Vec<ServerShadow>,
+    /// MCP only: non-fatal problems `validate_mcp_server` found, each
+    /// prefixed with the server key and contributor path.
+    pub warnings: Vec<String>,
 }
 
-/// Merge the JSON body of a single contributor file into a running accumulator.
-fn merge_json_file_into(path: &Path, dest: &mut Map<String, Value>) -> Result<()> {
-    let content = fs::read_to_string(path)
-        .with_context(|| format!("Failed to read {}", path.display()))?;
-    let obj: Map<String, Value> = serde_json::from_str(&content)
-        .with_context(|| format!("Failed to parse {}", path.display()))?;
-    dest.extend(obj);
-    Ok(())
+/// A server key defined differently by two MCP contributors. The later one
+/// in `default → lang → explicit` order wins.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerShadow {
+    pub key: String,
+    /// Template-relative path of the contributor whose entry was kept.
+    pub winner: String,
+    /// Template-relative path of the contributor whose entry was replaced.
+    pub shadowed: String,
+    /// `true` for the documented root override: a typed `--mcp <stem>` whose
+    /// `mcp/<stem>.json` replaces default or language entries.
+    pub intended: bool,
+}
+
+/// Running `mcpServers` map plus the bookkeeping `assemble_mcp_json` reports.
+struct ServerAccumulator<'a> {
+    servers: Map<String, Value>,
+    key_sources: BTreeMap<String, String>,
+    shadowed: Vec<ServerShadow>,
+    warnings: Vec<String>,
+    validate: bool,
+    clone_dir: &'a Path,
+}
+
+impl ServerAccumulator<'_> {
+    /// Merge the JSON body of a single contributor file. With `validate`, each
+    /// server entry is checked by `validate_mcp_server` and its warnings kept.
+    fn merge(&mut self, path: &Path, intended_override: bool) -> Result<()> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let obj: Map<String, Value> = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        let rel = path
+            .strip_prefix(self.clone_dir)
+            .unwrap_or(path)
+            .display()
+            .to_string();
+        for (key, entry) in obj {
+            if self.validate {
+                let warnings = validate_mcp_server(&entry).map_err(|problem| {
+                    anyhow::anyhow!(
+                        "Invalid MCP server '{key}' in {rel}: {problem} (use --no-validate to skip this check)"
+                    )
+                })?;
+                self.warnings.extend(
+                    warnings
+                        .into_iter()
+                        .map(|w| format!("MCP server '{key}' in {rel}: {w}")),
+                );
+            }
+            if let Some(previous) = self.servers.get(&key)
+                && *previous != entry
+            {
+                self.shadowed.push(ServerShadow {
+                    key: key.clone(),
+                    winner: rel.clone(),
+                    shadowed: self.key_sources[&key].clone(),
+                    intended: intended_override,
+                });
+            }
+            self.key_sources.insert(key.clone(), rel.clone());
+            self.servers.insert(key, entry);
+        }
+        Ok(())
+    }
+}
+
+const STDIO_SERVER_FIELDS: &[&str] = &["type", "command", "args", "env"];
+const REMOTE_SERVER_FIELDS: &[&str] = &["type", "url", "headers"];
+
+/// Check one `mcpServers` entry against the `.mcp.json` shapes Claude Code
+/// accepts: stdio (`command`, `args`, `env`) or `sse` / `http` (`url`,
+/// `headers`). Only a missing or mistyped `command` / `url` (or an unknown
+/// `type`) is an error; unknown fields and malformed optional ones come back
+/// as warnings, since Claude Code grows new fields faster than clemp does. A
+/// `url` with no `type` is checked as `http`, with a warning.
+pub fn validate_mcp_server(entry: &Value) -> std::result::Result<Vec<String>, String> {
+    let obj = entry.as_object().ok_or("entry is not an object")?;
+    let mut warnings = Vec::new();
+    let kind = match obj.get("type") {
+        None if obj.contains_key("url") && !obj.contains_key("command") => {
+            warnings.push("has `url` but no `type`; treating it as \"http\"".to_string());
+            "http"
+        }
+        None => "stdio",
+        Some(Value::String(t)) if matches!(t.as_str(), "stdio" | "sse" | "http") => t.as_str(),
+        Some(other) => {
+            return Err(format!(
+                "unknown `type` {other} (expected \"stdio\", \"sse\" or \"http\")"
+            ));
+        }
+    };
+    let (fields, required) = if kind == "stdio" {
+        (STDIO_SERVER_FIELDS, "command")
+    } else {
+        (REMOTE_SERVER_FIELDS, "url")
+    };
+
+    match obj.get(required) {
+        Some(Value::String(v)) if !v.is_empty() => {}
+        Some(_) => return Err(format!("`{required}` must be a non-empty string")),
+        None => return Err(format!("{kind} server is missing `{required}`")),
+    }
+    for key in obj.keys() {
+        if !fields.contains(&key.as_str()) {
+            warnings.push(format!(
+                "unknown field `{key}` for a {kind} server (expected one of: {})",
+                fields.join(", ")
+            ));
+        }
+    }
+    if let Some(args) = obj.get("args")
+        && !args
+            .as_array()
+            .is_some_and(|a| a.iter().all(Value::is_string))
+    {
+        warnings.push("`args` should be an array of strings".into());
+    }
+    for map_field in ["env", "headers"] {
+        if let Some(map) = obj.get(map_field)
+            && !map
+                .as_object()
+                .is_some_and(|m| m.values().all(Value::is_string))
+        {
+            warnings.push(format!(
+                "`{map_field}` should be an object of string values"
+            ));
+        }
+    }
+    Ok(warnings)
 }
 
-/// Stems of `<ext>` files directly in `dir`, sorted lexicographically, filtered
-/// through `exclude`.
+/// Stems of `<ext>` (or `<ext>.jinja`) files directly in `dir`, sorted
+/// lexicographically and deduped, filtered through `exclude`.
 fn layer_stems(dir: &Path, ext: &str, exclude: &HashSet<String>) -> Result<Vec<String>> {
     if !dir.is_dir() {
         return Ok(Vec::new());
     }
     let mut names: Vec<String> = fs::read_dir(dir)?
         .filter_map(|e| e.ok())
-        .filter(|e| {
-            let p = e.path();
-            p.is_file() && p.extension().and_then(|e| e.to_str()) == Some(ext)
-        })
-        .filter_map(|e| e.path().file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .filter(|e| e.path().is_file())
+        .filter_map(|e| contributor_stem(&e.file_name().to_string_lossy(), ext))
         .filter(|s| !exclude.contains(s))
         .collect();
     names.sort();
+    names.dedup();
     Ok(names)
 }
 
+/// Stem of a contributor file name: `x.json` and `x.json.jinja` both give `x`.
+fn contributor_stem(file_name: &str, ext: &str) -> Option<String> {
+    let base = file_name.strip_suffix(".jinja").unwrap_or(file_name);
+    base.strip_suffix(&format!(".{ext}"))
+        .filter(|stem| !stem.is_empty())
+        .map(str::to_string)
+}
+
 /// Assemble `.mcp.json` from `mcp/default/`, `mcp/<lang>/`, and the explicit
 /// (`user_named` ∪ `sticky_stems`) layer, filtering every layer through
 /// `excluded_stems`. Returns the merged JSON, its top-level keys (server names
 /// for `enabledMcpjsonServers`), and the stems eligible for persistence into
-/// `lockfile.resolved.mcp`.
+/// `lockfile.resolved.mcp`. With `validate`, every server entry must match a
+/// known `.mcp.json` shape (see `validate_mcp_server`).
 pub fn assemble_mcp_json(
     languages: &[String],
     user_named: &[String],
     sticky_stems: &[String],
     excluded_stems: &HashSet<String>,
+    validate: bool,
     clone_dir: &Path,
 ) -> Result<AssemblyResult> {
     let mcp_dir = clone_dir.join("mcp");
@@ -838,10 +1820,20 @@ pub fn assemble_mcp_json(
             rendered: serde_json::json!({"mcpServers": {}}),
             rendered_keys: Vec::new(),
             snapshottable_stems: Vec::new(),
+            key_sources: BTreeMap::new(),
+            shadowed: Vec::new(),
+            warnings: Vec::new(),
         });
     }
 
-    let mut servers: Map<String, Value> = Map::new();
+    let mut acc = ServerAccumulator {
+        servers: Map::new(),
+        key_sources: BTreeMap::new(),
+        shadowed: Vec::new(),
+        warnings: Vec::new(),
+        validate,
+        clone_dir,
+    };
     let mut snapshottable: Vec<String> = Vec::new();
     let mut snapshot_seen: HashSet<String> = HashSet::new();
     let push_snap = |stem: &str, out: &mut Vec<String>, seen: &mut HashSet<String>| {
@@ -854,7 +1846,7 @@ pub fn assemble_mcp_json(
     let default_dir = mcp_dir.join("default");
     let mut default_stems_present: HashSet<String> = HashSet::new();
     for stem in layer_stems(&default_dir, "json", excluded_stems)? {
-        merge_json_file_into(&default_dir.join(format!("{stem}.json")), &mut servers)?;
+        acc.merge(&default_dir.join(format!("{stem}.json")), false)?;
         default_stems_present.insert(stem.clone());
         push_snap(&stem, &mut snapshottable, &mut snapshot_seen);
     }
@@ -864,7 +1856,7 @@ pub fn assemble_mcp_json(
     for lang in languages {
         let lang_dir = mcp_dir.join(lang);
         for stem in layer_stems(&lang_dir, "json", excluded_stems)? {
-            merge_json_file_into(&lang_dir.join(format!("{stem}.json")), &mut servers)?;
+            acc.merge(&lang_dir.join(format!("{stem}.json")), false)?;
             lang_stems_present.insert(stem);
             // Intentionally NOT pushed into snapshottable — language stems stay
             // dynamic so dropping a language transitively drops them.
@@ -890,7 +1882,7 @@ pub fn assemble_mcp_json(
         if is_user_named && root_path.is_file() {
             // Root-override path: user-typed --mcp whose root file exists.
             // Merge unconditionally (may overwrite default/lang keys).
-            merge_json_file_into(&root_path, &mut servers)?;
+            acc.merge(&root_path, true)?;
             push_snap(stem, &mut snapshottable, &mut snapshot_seen);
         } else if default_stems_present.contains(stem) || lang_stems_present.contains(stem) {
             // Already-satisfied: contributor was merged by layer 1 or 2.
@@ -906,7 +1898,7 @@ pub fn assemble_mcp_json(
             // whose contributor was relocated upstream still resolve.
             match resolve_contributor("mcp", "json", &MCP_LAYERS, stem, languages, clone_dir) {
                 Some(path) => {
-                    merge_json_file_into(&path, &mut servers)?;
+                    acc.merge(&path, false)?;
                     push_snap(stem, &mut snapshottable, &mut snapshot_seen);
                 }
                 None => {
@@ -928,18 +1920,389 @@ pub fn assemble_mcp_json(
         }
     }
 
-    let rendered_keys: Vec<String> = servers.keys().cloned().collect();
-    let rendered = serde_json::json!({ "mcpServers": servers });
+    let rendered_keys: Vec<String> = acc.servers.keys().cloned().collect();
+    let rendered = serde_json::json!({ "mcpServers": acc.servers });
 
     Ok(AssemblyResult {
         rendered,
         rendered_keys,
         snapshottable_stems: snapshottable,
+        key_sources: acc.key_sources,
+        shadowed: acc.shadowed,
+        warnings: acc.warnings,
     })
 }
 
-// ── Clarg integration ────────────────────────────────────────────────
-
+// ── MCP placeholders ─────────────────────────────────────────────────
+
+/// Project-root file clemp reads placeholder values from, and optionally
+/// writes prompted values to.
+pub const ENV_FILE_NAME: &str = ".env";
+
+/// Visit every `${NAME}` placeholder in `s`, where `NAME` is a shell-style
+/// identifier. Unterminated or malformed `${` sequences are left alone.
+fn for_each_placeholder(s: &str, mut visit: impl FnMut(std::ops::Range<usize>, &str)) {
+    let mut start = 0;
+    while let Some(offset) = s[start..].find("${") {
+        let open = start + offset;
+        let body = &s[open + 2..];
+        let Some(len) = body.find('}') else { break };
+        let name = &body[..len];
+        let valid = name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if valid {
+            visit(open..open + 2 + len + 1, name);
+            start = open + 2 + len + 1;
+        } else {
+            start = open + 2;
+        }
+    }
+}
+
+fn map_strings(value: &Value, f: &impl Fn(&str) -> String) -> Value {
+    match value {
+        Value::String(s) => Value::String(f(s)),
+        Value::Array(items) => Value::Array(items.iter().map(|v| map_strings(v, f)).collect()),
+        Value::Object(obj) => Value::Object(
+            obj.iter()
+                .map(|(k, v)| (k.clone(), map_strings(v, f)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Names of the `${NAME}` placeholders used in string values of an assembled
+/// `.mcp.json`, sorted and deduped.
+pub fn mcp_placeholders(value: &Value) -> Vec<String> {
+    fn walk(value: &Value, names: &mut BTreeMap<String, ()>) {
+        match value {
+            Value::String(s) => for_each_placeholder(s, |_, name| {
+                names.insert(name.to_string(), ());
+            }),
+            Value::Array(items) => items.iter().for_each(|v| walk(v, names)),
+            Value::Object(obj) => obj.values().for_each(|v| walk(v, names)),
+            _ => {}
+        }
+    }
+    let mut names = BTreeMap::new();
+    walk(value, &mut names);
+    names.into_keys().collect()
+}
+
+fn substitute_str(s: &str, values: &BTreeMap<String, String>) -> String {
+    let mut out = String::new();
+    let mut last = 0;
+    for_each_placeholder(s, |range, name| {
+        if let Some(v) = values.get(name) {
+            out.push_str(&s[last..range.start]);
+            out.push_str(v);
+            last = range.end;
+        }
+    });
+    out.push_str(&s[last..]);
+    out
+}
+
+/// Replace every placeholder that has a value; unknown ones stay verbatim.
+pub fn substitute_placeholders(value: &Value, values: &BTreeMap<String, String>) -> Value {
+    map_strings(value, &|s| substitute_str(s, values))
+}
+
+/// Inverse of `substitute_placeholders`, used to hash a written `.mcp.json`
+/// in its pre-substitution form. Walks `value` alongside `template` (what
+/// clemp rendered before substitution) and restores a string only where the
+/// template has one that substitutes to it, so the same text elsewhere — a
+/// server the user added, say — is left alone.
+pub fn unsubstitute_placeholders(
+    value: &Value,
+    template: &Value,
+    values: &BTreeMap<String, String>,
+) -> Value {
+    match (value, template) {
+        (Value::String(s), Value::String(t)) if substitute_str(t, values) == *s => {
+            Value::String(t.clone())
+        }
+        (Value::Array(items), Value::Array(template_items)) => Value::Array(
+            items
+                .iter()
+                .enumerate()
+                .map(|(i, v)| match template_items.get(i) {
+                    Some(t) => unsubstitute_placeholders(v, t, values),
+                    None => v.clone(),
+                })
+                .collect(),
+        ),
+        (Value::Object(obj), Value::Object(template_obj)) => Value::Object(
+            obj.iter()
+                .map(|(k, v)| {
+                    let v = match template_obj.get(k) {
+                        Some(t) => unsubstitute_placeholders(v, t, values),
+                        None => v.clone(),
+                    };
+                    (k.clone(), v)
+                })
+                .collect(),
+        ),
+        _ => value.clone(),
+    }
+}
+
+/// A `.mcp.json` document holding `servers`, the shape `unsubstitute_placeholders`
+/// expects as its template.
+fn mcp_json_template(servers: Option<&Map<String, Value>>) -> Value {
+    let mut doc = Map::new();
+    doc.insert(
+        "mcpServers".into(),
+        Value::Object(servers.cloned().unwrap_or_default()),
+    );
+    Value::Object(doc)
+}
+
+/// Hash a written `.mcp.json` as it was before placeholder substitution, so
+/// secrets never make the file look modified. `template` is the pre-substitution
+/// document clemp rendered there. Falls back to the raw bytes when there is
+/// nothing to undo or the file no longer parses.
+pub fn hash_mcp_json(
+    path: &Path,
+    template: &Value,
+    values: &BTreeMap<String, String>,
+) -> Result<String> {
+    if values.is_empty() {
+        return hash_file(path);
+    }
+    let content = fs::read_to_string(path)?;
+    match serde_json::from_str::<Value>(&content) {
+        Ok(parsed) => Ok(hash_bytes(
+            serde_json::to_string_pretty(&unsubstitute_placeholders(&parsed, template, values))?
+                .as_bytes(),
+        )),
+        Err(_) => hash_file(path),
+    }
+}
+
+/// Parse `KEY=VALUE` lines from a dotenv file. Blank lines, `#` comments and
+/// an `export ` prefix are allowed. Single-quoted values are taken literally;
+/// double-quoted ones have the escapes `save_env_file` writes undone.
+pub fn read_env_file(path: &Path) -> Result<BTreeMap<String, String>> {
+    let mut values = BTreeMap::new();
+    if !path.is_file() {
+        return Ok(values);
+    }
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        let value = if let Some(quoted) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"'))
+        {
+            unescape_env_value(quoted)
+        } else if let Some(quoted) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+            quoted.to_string()
+        } else {
+            value.to_string()
+        };
+        values.insert(key.trim().to_string(), value);
+    }
+    Ok(values)
+}
+
+/// `value` as written to `.env`: bare when it reads back unchanged, otherwise
+/// double-quoted with `\`, `"`, newlines and tabs escaped.
+fn quote_env_value(value: &str) -> String {
+    let bare = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-.,:/@%+=~".contains(c));
+    if bare {
+        return value.to_string();
+    }
+    let mut quoted = String::from('"');
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Undo `quote_env_value`'s escapes inside a double-quoted `.env` value.
+/// Unknown escapes are kept as written.
+fn unescape_env_value(quoted: &str) -> String {
+    let mut value = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => value.push('\n'),
+            Some('r') => value.push('\r'),
+            Some('t') => value.push('\t'),
+            Some(c @ ('\\' | '"')) => value.push(c),
+            Some(c) => {
+                value.push('\\');
+                value.push(c);
+            }
+            None => value.push('\\'),
+        }
+    }
+    value
+}
+
+/// Resolve placeholder values from the environment, then `<project_dir>/.env`,
+/// then an interactive prompt. Returns the values and the names that had to
+/// be prompted for.
+pub fn resolve_placeholders(
+    names: &[String],
+    project_dir: &Path,
+) -> Result<(BTreeMap<String, String>, Vec<String>)> {
+    let env_file = read_env_file(&project_dir.join(ENV_FILE_NAME))?;
+    let mut values = BTreeMap::new();
+    let mut prompted = Vec::new();
+    for name in names {
+        let value = match env::var(name).ok().filter(|v| !v.is_empty()) {
+            Some(v) => v,
+            None => match env_file.get(name) {
+                Some(v) => v.clone(),
+                None => {
+                    print!("Value for ${{{name}}} (used in .mcp.json): ");
+                    io::stdout().flush()?;
+                    let mut input = String::new();
+                    io::stdin().read_line(&mut input)?;
+                    let input = input.trim().to_string();
+                    if input.is_empty() {
+                        bail!(
+                            "MCP placeholder ${{{name}}} has no value. Export {name} or add it to {ENV_FILE_NAME}"
+                        );
+                    }
+                    prompted.push(name.clone());
+                    input
+                }
+            },
+        };
+        values.insert(name.clone(), value);
+    }
+    Ok((values, prompted))
+}
+
+/// `${NAME}` placeholder values for one invocation. Each name is resolved
+/// (see `resolve_placeholders`) the first time a render needs it, so the root
+/// and every workspace package share one prompt per name. Values are read
+/// from, and offered to be saved to, the `.env` in `project_dir`.
+#[derive(Debug)]
+pub struct Placeholders {
+    project_dir: PathBuf,
+    values: RefCell<BTreeMap<String, String>>,
+    prompted: RefCell<Vec<String>>,
+}
+
+impl Default for Placeholders {
+    fn default() -> Self {
+        Self::new(Path::new("."))
+    }
+}
+
+impl Placeholders {
+    pub fn new(project_dir: &Path) -> Self {
+        Self {
+            project_dir: project_dir.to_path_buf(),
+            values: RefCell::default(),
+            prompted: RefCell::default(),
+        }
+    }
+
+    /// Values for `names`, resolving the ones no earlier render asked for.
+    pub fn resolve(&self, names: &[String]) -> Result<BTreeMap<String, String>> {
+        let missing: Vec<String> = names
+            .iter()
+            .filter(|name| !self.values.borrow().contains_key(*name))
+            .cloned()
+            .collect();
+        if !missing.is_empty() {
+            let (values, prompted) = resolve_placeholders(&missing, &self.project_dir)?;
+            self.values.borrow_mut().extend(values);
+            self.prompted.borrow_mut().extend(prompted);
+        }
+        let values = self.values.borrow();
+        Ok(names
+            .iter()
+            .map(|name| (name.clone(), values[name].clone()))
+            .collect())
+    }
+
+    /// Every value resolved so far.
+    pub fn values(&self) -> BTreeMap<String, String> {
+        self.values.borrow().clone()
+    }
+
+    /// Offer to save the prompted values to `<project_dir>/.env`. Called once
+    /// the invocation's files are written, never from a staging render.
+    pub fn offer_to_save(&self) -> Result<()> {
+        let prompted = self.prompted.borrow();
+        if !prompted.is_empty()
+            && confirm(&format!(
+                "Save {} to a gitignored {ENV_FILE_NAME}?",
+                prompted.join(", ")
+            ))?
+        {
+            save_env_file(&self.project_dir, &prompted, &self.values.borrow())?;
+        }
+        Ok(())
+    }
+}
+
+/// Append `names` from `values` to `<project_dir>/.env` (quoted where needed,
+/// see `quote_env_value`) and make sure the file is gitignored.
+pub fn save_env_file(
+    project_dir: &Path,
+    names: &[String],
+    values: &BTreeMap<String, String>,
+) -> Result<()> {
+    let path = project_dir.join(ENV_FILE_NAME);
+    let mut content = fs::read_to_string(&path).unwrap_or_default();
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    for name in names {
+        content.push_str(&format!("{name}={}\n", quote_env_value(&values[name])));
+    }
+    fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+
+    let gitignore = project_dir.join(".gitignore");
+    let mut existing = fs::read_to_string(&gitignore).unwrap_or_default();
+    let ignored = existing
+        .lines()
+        .any(|l| matches!(l.trim(), ".env" | "/.env"));
+    if !ignored {
+        if !existing.is_empty() && !existing.ends_with('\n') {
+            existing.push('\n');
+        }
+        existing.push_str(".env\n");
+        fs::write(&gitignore, existing)?;
+    }
+    Ok(())
+}
+
+// ── Clarg integration ────────────────────────────────────────────────
+
 /// Copy a clarg YAML config from the template and generate a PreToolUse hook entry.
 pub fn setup_clarg(name: &str, clone_dir: &Path) -> Result<Value> {
     let clarg_dir = clone_dir.join("clarg");
@@ -952,7 +2315,7 @@ pub fn setup_clarg(name: &str, clone_dir: &Path) -> Result<Value> {
                 .filter(|e| {
                     e.path()
                         .extension()
-                        .map_or(false, |ext| ext == "yaml" || ext == "yml")
+                        .is_some_and(|ext| ext == "yaml" || ext == "yml")
                 })
                 .map(|e| e.path().file_stem().unwrap().to_string_lossy().to_string())
                 .collect()
@@ -1021,12 +2384,15 @@ fn merge_hook_file(path: &Path, dest: &mut Map<String, Value>) -> Result<()> {
     Ok(())
 }
 
-/// Assemble the `hooks` block that feeds `.claude/settings.local.json`. Shape
-/// parallels `assemble_mcp_json` but with `HOOKS_LAYERS` (no language dirs in
-/// today's template). Arrays inside hook-type buckets are concatenated across
+/// Assemble the `hooks` block that feeds `.claude/settings.local.json` from
+/// `hooks/default/`, `hooks/<lang>/`, and the explicit layer. Shape parallels
+/// `assemble_mcp_json`. Arrays inside hook-type buckets are concatenated across
 /// contributor files; `rendered_keys` is the merged map's top-level hook-type
-/// names (not contributor stems).
+/// names (not contributor stems). A `hooks/<lang>/` file replaces the
+/// `hooks/default/` file of the same stem, and a user-named root file replaces
+/// both, so one stem never contributes its entries twice.
 pub fn assemble_hooks_json(
+    languages: &[String],
     user_named: &[String],
     sticky_stems: &[String],
     excluded_stems: &HashSet<String>,
@@ -1036,21 +2402,38 @@ pub fn assemble_hooks_json(
     let mut merged: Map<String, Value> = Map::new();
     let mut snapshottable: Vec<String> = Vec::new();
     let mut snapshot_seen: HashSet<String> = HashSet::new();
+    // Contributor files in merge order, by stem. Several languages may each
+    // contribute a file with the same stem; all of them apply.
+    let mut sources: Vec<(String, PathBuf)> = Vec::new();
 
     // Layer 1: default/
     let default_dir = hooks_dir.join("default");
     let mut default_stems_present: HashSet<String> = HashSet::new();
     for stem in layer_stems(&default_dir, "json", excluded_stems)? {
-        merge_hook_file(&default_dir.join(format!("{stem}.json")), &mut merged)?;
+        sources.push((stem.clone(), default_dir.join(format!("{stem}.json"))));
         default_stems_present.insert(stem.clone());
         if snapshot_seen.insert(stem.clone()) {
             snapshottable.push(stem);
         }
     }
 
-    // Layer 2: languages — disabled for hooks per HOOKS_LAYERS. Intentionally
-    // omitted to avoid silently picking up `hooks/<lang>/` dirs that no
-    // template uses today.
+    // Layer 2: per-language
+    let mut lang_stems_present: HashSet<String> = HashSet::new();
+    for lang in languages {
+        let lang_dir = hooks_dir.join(lang);
+        for stem in layer_stems(&lang_dir, "json", excluded_stems)? {
+            let path = lang_dir.join(format!("{stem}.json"));
+            match sources
+                .iter_mut()
+                .find(|(s, p)| *s == stem && p.starts_with(&default_dir))
+            {
+                Some(default) => default.1 = path,
+                None => sources.push((stem.clone(), path)),
+            }
+            lang_stems_present.insert(stem);
+            // Not snapshotted: dropping the language drops its hooks.
+        }
+    }
 
     // Layer 3: explicit/sticky
     let mut explicit: Vec<String> = Vec::new();
@@ -1069,25 +2452,31 @@ pub fn assemble_hooks_json(
         let root_path = hooks_dir.join(format!("{stem}.json"));
 
         if is_user_named && root_path.is_file() {
-            merge_hook_file(&root_path, &mut merged)?;
+            sources.retain(|(s, _)| s != stem);
+            sources.push((stem.clone(), root_path));
             if snapshot_seen.insert(stem.clone()) {
                 snapshottable.push(stem.clone());
             }
-        } else if default_stems_present.contains(stem) {
+        } else if default_stems_present.contains(stem) || lang_stems_present.contains(stem) {
             if is_user_named && snapshot_seen.insert(stem.clone()) {
                 snapshottable.push(stem.clone());
             }
         } else {
-            match resolve_contributor("hooks", "json", &HOOKS_LAYERS, stem, &[], clone_dir) {
+            match resolve_contributor("hooks", "json", &HOOKS_LAYERS, stem, languages, clone_dir) {
                 Some(path) => {
-                    merge_hook_file(&path, &mut merged)?;
+                    sources.push((stem.clone(), path));
                     if snapshot_seen.insert(stem.clone()) {
                         snapshottable.push(stem.clone());
                     }
                 }
                 None => {
-                    let available =
-                        available_contributor_stems("hooks", "json", &HOOKS_LAYERS, &[], clone_dir);
+                    let available = available_contributor_stems(
+                        "hooks",
+                        "json",
+                        &HOOKS_LAYERS,
+                        languages,
+                        clone_dir,
+                    );
                     bail!(
                         "Hook '{}' not found in {}. Available: {:?}",
                         stem,
@@ -1099,23 +2488,307 @@ pub fn assemble_hooks_json(
         }
     }
 
+    for (_, path) in &sources {
+        merge_hook_file(path, &mut merged)?;
+    }
+    let rendered_keys: Vec<String> = merged.keys().cloned().collect();
+    Ok(AssemblyResult {
+        rendered: Value::Object(merged),
+        rendered_keys,
+        snapshottable_stems: snapshottable,
+        key_sources: BTreeMap::new(),
+        shadowed: Vec::new(),
+        warnings: Vec::new(),
+    })
+}
+
+fn merge_permission_file(path: &Path, dest: &mut Map<String, Value>) -> Result<()> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let json: Value = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    let obj = json
+        .as_object()
+        .with_context(|| format!("{} is not an object", path.display()))?;
+
+    for (list, rules) in obj {
+        let rules = rules
+            .as_array()
+            .filter(|rules| rules.iter().all(Value::is_string))
+            .with_context(|| {
+                format!(
+                    "'{}' in {} is not an array of strings",
+                    list,
+                    path.display()
+                )
+            })?;
+        let existing = dest
+            .entry(list.clone())
+            .or_insert_with(|| Value::Array(vec![]))
+            .as_array_mut()
+            .unwrap();
+        for rule in rules {
+            if !existing.contains(rule) {
+                existing.push(rule.clone());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Assemble the `permissions` lists (`allow` / `deny` / `ask`, …) that feed
+/// `.claude/settings.local.json` from `permissions/default/`,
+/// `permissions/<lang>/`, and the explicit layer. Shape parallels
+/// `assemble_hooks_json`; each list is unioned across contributor files and
+/// `rendered_keys` is the merged map's list names.
+pub fn assemble_permissions_json(
+    languages: &[String],
+    user_named: &[String],
+    sticky_stems: &[String],
+    excluded_stems: &HashSet<String>,
+    clone_dir: &Path,
+) -> Result<AssemblyResult> {
+    let permissions_dir = clone_dir.join("permissions");
+    let mut merged: Map<String, Value> = Map::new();
+    let mut snapshottable: Vec<String> = Vec::new();
+    let mut snapshot_seen: HashSet<String> = HashSet::new();
+
+    // Layer 1: default/
+    let default_dir = permissions_dir.join("default");
+    let mut default_stems_present: HashSet<String> = HashSet::new();
+    for stem in layer_stems(&default_dir, "json", excluded_stems)? {
+        merge_permission_file(&default_dir.join(format!("{stem}.json")), &mut merged)?;
+        default_stems_present.insert(stem.clone());
+        if snapshot_seen.insert(stem.clone()) {
+            snapshottable.push(stem);
+        }
+    }
+
+    // Layer 2: per-language
+    let mut lang_stems_present: HashSet<String> = HashSet::new();
+    for lang in languages {
+        let lang_dir = permissions_dir.join(lang);
+        for stem in layer_stems(&lang_dir, "json", excluded_stems)? {
+            merge_permission_file(&lang_dir.join(format!("{stem}.json")), &mut merged)?;
+            lang_stems_present.insert(stem);
+            // Not snapshotted: dropping the language drops its permissions.
+        }
+    }
+
+    // Layer 3: explicit/sticky
+    let mut explicit: Vec<String> = Vec::new();
+    let mut explicit_seen: HashSet<String> = HashSet::new();
+    for stem in user_named.iter().chain(sticky_stems.iter()) {
+        if excluded_stems.contains(stem) {
+            continue;
+        }
+        if explicit_seen.insert(stem.clone()) {
+            explicit.push(stem.clone());
+        }
+    }
+    let user_named_set: HashSet<&str> = user_named.iter().map(|s| s.as_str()).collect();
+    for stem in &explicit {
+        let is_user_named = user_named_set.contains(stem.as_str());
+        let root_path = permissions_dir.join(format!("{stem}.json"));
+
+        if is_user_named && root_path.is_file() {
+            merge_permission_file(&root_path, &mut merged)?;
+            if snapshot_seen.insert(stem.clone()) {
+                snapshottable.push(stem.clone());
+            }
+        } else if default_stems_present.contains(stem) || lang_stems_present.contains(stem) {
+            if is_user_named && snapshot_seen.insert(stem.clone()) {
+                snapshottable.push(stem.clone());
+            }
+        } else {
+            match resolve_contributor(
+                "permissions",
+                "json",
+                &PERMISSIONS_LAYERS,
+                stem,
+                languages,
+                clone_dir,
+            ) {
+                Some(path) => {
+                    merge_permission_file(&path, &mut merged)?;
+                    if snapshot_seen.insert(stem.clone()) {
+                        snapshottable.push(stem.clone());
+                    }
+                }
+                None => {
+                    let available = available_contributor_stems(
+                        "permissions",
+                        "json",
+                        &PERMISSIONS_LAYERS,
+                        languages,
+                        clone_dir,
+                    );
+                    bail!(
+                        "Permission '{}' not found in {}. Available: {:?}",
+                        stem,
+                        permissions_dir.display(),
+                        available
+                    );
+                }
+            }
+        }
+    }
+
     let rendered_keys: Vec<String> = merged.keys().cloned().collect();
     Ok(AssemblyResult {
         rendered: Value::Object(merged),
         rendered_keys,
         snapshottable_stems: snapshottable,
+        key_sources: BTreeMap::new(),
+        shadowed: Vec::new(),
+        warnings: Vec::new(),
     })
 }
 
-/// Build `.claude/settings.local.json` from the pre-assembled hooks block,
-/// clarg PreToolUse entries, and the MCP `rendered_keys` list that populates
-/// `enabledMcpjsonServers`.
+/// A scalar (or differently-typed) settings value supplied by two files. The
+/// later file in `base → default → lang` order wins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettingsConflict {
+    /// Dotted path of the key, e.g. `env.RUST_LOG`.
+    pub key: String,
+    /// Template-relative path of the file whose value was kept.
+    pub winner: String,
+    /// Template-relative path of the file whose value was replaced.
+    pub overridden: String,
+}
+
+/// Deep-merge `settings/default/*.json` and `settings/<lang>/*.json` fragments
+/// into `settings`, in `default → lang` order with files sorted by name.
+/// Objects merge key by key and arrays are unioned; any other clash is
+/// resolved in favour of the later file and reported.
+pub fn merge_settings_fragments(
+    settings: &mut Value,
+    languages: &[String],
+    clone_dir: &Path,
+) -> Result<Vec<SettingsConflict>> {
+    let settings_dir = clone_dir.join("settings");
+    let mut origins: BTreeMap<String, String> = BTreeMap::new();
+    origins.insert(String::new(), "settings.local.json".into());
+    let mut conflicts = Vec::new();
+
+    let layers = std::iter::once("default").chain(languages.iter().map(|l| l.as_str()));
+    for layer in layers {
+        for stem in layer_stems(&settings_dir.join(layer), "json", &HashSet::new())? {
+            let label = format!("settings/{layer}/{stem}.json");
+            let path = clone_dir.join(&label);
+            let fragment: Value = serde_json::from_str(&fs::read_to_string(&path)?)
+                .with_context(|| format!("Failed to parse {}", path.display()))?;
+            let Value::Object(fragment) = fragment else {
+                bail!("{} is not an object", path.display());
+            };
+            let dest = settings
+                .as_object_mut()
+                .context("settings.local.json is not an object")?;
+            deep_merge_settings(dest, fragment, "", &label, &mut origins, &mut conflicts);
+        }
+    }
+    Ok(conflicts)
+}
+
+fn deep_merge_settings(
+    dest: &mut Map<String, Value>,
+    src: Map<String, Value>,
+    prefix: &str,
+    label: &str,
+    origins: &mut BTreeMap<String, String>,
+    conflicts: &mut Vec<SettingsConflict>,
+) {
+    for (key, value) in src {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match (dest.get_mut(&key), value) {
+            (None, value) => {
+                dest.insert(key, value);
+                origins.insert(path, label.to_string());
+            }
+            (Some(Value::Object(existing)), Value::Object(value)) => {
+                deep_merge_settings(existing, value, &path, label, origins, conflicts);
+            }
+            (Some(Value::Array(existing)), Value::Array(value)) => {
+                for item in value {
+                    if !existing.contains(&item) {
+                        existing.push(item);
+                    }
+                }
+            }
+            (Some(existing), value) => {
+                if *existing == value {
+                    continue;
+                }
+                // Nearest ancestor that some file set wins the attribution.
+                let mut owner = path.as_str();
+                let overridden = loop {
+                    if let Some(file) = origins.get(owner) {
+                        break file.clone();
+                    }
+                    owner = owner.rsplit_once('.').map_or("", |(parent, _)| parent);
+                };
+                conflicts.push(SettingsConflict {
+                    key: path.clone(),
+                    winner: label.to_string(),
+                    overridden,
+                });
+                *existing = value;
+                origins.insert(path, label.to_string());
+            }
+        }
+    }
+}
+
+/// Settings parts written to `SHARED_SETTINGS_PATH`: the template's
+/// `shared-settings`, plus `shared`, minus `local` (a command's
+/// `--shared-settings` / `--local-settings`). Sorted.
+pub fn resolve_shared_settings(
+    shared: &[SettingsPart],
+    local: &[SettingsPart],
+    clone_dir: &Path,
+) -> Result<Vec<SettingsPart>> {
+    let manifest = TemplateManifest::load(clone_dir)?;
+    let parts: BTreeSet<SettingsPart> = manifest
+        .shared_settings
+        .iter()
+        .flatten()
+        .chain(shared)
+        .filter(|p| !local.contains(p))
+        .copied()
+        .collect();
+    Ok(parts.into_iter().collect())
+}
+
+/// The settings file `part` is written to, relative to the project root.
+pub fn settings_path(part: SettingsPart, shared_parts: &[SettingsPart]) -> &'static str {
+    if shared_parts.contains(&part) {
+        SHARED_SETTINGS_PATH
+    } else {
+        SETTINGS_PATH
+    }
+}
+
+/// Build `.claude/settings.local.json` from the base settings plus language
+/// fragments, the pre-assembled hooks block, the assembled permission lists
+/// (unioned into `permissions`), clarg PreToolUse entries, and the MCP
+/// `rendered_keys` list that populates `enabledMcpjsonServers`. Parts listed
+/// in `shared_parts` go to `.claude/settings.json` instead, on top of the one
+/// the template ships (if any); it is only rewritten when at least one part is
+/// shared. Returns the settings as written, keyed by path relative to the
+/// project root.
 pub fn build_settings(
     hooks_result: &AssemblyResult,
+    permissions_result: &AssemblyResult,
     clarg_entries: &[Value],
     active_mcp_names: &[String],
+    languages: &[String],
+    shared_parts: &[SettingsPart],
     clone_dir: &Path,
-) -> Result<()> {
+) -> Result<BTreeMap<String, Value>> {
     let base_path = clone_dir.join("settings.local.json");
 
     let mut settings: Value = if base_path.exists() {
@@ -1125,49 +2798,216 @@ pub fn build_settings(
         serde_json::json!({})
     };
 
-    let settings_obj = settings
-        .as_object_mut()
-        .context("settings.local.json is not an object")?;
+    if !settings.is_object() {
+        bail!("settings.local.json is not an object");
+    }
+    for conflict in merge_settings_fragments(&mut settings, languages, clone_dir)? {
+        eprintln!(
+            "Warning: settings key '{}' from {} overrides {}",
+            conflict.key, conflict.winner, conflict.overridden
+        );
+    }
+    // A `.claude/settings.json` the template ships is the shared file's base.
+    let shared_base = clone_dir.join(SHARED_SETTINGS_PATH);
+    let shared = if shared_base.exists() {
+        let content = fs::read_to_string(&shared_base)?;
+        match serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {SHARED_SETTINGS_PATH}"))?
+        {
+            Value::Object(shared) => shared,
+            _ => bail!("{SHARED_SETTINGS_PATH} is not an object"),
+        }
+    } else {
+        Map::new()
+    };
+    // [local, shared], indexed by where each part goes.
+    let mut files = [std::mem::take(settings.as_object_mut().unwrap()), shared];
+    let dest = |part: SettingsPart| usize::from(shared_parts.contains(&part));
 
-    let mut merged_hooks: Map<String, Value> = hooks_result
+    // Permission contributors union into whatever the base settings allow.
+    if let Some(lists) = permissions_result
         .rendered
         .as_object()
-        .cloned()
-        .unwrap_or_default();
-
-    // Merge clarg PreToolUse hook entries on top of the assembled hooks.
-    for entry in clarg_entries {
-        merged_hooks
+        .filter(|l| !l.is_empty())
+    {
+        let file = settings_path(SettingsPart::Permissions, shared_parts);
+        let permissions = files[dest(SettingsPart::Permissions)]
+            .entry("permissions".to_string())
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .with_context(|| format!("'permissions' in {file} is not an object"))?;
+        for (list, rules) in lists {
+            let existing = permissions
+                .entry(list.clone())
+                .or_insert_with(|| Value::Array(vec![]))
+                .as_array_mut()
+                .with_context(|| format!("'permissions.{list}' in {file} is not an array"))?;
+            for rule in rules.as_array().into_iter().flatten() {
+                if !existing.contains(rule) {
+                    existing.push(rule.clone());
+                }
+            }
+        }
+    }
+
+    let hooks = hooks_result
+        .rendered
+        .as_object()
+        .cloned()
+        .unwrap_or_default();
+    files[dest(SettingsPart::Hooks)].insert("hooks".to_string(), Value::Object(hooks));
+
+    // Merge clarg PreToolUse hook entries on top of the assembled hooks, or
+    // into a hooks block of their own when the two are split.
+    for entry in clarg_entries {
+        let file = settings_path(SettingsPart::Clarg, shared_parts);
+        files[dest(SettingsPart::Clarg)]
+            .entry("hooks".to_string())
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .with_context(|| format!("'hooks' in {file} is not an object"))?
             .entry("PreToolUse".to_string())
             .or_insert_with(|| Value::Array(vec![]))
             .as_array_mut()
-            .unwrap()
+            .with_context(|| format!("'hooks.PreToolUse' in {file} is not an array"))?
             .push(entry.clone());
     }
 
-    settings_obj.insert("hooks".to_string(), Value::Object(merged_hooks));
-
     let mcp_names: Vec<Value> = active_mcp_names
         .iter()
         .map(|n| Value::String(n.clone()))
         .collect();
-    settings_obj.insert("enabledMcpjsonServers".to_string(), Value::Array(mcp_names));
+    files[dest(SettingsPart::Mcp)]
+        .insert("enabledMcpjsonServers".to_string(), Value::Array(mcp_names));
 
     let claude_dir = clone_dir.join(".claude");
     fs::create_dir_all(&claude_dir)?;
-    fs::write(
-        claude_dir.join("settings.local.json"),
-        serde_json::to_string_pretty(&settings)?,
-    )?;
+    let [local, shared] = files;
+    let mut written = BTreeMap::from([(SETTINGS_PATH.to_string(), Value::Object(local))]);
+    if !shared_parts.is_empty() {
+        written.insert(SHARED_SETTINGS_PATH.to_string(), Value::Object(shared));
+    }
+    for (path, doc) in &written {
+        fs::write(clone_dir.join(path), serde_json::to_string_pretty(doc)?)?;
+    }
 
-    Ok(())
+    Ok(written)
+}
+
+/// Merge the `.claude/settings.json` a project already has at `existing` with
+/// the one staged in `clone_dir`, against an empty base, as `clemp update`
+/// does for a file it never wrote. Returns the merged settings, or the dotted
+/// paths of the keys both set differently.
+fn merge_existing_shared_settings(
+    existing: &Path,
+    clone_dir: &Path,
+) -> Result<std::result::Result<Value, Vec<String>>> {
+    let read = |path: &Path| -> Result<Value> {
+        serde_json::from_str(&fs::read_to_string(path)?)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    };
+    let ours = read(existing)?;
+    if !ours.is_object() {
+        bail!("{} is not an object", existing.display());
+    }
+    let theirs = read(&clone_dir.join(SHARED_SETTINGS_PATH))?;
+    Ok(merge_settings_three_way(
+        &Value::Object(Map::new()),
+        &ours,
+        &theirs,
+    ))
 }
 
 // ── Template rendering ───────────────────────────────────────────────────
 
+fn truthy_dict(names: &[String]) -> Map<String, Value> {
+    names
+        .iter()
+        .map(|n| (n.clone(), Value::Bool(true)))
+        .collect()
+}
+
+/// The `lang` / `tags` / `mcp` truthy dicts shared by every Jinja template
+/// clemp renders, e.g. `{% if lang.rust %}`. `mcp` is keyed by the assembled
+/// server names.
+fn template_context(languages: &[String], tags: &[String], mcp: &[String]) -> Value {
+    serde_json::json!({ "lang": truthy_dict(languages), "tags": truthy_dict(tags), "mcp": truthy_dict(mcp) })
+}
+
+/// Context for `mcp/` templates, which render before the servers are
+/// assembled: `lang` and `tags`, plus `mcp_contributors` keyed by the
+/// contributor stems about to be assembled. There is no `mcp` yet.
+fn mcp_template_context(languages: &[String], tags: &[String], contributors: &[String]) -> Value {
+    serde_json::json!({
+        "lang": truthy_dict(languages),
+        "tags": truthy_dict(tags),
+        "mcp_contributors": truthy_dict(contributors),
+    })
+}
+
+/// Render the `*.json.jinja` files the assemblers will read to their `*.json`
+/// siblings: those directly in `path` and in its `default/` and `conditional`
+/// subdirectories (`path` may also be a single file). Templates for languages
+/// and tags the project doesn't use are left alone. Errors name the
+/// template-relative file.
+pub fn render_json_templates(
+    path: &Path,
+    conditional: &[String],
+    clone_dir: &Path,
+    ctx: &Value,
+) -> Result<()> {
+    let mut templates: Vec<PathBuf> = Vec::new();
+    if path.is_dir() {
+        let layers = std::iter::once(path.to_path_buf()).chain(
+            std::iter::once("default")
+                .chain(conditional.iter().map(|c| c.as_str()))
+                .map(|layer| path.join(layer)),
+        );
+        for dir in layers.filter(|d| d.is_dir()) {
+            for entry in fs::read_dir(&dir)?.filter_map(|e| e.ok()) {
+                if entry.path().is_file()
+                    && entry.file_name().to_string_lossy().ends_with(".json.jinja")
+                {
+                    templates.push(entry.path());
+                }
+            }
+        }
+    } else if path.is_file() {
+        templates.push(path.to_path_buf());
+    }
+    templates.sort();
+
+    let env = Environment::new();
+    for template in templates {
+        let rel = template
+            .strip_prefix(clone_dir)
+            .unwrap_or(&template)
+            .display()
+            .to_string();
+        let target = template.with_extension("");
+        if target.exists() {
+            bail!(
+                "{} and {} both exist in the template; keep only one",
+                rel,
+                rel.trim_end_matches(".jinja")
+            );
+        }
+        let content =
+            fs::read_to_string(&template).with_context(|| format!("Failed to read {rel}"))?;
+        let rendered = env
+            .render_str(&content, ctx)
+            .with_context(|| format!("Failed to render {rel}"))?;
+        serde_json::from_str::<Value>(&rendered)
+            .with_context(|| format!("{rel} did not render to valid JSON"))?;
+        fs::write(&target, rendered)?;
+    }
+    Ok(())
+}
+
 /// Render CLAUDE.md from the template and all its parts.
 pub fn render_claude_md(
     languages: &[String],
+    tags: &[String],
     active_mcp_names: &[String],
     clone_dir: &Path,
 ) -> Result<String> {
@@ -1180,25 +3020,31 @@ pub fn render_claude_md(
     // Build lang dict: {"typescript": true, ...} — truthy if non-empty, dot-accessible
     let lang_dict: BTreeMap<&str, bool> = languages.iter().map(|l| (l.as_str(), true)).collect();
 
+    // Build tags dict: {"react": true, ...}
+    let tags_dict: BTreeMap<&str, bool> = tags.iter().map(|t| (t.as_str(), true)).collect();
+
     // Build mcp dict: {"context7": true, ...}
     let mcp_dict: BTreeMap<&str, bool> = active_mcp_names.iter().map(|m| (m.as_str(), true)).collect();
 
-    // Build lang_rules and mcp_rules
+    // Build lang_rules, tag_rules and mcp_rules
     let lang_rules = build_language_rules(languages, &claude_md_dir)?;
+    let tag_rules = build_tag_rules(tags, &claude_md_dir)?;
     let mcp_rules = build_mcp_rules(active_mcp_names, &claude_md_dir)?;
 
     // Build template context as a dynamic map (supports misc variables with dynamic names)
     let mut ctx = Map::new();
     ctx.insert("lang".into(), serde_json::to_value(&lang_dict)?);
+    ctx.insert("tags".into(), serde_json::to_value(&tags_dict)?);
     ctx.insert("mcp".into(), serde_json::to_value(&mcp_dict)?);
     ctx.insert("lang_rules".into(), Value::String(lang_rules));
+    ctx.insert("tag_rules".into(), Value::String(tag_rules));
     ctx.insert("mcp_rules".into(), Value::String(mcp_rules));
 
     // Render misc files from claude-md/misc/
     let misc_dir = claude_md_dir.join("misc");
     if misc_dir.is_dir() {
         let env = Environment::new();
-        let partial_ctx = serde_json::json!({ "lang": &lang_dict, "mcp": &mcp_dict });
+        let partial_ctx = template_context(languages, tags, active_mcp_names);
 
         let mut entries: Vec<_> = fs::read_dir(&misc_dir)?
             .filter_map(|e| e.ok())
@@ -1246,60 +3092,952 @@ pub fn render_claude_md(
 
 // ── Git / filesystem ─────────────────────────────────────────────────────
 
-/// Clone the template repo to `CLONE_DIR`, removing any stale prior clone.
-/// Returns the HEAD commit SHA of the cloned tree.
-pub fn clone_repo(repo_url: &str) -> Result<String> {
-    let clone_path = Path::new(CLONE_DIR);
-    if clone_path.exists() {
-        eprintln!("Stale '{}' directory found, removing...", CLONE_DIR);
-        fs::remove_dir_all(clone_path)
-            .with_context(|| format!("Failed to remove stale {}", CLONE_DIR))?;
+/// Clear an earlier checkout at `path` so the next fetch into it starts empty.
+fn clear_checkout(path: &Path) -> Result<()> {
+    if path.exists() {
+        fs::remove_dir_all(path).with_context(|| format!("Failed to remove {}", path.display()))?;
     }
+    Ok(())
+}
 
+/// Root of the persistent template cache: `$XDG_CACHE_HOME/clemp`, falling
+/// back to `~/.cache/clemp`.
+pub fn cache_dir() -> Result<PathBuf> {
+    if let Some(xdg) = env::var_os("XDG_CACHE_HOME").filter(|v| !v.is_empty()) {
+        return Ok(PathBuf::from(xdg).join("clemp"));
+    }
+    let home = env::var("HOME").context("HOME environment variable not set")?;
+    Ok(PathBuf::from(home).join(".cache/clemp"))
+}
+
+/// Bare mirror of `repo_url` inside the cache. Named after the repo for
+/// readability, with a hash of the full URL so different hosts/owners with
+/// the same repo name never share a mirror.
+pub fn mirror_path(repo_url: &str) -> Result<PathBuf> {
+    let name: String = repo_url
+        .trim_end_matches('/')
+        .trim_end_matches(".git")
+        .rsplit(['/', ':'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let hash = hash_bytes(repo_url.as_bytes());
+    Ok(cache_dir()?.join(format!("{}-{}.git", name, &hash[..12])))
+}
+
+/// Bring the cached mirror of `repo_url` up to date: clone it on first use,
+/// fetch incrementally afterwards. With `offline`, the mirror is used as-is
+/// and must already exist.
+fn sync_mirror(repo_url: &str, mirror: &Path, offline: bool) -> Result<()> {
+    if offline {
+        if !mirror.is_dir() {
+            bail!(
+                "--offline: no cached copy of {} (expected at {}). Run once without --offline to populate the cache.",
+                repo_url,
+                mirror.display()
+            );
+        }
+        return Ok(());
+    }
+
+    if mirror.is_dir() {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(mirror)
+            .args(["fetch", "--quiet", "--prune", "origin"])
+            .status()
+            .context("Failed to execute git fetch")?;
+        if !status.success() {
+            bail!(
+                "git fetch of {} failed with status: {} — pass --offline to render from the cached copy",
+                repo_url,
+                status
+            );
+        }
+        return Ok(());
+    }
+
+    // Clone next to the final path and move it into place, so an interrupted
+    // first clone never leaves a half-populated mirror behind.
+    let parent = mirror.parent().context("Cache mirror path has no parent")?;
+    fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    let partial = mirror.with_extension(format!("partial-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&partial);
     let status = Command::new("git")
-        .args(["clone", "--depth=1", repo_url, CLONE_DIR])
+        .args(["clone", "--quiet", "--mirror", repo_url])
+        .arg(&partial)
         .status()
         .context("Failed to execute git clone")?;
+    if !status.success() {
+        let _ = fs::remove_dir_all(&partial);
+        bail!("git clone failed with status: {}", status);
+    }
+    fs::rename(&partial, mirror)
+        .with_context(|| format!("Failed to move template mirror to {}", mirror.display()))?;
+    Ok(())
+}
 
+/// Resolve `git_ref` (branch, tag or possibly-abbreviated SHA; `None` for the
+/// default branch) to a full commit SHA inside `mirror`. Branch and tag names
+/// are tried first, so an all-hex name like `cafe` is never mistaken for a
+/// commit; only a ref that names neither is looked up as a SHA.
+fn resolve_mirror_ref(
+    repo_url: &str,
+    mirror: &Path,
+    git_ref: Option<&str>,
+    offline: bool,
+) -> Result<String> {
+    let candidates = match git_ref {
+        None => vec!["HEAD".to_string()],
+        Some(r) => {
+            let mut candidates = vec![format!("refs/heads/{r}"), format!("refs/tags/{r}")];
+            if looks_like_sha(r) {
+                candidates.push(r.to_string());
+            }
+            candidates
+        }
+    };
+    for candidate in candidates {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(mirror)
+            .args([
+                "rev-parse",
+                "--verify",
+                "--quiet",
+                &format!("{candidate}^{{commit}}"),
+            ])
+            .output()
+            .context("Failed to execute git rev-parse")?;
+        if output.status.success() {
+            return Ok(String::from_utf8(output.stdout)?.trim().to_string());
+        }
+    }
+    let r = git_ref.unwrap_or("HEAD");
+    if offline {
+        bail!(
+            "--offline: ref '{}' isn't in the cached copy of {} — run without --offline to fetch it",
+            r,
+            repo_url
+        );
+    }
+    bail!(
+        "git checkout of ref '{}' failed — no such branch, tag or commit in {}",
+        r,
+        repo_url
+    );
+}
+
+/// Check `git_ref` of the template repo out into `clone_path`, removing any
+/// stale prior clone. The repo is fetched into a persistent bare mirror under
+/// `cache_dir()` (see `mirror_path`) and the working tree is checked out from
+/// there, so repeat runs only transfer new objects. `git_ref` selects a
+/// branch, tag or commit SHA; `None` takes the default branch. With
+/// `offline`, nothing is fetched and the cached mirror must already hold the
+/// ref. Returns the commit SHA of the checked-out tree.
+pub fn clone_repo(
+    repo_url: &str,
+    git_ref: Option<&str>,
+    clone_path: &Path,
+    offline: bool,
+) -> Result<String> {
+    clear_checkout(clone_path)?;
+    let mirror = mirror_path(repo_url)?;
+    sync_mirror(repo_url, &mirror, offline)?;
+    let sha = resolve_mirror_ref(repo_url, &mirror, git_ref, offline)?;
+
+    // `--shared` borrows the mirror's objects instead of copying them; the
+    // checkout is thrown away after rendering, long before any cache pruning.
+    let status = Command::new("git")
+        .args(["clone", "--quiet", "--shared", "--no-checkout"])
+        .arg(&mirror)
+        .arg(clone_path)
+        .status()
+        .context("Failed to execute git clone")?;
     if !status.success() {
         let _ = fs::remove_dir_all(clone_path);
-        bail!("git clone failed with status: {}", status);
+        bail!(
+            "git clone from the template cache failed with status: {}",
+            status
+        );
+    }
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(clone_path)
+        .args(["checkout", "--quiet", "--detach", &sha])
+        .status()
+        .context("Failed to checkout template ref")?;
+    if !status.success() {
+        let _ = fs::remove_dir_all(clone_path);
+        bail!("git checkout of {} failed with status: {}", sha, status);
     }
+    Ok(sha)
+}
 
-    let output = Command::new("git")
-        .args(["-C", CLONE_DIR, "rev-parse", "HEAD"])
-        .output()
-        .context("Failed to read template HEAD sha")?;
-    if !output.status.success() {
+/// Materialise a template `source` into `dest`. Local directories (plain
+/// paths or `file://` URLs) are copied straight from disk so template authors
+/// can render uncommitted work; anything else is checked out at `git_ref` via
+/// the template cache (see `clone_repo`).
+/// Returns the revision recorded as the lockfile's `template_sha`.
+pub fn fetch_template(
+    source: &str,
+    git_ref: Option<&str>,
+    dest: &Path,
+    offline: bool,
+) -> Result<String> {
+    match local_template_path(source) {
+        Some(dir) => {
+            if let Some(r) = git_ref {
+                bail!(
+                    "--ref '{}' can't be used with the local template directory {} — check the ref out there instead",
+                    r,
+                    dir.display()
+                );
+            }
+            copy_local_template(&dir, dest)
+        }
+        None => clone_repo(source, git_ref, dest, offline),
+    }
+}
+
+/// Interpret `source` as a local template directory. `file://` URLs always
+/// count; bare paths count when they start with `/`, `./`, `../` or `~/`, or
+/// name an existing directory.
+pub fn local_template_path(source: &str) -> Option<PathBuf> {
+    if let Some(rest) = source.strip_prefix("file://") {
+        return Some(PathBuf::from(rest));
+    }
+    if let Some(rest) = source.strip_prefix("~/") {
+        let home = env::var("HOME").ok()?;
+        return Some(PathBuf::from(home).join(rest));
+    }
+    let looks_like_path = ["/", "./", "../"].iter().any(|p| source.starts_with(p));
+    (looks_like_path || Path::new(source).is_dir()).then(|| PathBuf::from(source))
+}
+
+/// Copy a local template directory into `clone_path` (skipping `.git`) and
+/// return its revision: the HEAD SHA when `dir` is a clean git checkout,
+/// otherwise a content hash of the tree (see `hash_template_tree`) so
+/// uncommitted edits still register as a template change on `clemp update`.
+pub fn copy_local_template(dir: &Path, clone_path: &Path) -> Result<String> {
+    if !dir.is_dir() {
+        bail!("Local template directory {} does not exist", dir.display());
+    }
+    clear_checkout(clone_path)?;
+
+    fs::create_dir_all(clone_path)?;
+    let copied = (|| {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry.file_name() == ".git" {
+                continue;
+            }
+            let src = entry.path();
+            let dest = clone_path.join(entry.file_name());
+            if src.is_dir() {
+                copy_dir_recursive(&src, &dest)?;
+            } else {
+                fs::copy(&src, &dest)
+                    .with_context(|| format!("Failed to copy {}", src.display()))?;
+            }
+        }
+        Ok::<_, anyhow::Error>(())
+    })();
+    if let Err(e) = copied {
+        let _ = fs::remove_dir_all(clone_path);
+        return Err(e);
+    }
+
+    match clean_git_head(dir) {
+        Some(sha) => Ok(sha),
+        None => hash_template_tree(dir),
+    }
+}
+
+/// HEAD SHA of `dir` if it is the root of a git work tree with no uncommitted
+/// or untracked changes.
+fn clean_git_head(dir: &Path) -> Option<String> {
+    if !dir.join(".git").exists() {
+        return None;
+    }
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+    };
+    let status = git(&["status", "--porcelain"])?;
+    if !status.is_empty() {
+        return None;
+    }
+    git(&["rev-parse", "HEAD"])
+}
+
+/// SHA-256 over every file under `dir` (excluding `.git`), fed as sorted
+/// `path\0contents\0` records so renames and edits both change the digest.
+pub fn hash_template_tree(dir: &Path) -> Result<String> {
+    fn walk(root: &Path, rel: &Path, out: &mut Vec<PathBuf>) -> Result<()> {
+        for entry in fs::read_dir(root.join(rel))? {
+            let entry = entry?;
+            if rel.as_os_str().is_empty() && entry.file_name() == ".git" {
+                continue;
+            }
+            let sub = rel.join(entry.file_name());
+            if entry.path().is_dir() {
+                walk(root, &sub, out)?;
+            } else {
+                out.push(sub);
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    walk(dir, Path::new(""), &mut files)?;
+    let mut keyed: Vec<(String, PathBuf)> =
+        files.into_iter().map(|p| (lockfile_key(&p), p)).collect();
+    keyed.sort();
+
+    let mut hasher = Sha256::new();
+    for (key, rel) in &keyed {
+        let path = dir.join(rel);
+        let bytes =
+            fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        hasher.update(key.as_bytes());
+        hasher.update([0]);
+        hasher.update(&bytes);
+        hasher.update([0]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
+
+/// Whether a `--ref` value can be looked up as a commit SHA once it has
+/// failed to resolve as a branch or tag name: 7–40 hex digits.
+pub fn looks_like_sha(git_ref: &str) -> bool {
+    (7..=40).contains(&git_ref.len()) && git_ref.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// ── Project config ───────────────────────────────────────────────────────
+
+/// Checked-in desired state for a project: default setup arguments that bare
+/// `clemp` and `clemp update` start from. Unlike the lockfile, clemp never
+/// writes it.
+pub const PROJECT_CONFIG_NAME: &str = ".clemp.yaml";
+
+/// Parsed `.clemp.yaml`: the root project's command plus, for monorepos, a
+/// `packages:` map from subdirectory to that package's own command.
+#[derive(Clone, Debug, Default)]
+pub struct ProjectConfig {
+    pub command: OriginalCommand,
+    pub packages: BTreeMap<String, OriginalCommand>,
+}
+
+/// `.clemp.yaml` as written. `deny_unknown_fields` doesn't combine with
+/// `flatten`, so keys `OriginalCommand` doesn't know are collected instead and
+/// rejected by `ProjectConfig::load`.
+#[derive(Deserialize, Default)]
+struct RawProjectConfig {
+    #[serde(flatten)]
+    command: RawCommand,
+    #[serde(default)]
+    packages: BTreeMap<String, RawCommand>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawCommand {
+    #[serde(flatten)]
+    command: OriginalCommand,
+    #[serde(flatten)]
+    unknown: BTreeMap<String, serde_yaml::Value>,
+}
+
+impl RawCommand {
+    /// The command, or an error naming the keys a typo left unused.
+    fn into_command(self) -> Result<OriginalCommand> {
+        if !self.unknown.is_empty() {
+            let keys: Vec<String> = self.unknown.keys().map(|k| format!("`{k}`")).collect();
+            bail!(
+                "Unknown key{} {}",
+                if keys.len() == 1 { "" } else { "s" },
+                keys.join(", ")
+            );
+        }
+        Ok(self.command)
+    }
+}
+
+impl ProjectConfig {
+    /// Load `.clemp.yaml` from `dir`, `None` when the project doesn't have one.
+    /// Unknown keys, at the top level or in a package, are an error.
+    pub fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(PROJECT_CONFIG_NAME);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        // An empty file parses as YAML null; treat it as "no defaults".
+        let raw: Option<RawProjectConfig> = serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        let raw = raw.unwrap_or_default();
+        let mut config = ProjectConfig {
+            command: raw
+                .command
+                .into_command()
+                .with_context(|| format!("In {}", path.display()))?,
+            packages: BTreeMap::new(),
+        };
+        for (package, command) in raw.packages {
+            let command = command
+                .into_command()
+                .with_context(|| format!("In {} package '{package}'", path.display()))?;
+            config.packages.insert(package, command);
+        }
+        // Only the built-in aliases are known before the template is fetched;
+        // callers re-check with the template's once it is.
+        let aliases = LanguageAliases::default();
+        reject_add_drop_overlap(&config.command, &aliases)
+            .with_context(|| format!("In {}", path.display()))?;
+        for (package, command) in &config.packages {
+            validate_package_dir(package, &config.packages)
+                .with_context(|| format!("In {}", path.display()))?;
+            reject_add_drop_overlap(command, &aliases)
+                .with_context(|| format!("In {} package '{package}'", path.display()))?;
+        }
+        Ok(Some(config))
+    }
+}
+
+/// Load `.clemp.yaml` from `dir`. Same shape as the lockfile's
+/// `original-command` (`languages`, `mcp`, `drop-mcp`, `clarg`, …). `None`
+/// when the project doesn't have one.
+pub fn load_project_config(dir: &Path) -> Result<Option<OriginalCommand>> {
+    Ok(ProjectConfig::load(dir)?.map(|config| config.command))
+}
+
+/// Workspace packages declared in `dir/.clemp.yaml`, keyed by subdirectory.
+/// Empty for single-package projects.
+pub fn load_workspace_packages(dir: &Path) -> Result<BTreeMap<String, OriginalCommand>> {
+    Ok(ProjectConfig::load(dir)?
+        .map(|config| config.packages)
+        .unwrap_or_default())
+}
+
+/// A package key must be a plain relative path below the project root, and
+/// packages may not nest inside each other (their files would overlap).
+fn validate_package_dir(package: &str, all: &BTreeMap<String, OriginalCommand>) -> Result<()> {
+    let path = Path::new(package);
+    let plain = !package.is_empty()
+        && !package.ends_with('/')
+        && path
+            .components()
+            .all(|c| matches!(c, std::path::Component::Normal(_)));
+    if !plain {
         bail!(
-            "git rev-parse HEAD failed: {}",
-            String::from_utf8_lossy(&output.stderr)
+            "Package '{package}' must be a relative path below the project root (e.g. `web` or `apps/api`)"
         );
     }
-    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+    if let Some(outer) = all
+        .keys()
+        .find(|other| package.starts_with(&format!("{other}/")))
+    {
+        bail!("Package '{package}' is nested inside package '{outer}'");
+    }
+    Ok(())
+}
+
+/// The root command of `dir/.clemp.yaml`, checked against the template's
+/// `aliases`. `None` when the project doesn't have one.
+fn project_defaults(dir: &Path, aliases: &LanguageAliases) -> Result<Option<OriginalCommand>> {
+    let Some(command) = load_project_config(dir)? else {
+        return Ok(None);
+    };
+    println!("Using project defaults from {PROJECT_CONFIG_NAME}");
+    reject_add_drop_overlap(&command, aliases)
+        .with_context(|| format!("In {}", dir.join(PROJECT_CONFIG_NAME).display()))?;
+    Ok(Some(command))
+}
+
+/// The effective arguments of a fresh setup: the project's `.clemp.yaml`
+/// defaults with the CLI args merged additively on top (see
+/// `OriginalCommand::merge_additive`). Runtime-only fields (`template`,
+/// `git_ref`, `force`, `no_validate`, `strict_mcp`, `offline`) always come
+/// from the CLI.
+pub fn with_project_config(
+    args: &SetupArgs,
+    dir: &Path,
+    aliases: &LanguageAliases,
+) -> Result<SetupArgs> {
+    let Some(mut command) = project_defaults(dir, aliases)? else {
+        return Ok(args.clone());
+    };
+    command.merge_additive(&OriginalCommand::from_setup(args), aliases)?;
+    Ok(SetupArgs {
+        template: args.template.clone(),
+        git_ref: args.git_ref.clone(),
+        force: args.force,
+        no_validate: args.no_validate,
+        strict_mcp: args.strict_mcp,
+        offline: args.offline,
+        ..command.into_setup()
+    })
+}
+
+// ── Template manifest / inheritance ──────────────────────────────────────
+
+/// Optional template-root file describing the template itself.
+pub const TEMPLATE_MANIFEST_NAME: &str = "clemp-template.yaml";
+
+/// Longest `extends` chain clemp will follow before assuming a misconfiguration.
+const MAX_EXTENDS_DEPTH: usize = 8;
+
+/// Newest `clemp-template.yaml` schema this build understands.
+pub const TEMPLATE_SCHEMA_VERSION: u32 = 1;
+
+/// Parsed `clemp-template.yaml`. Every field is optional; a template without
+/// the file behaves exactly like `TemplateManifest::default()`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TemplateManifest {
+    /// Manifest schema the template was written against. `None` means 1.
+    #[serde(default)]
+    pub schema: Option<u32>,
+    /// Oldest clemp release that can render this template, e.g. `0.9` or
+    /// `0.9.2`.
+    #[serde(default, rename = "min-clemp-version")]
+    pub min_clemp_version: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub maintainers: Vec<String>,
+    /// Canonical language → aliases users may type for it, e.g.
+    /// `kotlin: [kt, kts]`. Extends the built-in alias table; the canonical
+    /// name is what `claude-md/lang-rules/<name>.md` and conditional
+    /// directories are keyed by.
+    #[serde(default)]
+    pub aliases: BTreeMap<String, Vec<String>>,
+    /// Languages that pull in others, e.g. `svelte: [typescript, html]`.
+    /// Expanded transitively at render time; the lockfile only records what
+    /// the user typed, so implications can change upstream.
+    #[serde(default)]
+    pub implies: BTreeMap<String, Vec<String>>,
+    /// Extra marker-file rules for detecting project languages when none are
+    /// passed; checked before the built-in rules.
+    #[serde(default)]
+    pub detect: Vec<DetectRule>,
+    /// Parent template this one is layered on top of.
+    #[serde(default)]
+    pub extends: Option<TemplateParent>,
+    /// Settings parts written to the shared `.claude/settings.json` by
+    /// default, e.g. `[hooks, permissions]`. Projects override per part with
+    /// `--shared-settings` / `--local-settings`. Unset inherits the parent
+    /// template's list; `[]` clears it.
+    #[serde(
+        default,
+        rename = "shared-settings",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub shared_settings: Option<Vec<SettingsPart>>,
+}
+
+/// `extends:` entry of a template manifest.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TemplateParent {
+    /// Git URL or local directory of the parent template. Relative paths are
+    /// resolved against the child when the child is itself a local directory.
+    pub repo: String,
+    #[serde(default, rename = "ref")]
+    pub git_ref: Option<String>,
+}
+
+impl TemplateManifest {
+    /// Load `clemp-template.yaml` from a fetched template tree. A missing file
+    /// yields the default (empty) manifest.
+    pub fn load(template_dir: &Path) -> Result<Self> {
+        let path = template_dir.join(TEMPLATE_MANIFEST_NAME);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Layer `over` (a child template, or a later template in a stack) on top
+    /// of this manifest. Alias lists are unioned per language, `over`'s
+    /// `implies` entries replace ours for the same language, its `detect`
+    /// rules are checked first, and its metadata wins where set. `extends` is
+    /// dropped: by the time manifests are layered the chain is resolved.
+    pub fn layered(mut self, over: TemplateManifest) -> TemplateManifest {
+        for (canonical, aliases) in over.aliases {
+            let entry = self.aliases.entry(canonical).or_default();
+            for alias in aliases {
+                if !entry.contains(&alias) {
+                    entry.push(alias);
+                }
+            }
+        }
+        self.implies.extend(over.implies);
+        let mut detect = over.detect;
+        detect.append(&mut self.detect);
+        TemplateManifest {
+            schema: over.schema.or(self.schema),
+            min_clemp_version: over.min_clemp_version.or(self.min_clemp_version),
+            name: over.name.or(self.name),
+            description: over.description.or(self.description),
+            maintainers: if over.maintainers.is_empty() {
+                self.maintainers
+            } else {
+                over.maintainers
+            },
+            aliases: self.aliases,
+            implies: self.implies,
+            detect,
+            extends: None,
+            shared_settings: over.shared_settings.or(self.shared_settings),
+        }
+    }
+
+    /// Write the layered manifest of an assembled template into `dest`, in
+    /// place of whichever layer's file the overlay left there.
+    fn save_layered(manifests: Vec<TemplateManifest>, dest: &Path) -> Result<()> {
+        let merged = manifests
+            .into_iter()
+            .reduce(TemplateManifest::layered)
+            .unwrap_or_default();
+        let path = dest.join(TEMPLATE_MANIFEST_NAME);
+        fs::write(&path, serde_yaml::to_string(&merged)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Refuse templates this build can't render correctly: a `schema` newer
+    /// than `TEMPLATE_SCHEMA_VERSION`, or a `min-clemp-version` above the
+    /// running version. `source` names the template in error messages.
+    pub fn validate(&self, source: &str) -> Result<()> {
+        let label = match &self.name {
+            Some(name) => format!("'{name}' ({source})"),
+            None => source.to_string(),
+        };
+        let current = env!("CARGO_PKG_VERSION");
+        let upgrade = "Upgrade clemp (`brew upgrade clemp`, or `cargo install --path .` from an up-to-date checkout) and re-run.";
+
+        if let Some(min) = &self.min_clemp_version {
+            let min = min.trim().trim_start_matches('v');
+            let req = semver::VersionReq::parse(&format!(">={min}")).with_context(|| {
+                format!("Invalid min-clemp-version '{min}' in {TEMPLATE_MANIFEST_NAME} of template {label}")
+            })?;
+            let version = semver::Version::parse(current).context("Invalid clemp version")?;
+            if !req.matches(&version) {
+                bail!(
+                    "Template {label} requires clemp {min} or newer, but this is clemp {current}.\n{upgrade}"
+                );
+            }
+        }
+        match self.schema {
+            Some(0) => bail!("Invalid schema 0 in {TEMPLATE_MANIFEST_NAME} of template {label}"),
+            Some(schema) if schema > TEMPLATE_SCHEMA_VERSION => bail!(
+                "Template {label} uses {TEMPLATE_MANIFEST_NAME} schema {schema}, but clemp {current} only understands schema {TEMPLATE_SCHEMA_VERSION}.\n{upgrade}"
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A template source pinned at the revision clemp rendered from.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LockedSource {
+    pub repo: String,
+    #[serde(default, rename = "ref", skip_serializing_if = "Option::is_none")]
+    pub git_ref: Option<String>,
+    pub sha: String,
+}
+
+/// Identity of one template source assembled in the clone dir: the profile
+/// and source the user selected, its revision, and every ancestor it inherits
+/// from. Stacked templates are recorded in the lockfile in this form.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TemplateRevision {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    pub repo: String,
+    pub sha: String,
+    /// Ancestors pulled in via `extends`, nearest parent first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parents: Vec<LockedSource>,
+}
+
+/// One entry of a template stack to fetch.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateSource {
+    pub profile: Option<String>,
+    pub repo: String,
+    pub git_ref: Option<String>,
+}
+
+/// Fetch `source` into `dest` and, if its manifest `extends` a parent, fetch
+/// the whole ancestor chain and overlay it so `dest` ends up holding the
+/// effective template: each child's files replace its parent's at the same
+/// path, and everything else is inherited. Because contributors are files
+/// keyed by stem (`mcp/default/<stem>.json`, `commands/<lang>/<name>.md`, …),
+/// this merges the layered categories per stem. `clemp-template.yaml` is the
+/// exception: the manifests are merged table by table (see
+/// `TemplateManifest::layered`) rather than the child's replacing the parent's.
+///
+/// `pinned_parents` (from a lockfile) overrides the declared ref of any git
+/// parent with the same repo so `--only` can reproduce the recorded tree.
+pub fn prepare_template(
+    source: &str,
+    git_ref: Option<&str>,
+    pinned_parents: &[LockedSource],
+    dest: &Path,
+    offline: bool,
+) -> Result<TemplateRevision> {
+    let sha = fetch_template(source, git_ref, dest, offline)?;
+    let mut revision = TemplateRevision {
+        repo: source.to_string(),
+        sha,
+        ..Default::default()
+    };
+
+    let layer_dir = |n: usize| {
+        let name = dest
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        dest.with_file_name(format!("{name}.layer-{n}"))
+    };
+    // Fetched ancestors, nearest parent first; the child is renamed to
+    // `layer-0` once we know there is something to overlay.
+    let mut layers: Vec<PathBuf> = Vec::new();
+
+    let result = (|| {
+        let mut chain = vec![source.to_string()];
+        let mut child_dir = dest.to_path_buf();
+        // Each layer's manifest, root ancestor first.
+        let mut manifests = Vec::new();
+        loop {
+            // Validate every layer before anything is rendered from it.
+            let manifest = TemplateManifest::load(&child_dir)?;
+            manifest.validate(chain.last().unwrap())?;
+            let extends = manifest.extends.clone();
+            manifests.insert(0, manifest);
+            let Some(parent) = extends else { break };
+            let repo = resolve_parent_source(&parent.repo, chain.last().unwrap());
+            if chain.contains(&repo) {
+                bail!(
+                    "Template inheritance cycle: {} → {}",
+                    chain.join(" → "),
+                    repo
+                );
+            }
+            if layers.len() >= MAX_EXTENDS_DEPTH {
+                bail!(
+                    "Template inheritance deeper than {} levels: {}",
+                    MAX_EXTENDS_DEPTH,
+                    chain.join(" → ")
+                );
+            }
+            let pinned = pinned_parents
+                .iter()
+                .find(|p| p.repo == repo && local_template_path(&repo).is_none())
+                .map(|p| p.sha.as_str());
+
+            println!("Cloning parent template {}...", repo);
+            let dir = layer_dir(layers.len() + 1);
+            layers.push(dir.clone());
+            let sha = fetch_template(&repo, pinned.or(parent.git_ref.as_deref()), &dir, offline)?;
+            revision.parents.push(LockedSource {
+                repo: repo.clone(),
+                git_ref: parent.git_ref,
+                sha,
+            });
+            chain.push(repo);
+            child_dir = dir;
+        }
+
+        if layers.is_empty() {
+            return Ok(());
+        }
+        let child = layer_dir(0);
+        fs::rename(dest, &child)
+            .with_context(|| format!("Failed to move {} aside", dest.display()))?;
+        layers.insert(0, child);
+        fs::create_dir_all(dest)?;
+        for layer in layers.iter().rev() {
+            overlay_dir(layer, dest)?;
+        }
+        TemplateManifest::save_layered(manifests, dest)
+    })();
+
+    for layer in &layers {
+        let _ = fs::remove_dir_all(layer);
+    }
+    if let Err(e) = result {
+        let _ = fs::remove_dir_all(dest);
+        return Err(e);
+    }
+    Ok(revision)
+}
+
+/// Prepare every source of a template stack (see `prepare_template`) and
+/// overlay them in order into `dest`, so a later template's files replace an
+/// earlier one's at the same path. Contributors therefore merge per stem across
+/// sources exactly as they do across `extends` ancestors, and the rendered
+/// files carry no trace of which source produced them. Manifests are layered
+/// in stack order the same way.
+///
+/// Returns one revision per source, in stack order.
+pub fn prepare_template_stack(
+    sources: &[TemplateSource],
+    pinned_parents: &[LockedSource],
+    dest: &Path,
+    offline: bool,
+) -> Result<Vec<TemplateRevision>> {
+    let with_profile = |mut revision: TemplateRevision, source: &TemplateSource| {
+        revision.profile = source.profile.clone();
+        revision
+    };
+    match sources {
+        [] => bail!("No template sources to render from"),
+        [source] => {
+            let revision = prepare_template(
+                &source.repo,
+                source.git_ref.as_deref(),
+                pinned_parents,
+                dest,
+                offline,
+            )?;
+            return Ok(vec![with_profile(revision, source)]);
+        }
+        _ => {}
+    }
+
+    let source_dir = |n: usize| {
+        let name = dest
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        dest.with_file_name(format!("{name}.source-{n}"))
+    };
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    let result = (|| {
+        let mut revisions = Vec::new();
+        for (n, source) in sources.iter().enumerate() {
+            if n > 0 {
+                println!("Cloning stacked template {}...", source.repo);
+            }
+            let dir = source_dir(n);
+            dirs.push(dir.clone());
+            let revision = prepare_template(
+                &source.repo,
+                source.git_ref.as_deref(),
+                pinned_parents,
+                &dir,
+                offline,
+            )?;
+            revisions.push(with_profile(revision, source));
+        }
+        clear_checkout(dest)?;
+        fs::create_dir_all(dest)?;
+        let mut manifests = Vec::new();
+        for dir in &dirs {
+            overlay_dir(dir, dest)?;
+            manifests.push(TemplateManifest::load(dir)?);
+        }
+        TemplateManifest::save_layered(manifests, dest)?;
+        Ok(revisions)
+    })();
+
+    for dir in &dirs {
+        let _ = fs::remove_dir_all(dir);
+    }
+    if result.is_err() {
+        let _ = fs::remove_dir_all(dest);
+    }
+    result
 }
 
-/// Fetch and checkout a specific commit in an already-cloned template directory.
-/// Used by `--only` to pin the template at the lockfile's SHA.
-pub fn checkout_sha(clone_dir: &Path, sha: &str) -> Result<()> {
-    let status = Command::new("git")
-        .args(["-C", &clone_dir.to_string_lossy(), "fetch", "origin", sha])
-        .status()
-        .context("Failed to fetch pinned SHA")?;
-    if !status.success() {
-        bail!("git fetch for --only pinned SHA {sha} failed — the commit may have been force-pushed away");
+/// Resolve a parent `repo` declared by the template at `child_source`. Relative
+/// local paths are taken relative to a local child directory; everything else
+/// passes through unchanged.
+fn resolve_parent_source(parent: &str, child_source: &str) -> String {
+    let relative = parent.starts_with("./") || parent.starts_with("../");
+    let Some(child_dir) = local_template_path(child_source).filter(|_| relative) else {
+        return parent.to_string();
+    };
+    // Fold `.`/`..` lexically so the same directory always yields the same
+    // string — cycle detection and lockfile pins compare these verbatim.
+    let mut out = PathBuf::new();
+    for component in child_dir.join(parent).components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir
+                if matches!(
+                    out.components().next_back(),
+                    Some(std::path::Component::Normal(_))
+                ) =>
+            {
+                out.pop();
+            }
+            c => out.push(c),
+        }
     }
+    out.to_string_lossy().into_owned()
+}
 
-    let status = Command::new("git")
-        .args(["-C", &clone_dir.to_string_lossy(), "checkout", sha])
-        .status()
-        .context("Failed to checkout pinned SHA")?;
-    if !status.success() {
-        bail!("git checkout {sha} failed");
+/// Copy `src` over `dest`, replacing files at the same path and recursing
+/// into directories present in both. A file in `src` replaces a directory in
+/// `dest` and vice versa. The top-level `.git` of `src` is skipped.
+fn overlay_dir(src: &Path, dest: &Path) -> Result<()> {
+    fn walk(src: &Path, dest: &Path, top: bool) -> Result<()> {
+        fs::create_dir_all(dest)?;
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            if top && entry.file_name() == ".git" {
+                continue;
+            }
+            let from = entry.path();
+            let to = dest.join(entry.file_name());
+            if from.is_dir() {
+                if to.is_file() {
+                    fs::remove_file(&to)?;
+                }
+                walk(&from, &to, false)?;
+            } else {
+                if to.is_dir() {
+                    fs::remove_dir_all(&to)?;
+                }
+                fs::copy(&from, &to).with_context(|| {
+                    format!("Failed to copy {} to {}", from.display(), to.display())
+                })?;
+            }
+        }
+        Ok(())
     }
-    Ok(())
+    walk(src, dest, true)
 }
 
+/// Header `update_gitignore` writes above the lines it appends.
+const GITIGNORE_BLOCK_HEADER: &str = "# Claude related";
+
 /// Append template gitignore fragments to `<dest_dir>/.gitignore`, skipping any
 /// lines already present. Idempotent.
 ///
@@ -1369,7 +4107,7 @@ pub fn update_gitignore(clone_dir: &Path, dest_dir: &Path, langs: &[String]) ->
     if !content.ends_with('\n') && !content.is_empty() {
         content.push('\n');
     }
-    content.push_str("\n# Claude related\n");
+    content.push_str(&format!("\n{GITIGNORE_BLOCK_HEADER}\n"));
     for entry in new_entries {
         content.push_str(entry);
         content.push('\n');
@@ -1382,6 +4120,125 @@ pub fn update_gitignore(clone_dir: &Path, dest_dir: &Path, langs: &[String]) ->
     Ok(())
 }
 
+/// Non-empty trimmed lines of `gitignore-additions/<name>.gitignore`, or none
+/// when the fragment doesn't exist.
+fn gitignore_fragment_lines(clone_dir: &Path, name: &str) -> Result<Vec<String>> {
+    let path = clone_dir
+        .join("gitignore-additions")
+        .join(format!("{name}.gitignore"));
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// For each line of a `.gitignore`, whether it sits inside a block clemp
+/// appended: after a `# Claude related` header and before the next blank line.
+fn in_clemp_gitignore_block(lines: &[&str]) -> Vec<bool> {
+    let mut inside = false;
+    lines
+        .iter()
+        .map(|line| match line.trim() {
+            GITIGNORE_BLOCK_HEADER => {
+                inside = true;
+                false
+            }
+            "" => {
+                inside = false;
+                false
+            }
+            _ => inside,
+        })
+        .collect()
+}
+
+/// Lines clemp appended to `<dest_dir>/.gitignore` that only the fragments of
+/// `removed` languages supply — lines also in `default.gitignore` or a
+/// fragment of a `kept` language are never reported, and neither are lines
+/// outside clemp's `# Claude related` blocks, which the user wrote.
+pub fn stale_gitignore_lines(
+    clone_dir: &Path,
+    dest_dir: &Path,
+    removed: &[String],
+    kept: &[String],
+) -> Result<Vec<String>> {
+    let gitignore_path = dest_dir.join(".gitignore");
+    if removed.is_empty() || !gitignore_path.is_file() {
+        return Ok(Vec::new());
+    }
+    let mut still_supplied: HashSet<String> = gitignore_fragment_lines(clone_dir, "default")?
+        .into_iter()
+        .collect();
+    for lang in kept {
+        still_supplied.extend(gitignore_fragment_lines(clone_dir, lang)?);
+    }
+    let existing = fs::read_to_string(&gitignore_path)?;
+    let lines: Vec<&str> = existing.lines().collect();
+    let present: HashSet<&str> = lines
+        .iter()
+        .zip(in_clemp_gitignore_block(&lines))
+        .filter(|(_, appended)| *appended)
+        .map(|(line, _)| line.trim())
+        .collect();
+
+    let mut stale = Vec::new();
+    for lang in removed {
+        for line in gitignore_fragment_lines(clone_dir, lang)? {
+            if present.contains(line.as_str())
+                && !still_supplied.contains(&line)
+                && !stale.contains(&line)
+            {
+                stale.push(line);
+            }
+        }
+    }
+    Ok(stale)
+}
+
+/// Remove `lines` from clemp's `# Claude related` blocks in
+/// `<dest_dir>/.gitignore`, along with any block left empty. User-written
+/// copies of the same lines elsewhere in the file are kept, and the rest of
+/// the file stays byte-for-byte intact.
+pub fn remove_gitignore_lines(dest_dir: &Path, lines: &[String]) -> Result<()> {
+    let gitignore_path = dest_dir.join(".gitignore");
+    let existing = fs::read_to_string(&gitignore_path)
+        .with_context(|| format!("Failed to read {}", gitignore_path.display()))?;
+    let all: Vec<&str> = existing.split_inclusive('\n').collect();
+    let appended = in_clemp_gitignore_block(&all);
+    let kept: Vec<&str> = all
+        .iter()
+        .zip(appended)
+        .filter(|(line, appended)| !(*appended && lines.iter().any(|l| l == line.trim())))
+        .map(|(line, _)| *line)
+        .collect();
+
+    // Drop headers whose block is now empty, with the blank line
+    // `update_gitignore` put above them.
+    let mut content: Vec<&str> = Vec::with_capacity(kept.len());
+    for (i, line) in kept.iter().enumerate() {
+        let empty_block = line.trim() == GITIGNORE_BLOCK_HEADER
+            && kept
+                .get(i + 1)
+                .is_none_or(|next| next.trim().is_empty() || next.trim() == GITIGNORE_BLOCK_HEADER);
+        if empty_block {
+            if content.last().is_some_and(|prev| prev.trim().is_empty()) {
+                content.pop();
+            }
+            continue;
+        }
+        content.push(line);
+    }
+    fs::write(&gitignore_path, content.concat())?;
+    Ok(())
+}
+
 /// Collect destination paths that already exist and would be overwritten.
 pub fn collect_conflicts(sources: &[PathBuf], dest_dir: &Path) -> Vec<PathBuf> {
     sources
@@ -1434,8 +4291,12 @@ const COPY_FILES_EXCLUDE: &[&str] = &[
     "copied",
     "hooks",
     "mcp",
+    "permissions",
     "githooks",
+    "settings",
     "settings.local.json",
+    "settings.local.json.jinja",
+    TEMPLATE_MANIFEST_NAME,
 ];
 
 /// Collect the source paths that `copy_files` would copy to CWD.
@@ -1659,7 +4520,7 @@ pub fn copy_named_commands(named_commands: &[String], clone_dir: &Path) -> Resul
                 .filter_map(|e| e.ok())
                 .filter(|e| {
                     let p = e.path();
-                    p.is_file() && p.extension().map_or(false, |ext| ext == "md")
+                    p.is_file() && p.extension().is_some_and(|ext| ext == "md")
                 })
                 .map(|e| e.path().file_stem().unwrap().to_string_lossy().to_string())
                 .collect();
@@ -1677,26 +4538,99 @@ pub fn copy_named_commands(named_commands: &[String], clone_dir: &Path) -> Resul
     Ok(())
 }
 
-pub fn cleanup(clone_dir: &Path) -> Result<()> {
-    fs::remove_dir_all(clone_dir)
-        .with_context(|| format!("Failed to remove {}", clone_dir.display()))?;
-    Ok(())
+/// Private per-run scratch space for the template checkout and everything
+/// derived from it (parent/stack layers, update staging). Uniquely named under
+/// the system temp dir, so concurrent runs never collide and nothing scratch
+/// ever lands in the user's CWD. Removed on drop — success, error or panic —
+/// and, once `remove_on_interrupt` is called, on Ctrl-C / SIGTERM.
+pub struct ScratchDir {
+    dir: tempfile::TempDir,
+}
+
+impl ScratchDir {
+    pub fn new() -> Result<Self> {
+        let dir = tempfile::Builder::new()
+            .prefix("clemp-")
+            .tempdir()
+            .context("Failed to create a temporary directory for the template")?;
+        Ok(Self { dir })
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Where the template is checked out. Not created until fetched.
+    pub fn clone_dir(&self) -> PathBuf {
+        self.path().join(CLONE_DIR)
+    }
+
+    /// Delete this directory on Ctrl-C / SIGTERM before exiting with status
+    /// 130. Drop never runs on a signal, so without this an interrupted clone
+    /// would leak into the temp dir. The process-wide handler is installed by
+    /// the first call; later ones only register their directory with it.
+    pub fn remove_on_interrupt(&self) -> Result<()> {
+        let mut installed = Ok(());
+        let dirs = INTERRUPT_CLEANUP.get_or_init(|| {
+            installed = ctrlc::set_handler(|| {
+                if let Some(dirs) = INTERRUPT_CLEANUP.get() {
+                    for dir in dirs.lock().unwrap_or_else(PoisonError::into_inner).iter() {
+                        let _ = fs::remove_dir_all(dir);
+                    }
+                }
+                eprintln!("\nInterrupted.");
+                std::process::exit(130);
+            });
+            Mutex::new(Vec::new())
+        });
+        installed.context("Failed to install the Ctrl-C handler")?;
+        dirs.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(self.path().to_path_buf());
+        Ok(())
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        if let Some(dirs) = INTERRUPT_CLEANUP.get() {
+            dirs.lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .retain(|dir| dir != self.dir.path());
+        }
+    }
 }
 
+/// Scratch directories the interrupt handler removes (see
+/// `ScratchDir::remove_on_interrupt`).
+static INTERRUPT_CLEANUP: OnceLock<Mutex<Vec<PathBuf>>> = OnceLock::new();
+
 // ── Listing ──────────────────────────────────────────────────────────────
 
-const LIST_CATEGORIES: &[&str] = &["mcp", "hooks", "commands", "githooks", "clarg", "gitignore", "languages"];
+const LIST_CATEGORIES: &[&str] = &[
+    "mcp",
+    "hooks",
+    "permissions",
+    "commands",
+    "githooks",
+    "clarg",
+    "gitignore",
+    "languages",
+    "tags",
+];
 
 /// List available named files for a template category.
 pub fn list_category(category: &str, clone_dir: &Path) -> Result<Vec<String>> {
     let (subdir, extensions): (&str, &[&str]) = match category {
         "mcp" => ("mcp", &["json"]),
         "hooks" => ("hooks", &["json"]),
+        "permissions" => ("permissions", &["json"]),
         "commands" => ("commands", &["md"]),
         "githooks" => ("githooks", &[]),
         "clarg" => ("clarg", &["yaml", "yml"]),
         "gitignore" => ("gitignore-additions", &["gitignore"]),
         "languages" => ("claude-md/lang-rules", &["md"]),
+        "tags" => ("claude-md/tag-rules", &["md"]),
         _ => bail!(
             "Unknown category '{}'. Valid categories: {}",
             category,
@@ -1709,6 +4643,13 @@ pub fn list_category(category: &str, clone_dir: &Path) -> Result<Vec<String>> {
         return Ok(vec![]);
     }
 
+    // Contributor kinds also accept `<name>.json.jinja`.
+    if matches!(category, "mcp" | "hooks" | "permissions") {
+        let mut names = layer_stems(&dir, extensions[0], &HashSet::new())?;
+        names.extend(language_layer_entries(&dir, extensions[0])?);
+        return Ok(names);
+    }
+
     let mut names: Vec<String> = fs::read_dir(&dir)?
         .filter_map(|e| e.ok())
         .filter(|e| e.path().is_file())
@@ -1730,12 +4671,58 @@ pub fn list_category(category: &str, clone_dir: &Path) -> Result<Vec<String>> {
     Ok(names)
 }
 
+/// Contributors filed under a kind's language (and `tags/<tag>`) layer, as
+/// `<subdir>/<stem>`. These are picked up automatically for the matching
+/// language or tag rather than named on the command line.
+fn language_layer_entries(kind_dir: &Path, ext: &str) -> Result<Vec<String>> {
+    let mut subdirs: Vec<String> = Vec::new();
+    for entry in fs::read_dir(kind_dir)?.filter_map(|e| e.ok()) {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !entry.path().is_dir() || name == "default" {
+            continue;
+        }
+        if name == TAGS_DIR {
+            for tag in fs::read_dir(entry.path())?.filter_map(|e| e.ok()) {
+                if tag.path().is_dir() {
+                    subdirs.push(format!("{TAGS_DIR}/{}", tag.file_name().to_string_lossy()));
+                }
+            }
+        } else {
+            subdirs.push(name);
+        }
+    }
+    subdirs.sort();
+
+    let mut out = Vec::new();
+    for subdir in subdirs {
+        for stem in layer_stems(&kind_dir.join(&subdir), ext, &HashSet::new())? {
+            out.push(format!("{subdir}/{stem}"));
+        }
+    }
+    Ok(out)
+}
+
 /// Format available template files for display. "all" lists every category with headers;
 /// a specific category lists just its names.
 pub fn list_available(category: &str, clone_dir: &Path) -> Result<String> {
     let mut output = String::new();
 
     if category == "all" {
+        // Template metadata header, when the manifest declares any.
+        let manifest = TemplateManifest::load(clone_dir)?;
+        if let Some(name) = &manifest.name {
+            output.push_str(&format!("template: {name}\n"));
+            if let Some(description) = &manifest.description {
+                output.push_str(&format!("  {description}\n"));
+            }
+            if !manifest.maintainers.is_empty() {
+                output.push_str(&format!(
+                    "  maintainers: {}\n",
+                    manifest.maintainers.join(", ")
+                ));
+            }
+            output.push('\n');
+        }
         let mut first = true;
         for &cat in LIST_CATEGORIES {
             let names = list_category(cat, clone_dir)?;
@@ -1768,13 +4755,16 @@ pub fn list_available(category: &str, clone_dir: &Path) -> Result<String> {
 // ── Orchestration ────────────────────────────────────────────────────────
 
 /// Inputs to the render pipeline. `setup` carries the user's latest-merged
-/// command (positive flags + drops). `sticky_mcp` / `sticky_hooks` carry
-/// contributor stems pulled from `lockfile.resolved` that survived the
-/// pre-render name-stale pass (empty for initial setup).
+/// command (positive flags + drops). `sticky_mcp` / `sticky_hooks` /
+/// `sticky_permissions` carry contributor stems pulled from
+/// `lockfile.resolved` that survived the pre-render name-stale pass (empty for
+/// initial setup). `placeholders` is shared by every render of the invocation.
 pub struct RenderInputs<'a> {
     pub setup: &'a SetupArgs,
     pub sticky_mcp: &'a [String],
     pub sticky_hooks: &'a [String],
+    pub sticky_permissions: &'a [String],
+    pub placeholders: &'a Placeholders,
 }
 
 /// What `run_setup` produces in addition to writing files, for the caller to
@@ -1782,8 +4772,27 @@ pub struct RenderInputs<'a> {
 #[derive(Debug, Clone)]
 pub struct SetupOutcome {
     pub resolved_languages: Vec<String>,
+    pub resolved_tags: Vec<String>,
     pub mcp_snapshottable_stems: Vec<String>,
     pub hooks_snapshottable_stems: Vec<String>,
+    pub permissions_snapshottable_stems: Vec<String>,
+    /// Values substituted for `${NAME}` placeholders in `.mcp.json`. Never
+    /// persisted; `hash_mcp_json` uses them to undo the substitution.
+    pub placeholder_values: BTreeMap<String, String>,
+    /// The assembled `mcpServers` map before placeholder substitution, for
+    /// `Lockfile::mcp_servers`.
+    pub mcp_servers: Map<String, Value>,
+    /// The rendered settings files (`.claude/settings.local.json` and, when
+    /// any part is shared, `.claude/settings.json`) keyed by path relative to
+    /// `dest_dir`, for `Lockfile::settings`.
+    pub settings: BTreeMap<String, Value>,
+}
+
+impl SetupOutcome {
+    /// Conditional subdirectories this render used; see `conditional_subdirs`.
+    pub fn conditional_subdirs(&self) -> Vec<String> {
+        conditional_subdirs(&self.resolved_languages, &self.resolved_tags)
+    }
 }
 
 /// Drive the full clemp pipeline: clone-dir prep → conflict check → write to
@@ -1796,6 +4805,10 @@ pub struct SetupOutcome {
 /// * `install_git_hooks` — when `true`, git hooks are written under
 ///   `dest_dir/.git/hooks`. Callers that target a real CWD should gate this on
 ///   the presence of a `.git/` directory themselves.
+///
+/// Callers writing several renders (a workspace root and its packages) use
+/// `stage_setup`, `overwrite_conflicts` and `StagedSetup::apply` directly, so
+/// every conflict is found before anything is written.
 pub fn run_setup(
     inputs: &RenderInputs,
     clone_dir: &Path,
@@ -1803,31 +4816,217 @@ pub fn run_setup(
     check_conflicts: bool,
     install_git_hooks: bool,
 ) -> Result<SetupOutcome> {
+    let staged = stage_setup(
+        inputs,
+        clone_dir,
+        dest_dir,
+        check_conflicts,
+        install_git_hooks,
+    )?;
+    overwrite_conflicts(&[&staged], inputs.setup.force)?;
+    staged.apply()
+}
+
+/// A render prepared in its clone dir by `stage_setup`, together with the
+/// existing paths in `dest_dir` it would overwrite. Nothing in `dest_dir` has
+/// been touched yet; `apply` writes it.
+pub struct StagedSetup {
+    clone_dir: PathBuf,
+    dest_dir: PathBuf,
+    conditional: Vec<String>,
+    githooks: Vec<String>,
+    install_git_hooks: bool,
+    conflicts: Vec<PathBuf>,
+    merged_shared_settings: Option<Value>,
+    substituted_mcp: Option<Value>,
+    outcome: SetupOutcome,
+}
+
+impl StagedSetup {
+    /// Existing paths in `dest_dir` this render would overwrite.
+    pub fn conflicts(&self) -> &[PathBuf] {
+        &self.conflicts
+    }
+
+    /// Write the staged render into `dest_dir`. Conflicts must have been
+    /// cleared by `overwrite_conflicts` first.
+    pub fn apply(self) -> Result<SetupOutcome> {
+        let Self {
+            clone_dir,
+            dest_dir,
+            conditional,
+            ..
+        } = &self;
+
+        println!("Updating .gitignore...");
+        update_gitignore(clone_dir, dest_dir, conditional)?;
+
+        println!("Copying files...");
+        copy_files(clone_dir, dest_dir)?;
+        if let Some(merged) = &self.merged_shared_settings {
+            fs::write(
+                dest_dir.join(SHARED_SETTINGS_PATH),
+                serde_json::to_string_pretty(merged)?,
+            )?;
+        }
+        if let Some(substituted) = &self.substituted_mcp {
+            fs::write(
+                dest_dir.join(".mcp.json"),
+                serde_json::to_string_pretty(substituted)?,
+            )?;
+        }
+
+        println!("Copying language- and tag-specific files...");
+        copy_conditional_dir(&clone_dir.join("copied"), conditional, dest_dir)?;
+
+        let githooks_dir = clone_dir.join("githooks");
+        if self.install_git_hooks {
+            if githooks_dir.exists() || !self.githooks.is_empty() {
+                println!("Installing git hooks...");
+                let git_hooks_dest = dest_dir.join(".git/hooks");
+                copy_conditional_githooks(&githooks_dir, conditional, &git_hooks_dest)?;
+                copy_named_githooks(&self.githooks, clone_dir, &git_hooks_dest)?;
+            }
+        } else if !self.githooks.is_empty() || githooks_dir.join("default").is_dir() {
+            eprintln!("Warning: git hooks not installed (no .git/ directory in target)");
+        }
+
+        Ok(self.outcome)
+    }
+}
+
+/// Clear the conflicts of every staged render before any of them is
+/// applied. Without `force` this fails listing them all; with it, the user
+/// confirms once and the conflicting paths are removed.
+pub fn overwrite_conflicts(staged: &[&StagedSetup], force: bool) -> Result<()> {
+    let conflicts: Vec<&PathBuf> = staged.iter().flat_map(|s| &s.conflicts).collect();
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    let names: Vec<_> = conflicts.iter().map(|p| p.display().to_string()).collect();
+
+    if !force {
+        bail!(
+            "The following files/directories already exist and would be overwritten:\n  {}\nRemove them first, run from a clean directory, or use --force.\n\nIf this is a previously clemp-configured project, try `clemp update` instead.",
+            names.join("\n  ")
+        );
+    }
+
+    println!(
+        "The following files/directories will be overwritten:\n  {}",
+        names.join("\n  ")
+    );
+    if !confirm("Proceed?")? {
+        bail!("Aborted.");
+    }
+    for path in conflicts {
+        if path.is_dir() {
+            fs::remove_dir_all(path)?;
+        } else {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Phases 1 and 2 of `run_setup`: render into `clone_dir` and, when
+/// `check_conflicts` is set, collect what the render would overwrite in
+/// `dest_dir`. Only `clone_dir` is written.
+pub fn stage_setup(
+    inputs: &RenderInputs,
+    clone_dir: &Path,
+    dest_dir: &Path,
+    check_conflicts: bool,
+    install_git_hooks: bool,
+) -> Result<StagedSetup> {
     let args = inputs.setup;
 
     // ── Phase 1: clone_dir prep (no dest_dir mutations) ─────────────────
 
     println!("Resolving languages...");
-    let resolved_languages = resolve_all_languages(&args.languages, clone_dir)?;
+    let (resolved_languages, _) =
+        resolve_languages_explained(&args.languages, &args.drop_lang, clone_dir)?;
+    let resolved_tags = resolve_tags(&args.tags, &args.drop_tags, clone_dir)?;
+    let conditional = conditional_subdirs(&resolved_languages, &resolved_tags);
 
     let mcp_excluded: HashSet<String> = args.drop_mcp.iter().cloned().collect();
     let hooks_excluded: HashSet<String> = args.drop_hooks.iter().cloned().collect();
+    let permissions_excluded: HashSet<String> = args.drop_permissions.iter().cloned().collect();
+
+    // `mcp/` templates see the contributors about to be assembled as
+    // `mcp_contributors`; hooks and settings templates below see the
+    // assembled server names as `mcp`.
+    let mcp_dir = clone_dir.join("mcp");
+    let mut mcp_contributors = Vec::new();
+    for layer in std::iter::once("default").chain(conditional.iter().map(|c| c.as_str())) {
+        mcp_contributors.extend(layer_stems(&mcp_dir.join(layer), "json", &mcp_excluded)?);
+    }
+    mcp_contributors.extend(
+        args.mcp
+            .iter()
+            .chain(inputs.sticky_mcp)
+            .filter(|s| !mcp_excluded.contains(*s))
+            .cloned(),
+    );
+    render_json_templates(
+        &mcp_dir,
+        &conditional,
+        clone_dir,
+        &mcp_template_context(&resolved_languages, &resolved_tags, &mcp_contributors),
+    )?;
 
     println!("Assembling MCP servers...");
     let mcp_result = assemble_mcp_json(
-        &resolved_languages,
+        &conditional,
         &args.mcp,
         inputs.sticky_mcp,
         &mcp_excluded,
+        !args.no_validate,
         clone_dir,
     )?;
+    for w in &mcp_result.warnings {
+        eprintln!("Warning: {w}");
+    }
+    let unexpected: Vec<&ServerShadow> =
+        mcp_result.shadowed.iter().filter(|s| !s.intended).collect();
+    if args.strict_mcp && !unexpected.is_empty() {
+        let lines: Vec<String> = unexpected
+            .iter()
+            .map(|s| format!("'{}' in {} is shadowed by {}", s.key, s.shadowed, s.winner))
+            .collect();
+        bail!(
+            "MCP contributors define the same server (--strict-mcp):\n  {}",
+            lines.join("\n  ")
+        );
+    }
+    for s in unexpected {
+        eprintln!(
+            "Warning: MCP server '{}' in {} is shadowed by {}",
+            s.key, s.shadowed, s.winner
+        );
+    }
     fs::write(
         clone_dir.join(".mcp.json"),
         serde_json::to_string_pretty(&mcp_result.rendered)?,
     )?;
 
+    // Placeholder values live in the project root's environment / `.env`,
+    // even when rendering into a staging dir or a workspace package.
+    let placeholders = mcp_placeholders(&mcp_result.rendered);
+    let placeholder_values = if placeholders.is_empty() {
+        BTreeMap::new()
+    } else {
+        println!("Resolving MCP placeholders...");
+        inputs.placeholders.resolve(&placeholders)?
+    };
+
     println!("Rendering CLAUDE.md...");
-    let claude_md = render_claude_md(&resolved_languages, &mcp_result.rendered_keys, clone_dir)?;
+    let claude_md = render_claude_md(
+        &resolved_languages,
+        &resolved_tags,
+        &mcp_result.rendered_keys,
+        clone_dir,
+    )?;
     fs::write(clone_dir.join("CLAUDE.md"), claude_md)?;
 
     let clarg_name = args.clarg.clone().or_else(|| {
@@ -1840,16 +5039,51 @@ pub fn run_setup(
         vec![]
     };
 
+    let rendered_ctx = template_context(
+        &resolved_languages,
+        &resolved_tags,
+        &mcp_result.rendered_keys,
+    );
+    for dir in ["hooks", "permissions"] {
+        render_json_templates(&clone_dir.join(dir), &conditional, clone_dir, &rendered_ctx)?;
+    }
+    render_json_templates(
+        &clone_dir.join("settings.local.json.jinja"),
+        &[],
+        clone_dir,
+        &rendered_ctx,
+    )?;
+
     println!("Assembling hooks...");
     let hooks_result = assemble_hooks_json(
+        &conditional,
         &args.hooks,
         inputs.sticky_hooks,
         &hooks_excluded,
         clone_dir,
     )?;
 
+    println!("Assembling permissions...");
+    let permissions_result = assemble_permissions_json(
+        &conditional,
+        &args.permissions,
+        inputs.sticky_permissions,
+        &permissions_excluded,
+        clone_dir,
+    )?;
+
     println!("Building settings...");
-    build_settings(&hooks_result, &clarg_entries, &mcp_result.rendered_keys, clone_dir)?;
+    let shared_settings =
+        resolve_shared_settings(&args.shared_settings, &args.local_settings, clone_dir)?;
+    let settings = build_settings(
+        &hooks_result,
+        &permissions_result,
+        &clarg_entries,
+        &mcp_result.rendered_keys,
+        &conditional,
+        &shared_settings,
+        clone_dir,
+    )?;
 
     if clarg_name.is_some() {
         check_clarg_installed();
@@ -1858,7 +5092,7 @@ pub fn run_setup(
     println!("Assembling commands...");
     copy_conditional_dir(
         &clone_dir.join("commands"),
-        &resolved_languages,
+        &conditional,
         &clone_dir.join(".claude/commands"),
     )?;
     copy_named_commands(&args.commands, clone_dir)?;
@@ -1866,7 +5100,7 @@ pub fn run_setup(
     println!("Assembling skills...");
     copy_conditional_dir(
         &clone_dir.join("skills"),
-        &resolved_languages,
+        &conditional,
         &clone_dir.join(".claude/skills"),
     )?;
 
@@ -1875,17 +5109,43 @@ pub fn run_setup(
     let githooks_dir = clone_dir.join("githooks");
     let git_hooks_dest = dest_dir.join(".git/hooks");
 
+    let mut collected = Vec::new();
+    let mut merged_shared_settings = None;
     if check_conflicts {
         println!("Checking for conflicts...");
         let mut all_cwd_targets = collect_copy_files_sources(clone_dir)?;
         all_cwd_targets.extend(collect_conditional_dir_sources(
             &clone_dir.join("copied"),
-            &resolved_languages,
+            &conditional,
         ));
         let mut conflicts = collect_conflicts(&all_cwd_targets, dest_dir);
 
-        let mut githooks_sources =
-            collect_conditional_dir_sources(&githooks_dir, &resolved_languages);
+        // A project's own `.claude/` is checked entry by entry, and a
+        // `.claude/settings.json` it already has merges with the shared one.
+        let claude_dest = dest_dir.join(".claude");
+        if claude_dest.is_dir() && conflicts.contains(&claude_dest) {
+            conflicts.retain(|p| *p != claude_dest);
+            let staged = fs::read_dir(clone_dir.join(".claude"))?
+                .map(|e| e.map(|e| e.path()))
+                .collect::<io::Result<Vec<_>>>()?;
+            conflicts.extend(collect_conflicts(&staged, &claude_dest));
+        }
+        let shared_dest = dest_dir.join(SHARED_SETTINGS_PATH);
+        if conflicts.contains(&shared_dest) {
+            match merge_existing_shared_settings(&shared_dest, clone_dir)? {
+                Ok(merged) => {
+                    conflicts.retain(|p| *p != shared_dest);
+                    merged_shared_settings = Some(merged);
+                }
+                Err(keys) if !args.force => bail!(
+                    "{SHARED_SETTINGS_PATH} already exists and conflicts with the template's on:\n  {}\nResolve these keys first, or use --force to replace the file.",
+                    keys.join("\n  ")
+                ),
+                Err(_) => {}
+            }
+        }
+
+        let mut githooks_sources = collect_conditional_dir_sources(&githooks_dir, &conditional);
         for name in &args.githooks {
             let src = githooks_dir.join(name);
             if src.is_file() {
@@ -1896,68 +5156,44 @@ pub fn run_setup(
             conflicts.extend(collect_conflicts(&githooks_sources, &git_hooks_dest));
         }
 
-        if !conflicts.is_empty() {
-            let names: Vec<_> = conflicts.iter().map(|p| p.display().to_string()).collect();
-
-            if !args.force {
-                bail!(
-                    "The following files/directories already exist and would be overwritten:\n  {}\nRemove them first, run from a clean directory, or use --force.\n\nIf this is a previously clemp-configured project, try `clemp update` instead.",
-                    names.join("\n  ")
-                );
-            }
-
-            println!(
-                "The following files/directories will be overwritten:\n  {}",
-                names.join("\n  ")
-            );
-            if !confirm("Proceed?")? {
-                bail!("Aborted.");
-            }
-            for path in &conflicts {
-                if path.is_dir() {
-                    fs::remove_dir_all(path)?;
-                } else {
-                    fs::remove_file(path)?;
-                }
-            }
-        }
-    }
-
-    // ── Phase 3: dest_dir mutations ─────────────────────────────────────
-
-    println!("Updating .gitignore...");
-    update_gitignore(clone_dir, dest_dir, &resolved_languages)?;
-
-    println!("Copying files...");
-    copy_files(clone_dir, dest_dir)?;
-
-    println!("Copying language-specific files...");
-    copy_conditional_dir(&clone_dir.join("copied"), &resolved_languages, dest_dir)?;
-
-    if install_git_hooks {
-        if githooks_dir.exists() || !args.githooks.is_empty() {
-            println!("Installing git hooks...");
-            copy_conditional_githooks(&githooks_dir, &resolved_languages, &git_hooks_dest)?;
-            copy_named_githooks(&args.githooks, clone_dir, &git_hooks_dest)?;
-        }
-    } else if !args.githooks.is_empty() || githooks_dir.join("default").is_dir() {
-        eprintln!("Warning: git hooks not installed (no .git/ directory in target)");
+        collected = conflicts;
     }
 
-    Ok(SetupOutcome {
-        resolved_languages,
-        mcp_snapshottable_stems: mcp_result.snapshottable_stems,
-        hooks_snapshottable_stems: hooks_result.snapshottable_stems,
+    let substituted_mcp = (!placeholder_values.is_empty())
+        .then(|| substitute_placeholders(&mcp_result.rendered, &placeholder_values));
+    Ok(StagedSetup {
+        clone_dir: clone_dir.to_path_buf(),
+        dest_dir: dest_dir.to_path_buf(),
+        conditional,
+        githooks: args.githooks.clone(),
+        install_git_hooks,
+        conflicts: collected,
+        merged_shared_settings,
+        substituted_mcp,
+        outcome: SetupOutcome {
+            resolved_languages,
+            resolved_tags,
+            mcp_snapshottable_stems: mcp_result.snapshottable_stems,
+            hooks_snapshottable_stems: hooks_result.snapshottable_stems,
+            permissions_snapshottable_stems: permissions_result.snapshottable_stems,
+            placeholder_values,
+            mcp_servers: mcp_result.rendered["mcpServers"]
+                .as_object()
+                .cloned()
+                .unwrap_or_default(),
+            settings,
+        },
     })
 }
 
 /// Enumerate every file clemp wrote under `dest_dir` (derived from `clone_dir`'s
 /// staged tree + conditional dirs + git hooks) and compute its SHA-256.
 /// Excludes `.gitignore` and the lockfile itself. Paths in the returned map are
-/// normalized to forward-slash form.
+/// normalized to forward-slash form. `conditional` is the render's
+/// `SetupOutcome::conditional_subdirs` (resolved languages, then tags).
 pub fn compute_manifest(
     args: &SetupArgs,
-    resolved_languages: &[String],
+    conditional: &[String],
     clone_dir: &Path,
     dest_dir: &Path,
 ) -> Result<BTreeMap<String, String>> {
@@ -1970,17 +5206,23 @@ pub fn compute_manifest(
         if COPY_FILES_EXCLUDE.contains(&name.to_string_lossy().as_ref()) {
             continue;
         }
+        // `.mcp.json` is hashed before placeholder substitution, i.e. as
+        // staged in the clone, so secrets never read as local edits.
+        if name == ".mcp.json" && dest_dir.join(&name).is_file() {
+            manifest.insert(".mcp.json".into(), hash_file(&entry.path())?);
+            continue;
+        }
         let rel_start = PathBuf::from(&name);
-        hash_tree_into_manifest(dest_dir, &rel_start, &mut manifest)?;
+        hash_tree_into_manifest(clone_dir, dest_dir, &rel_start, &mut manifest)?;
     }
 
-    // 2. copied/default/ and copied/<lang>/ flattened under dest_dir.
+    // 2. copied/default/, copied/<lang>/ and copied/tags/<tag>/ flattened under dest_dir.
     let copied_dir = clone_dir.join("copied");
     let mut overlay_dirs: Vec<PathBuf> = Vec::new();
     if copied_dir.join("default").is_dir() {
         overlay_dirs.push(copied_dir.join("default"));
     }
-    for lang in resolved_languages {
+    for lang in conditional {
         let ld = copied_dir.join(lang);
         if ld.is_dir() {
             overlay_dirs.push(ld);
@@ -1990,7 +5232,7 @@ pub fn compute_manifest(
         for entry in fs::read_dir(overlay)? {
             let entry = entry?;
             let rel_start = PathBuf::from(entry.file_name());
-            hash_tree_into_manifest(dest_dir, &rel_start, &mut manifest)?;
+            hash_tree_into_manifest(overlay, dest_dir, &rel_start, &mut manifest)?;
         }
     }
 
@@ -2010,7 +5252,7 @@ pub fn compute_manifest(
         Ok(())
     };
     collect_names(&githooks_src.join("default"))?;
-    for lang in resolved_languages {
+    for lang in conditional {
         collect_names(&githooks_src.join(lang))?;
     }
     for name in &args.githooks {
@@ -2031,7 +5273,10 @@ pub fn compute_manifest(
     Ok(manifest)
 }
 
+/// Hash every file under `dest_dir/rel_start` that `source_dir/rel_start`
+/// put there, leaving anything else the project keeps alongside untracked.
 fn hash_tree_into_manifest(
+    source_dir: &Path,
     dest_dir: &Path,
     rel_start: &Path,
     manifest: &mut BTreeMap<String, String>,
@@ -2040,36 +5285,146 @@ fn hash_tree_into_manifest(
     if !full.exists() {
         return Ok(());
     }
-    if full.is_file() {
-        manifest.insert(lockfile_key(rel_start), hash_file(&full)?);
+    let src = source_dir.join(rel_start);
+    if full.is_file() || !src.is_dir() {
+        if full.is_file() {
+            manifest.insert(lockfile_key(rel_start), hash_file(&full)?);
+        }
         return Ok(());
     }
-    for entry in fs::read_dir(&full)? {
+    for entry in fs::read_dir(&src)? {
         let entry = entry?;
         let sub_rel = rel_start.join(entry.file_name());
-        hash_tree_into_manifest(dest_dir, &sub_rel, manifest)?;
+        hash_tree_into_manifest(source_dir, dest_dir, &sub_rel, manifest)?;
     }
     Ok(())
 }
 
-/// Split values on whitespace in addition to clap's comma delimiter.
-pub fn split_multi_values(values: Vec<String>) -> Vec<String> {
-    values
-        .iter()
-        .flat_map(|v| v.split_whitespace())
-        .map(String::from)
-        .collect()
+/// Split values on whitespace in addition to clap's comma delimiter.
+pub fn split_multi_values(values: Vec<String>) -> Vec<String> {
+    values
+        .iter()
+        .flat_map(|v| v.split_whitespace())
+        .map(String::from)
+        .collect()
+}
+
+/// Normalize all multi-value fields in-place (replaces clap's comma-only split
+/// with comma+whitespace splitting).
+pub fn normalize_setup_args(args: &mut SetupArgs) {
+    args.hooks = split_multi_values(std::mem::take(&mut args.hooks));
+    args.mcp = split_multi_values(std::mem::take(&mut args.mcp));
+    args.commands = split_multi_values(std::mem::take(&mut args.commands));
+    args.githooks = split_multi_values(std::mem::take(&mut args.githooks));
+    args.drop_mcp = split_multi_values(std::mem::take(&mut args.drop_mcp));
+    args.drop_hooks = split_multi_values(std::mem::take(&mut args.drop_hooks));
+    args.permissions = split_multi_values(std::mem::take(&mut args.permissions));
+    args.drop_permissions = split_multi_values(std::mem::take(&mut args.drop_permissions));
+    args.drop_lang = split_multi_values(std::mem::take(&mut args.drop_lang));
+    args.tags = split_multi_values(std::mem::take(&mut args.tags))
+        .into_iter()
+        .map(|t| t.to_lowercase())
+        .collect();
+    args.drop_tags = split_multi_values(std::mem::take(&mut args.drop_tags))
+        .into_iter()
+        .map(|t| t.to_lowercase())
+        .collect();
+}
+
+// ── Workspaces ───────────────────────────────────────────────────────────
+
+/// Pristine copies of the template, one per workspace package. `run_setup`
+/// writes its generated files into the clone it renders from, so every
+/// package needs its own copy taken before anything is rendered. Removed on
+/// drop.
+pub struct PackageTemplates {
+    dir: tempfile::TempDir,
+}
+
+impl PackageTemplates {
+    /// Copy `clone_dir` once per package into a temp dir beside it.
+    pub fn new<'a>(
+        clone_dir: &Path,
+        packages: impl IntoIterator<Item = &'a String>,
+    ) -> Result<Self> {
+        let parent = clone_dir.parent().unwrap_or(Path::new("."));
+        let dir = tempfile::Builder::new()
+            .prefix("clemp-packages-")
+            .tempdir_in(parent)
+            .context("Failed to create package template dir")?;
+        let templates = Self { dir };
+        for package in packages {
+            copy_dir_recursive(clone_dir, &templates.path(package))?;
+        }
+        Ok(templates)
+    }
+
+    /// The template copy reserved for `package`.
+    pub fn path(&self, package: &str) -> PathBuf {
+        self.dir.path().join(package)
+    }
+}
+
+/// Render one workspace package into `<dest_dir>/<package>/` from its
+/// pristine template copy. Returns the outcome and the package's manifest
+/// keyed relative to `dest_dir` (i.e. prefixed with `<package>/`), ready to
+/// merge into the root manifest. Git hooks are only ever installed at the root.
+pub fn render_package(
+    package: &str,
+    inputs: &RenderInputs,
+    template: &Path,
+    dest_dir: &Path,
+    check_conflicts: bool,
+) -> Result<(SetupOutcome, BTreeMap<String, String>)> {
+    let staged = stage_package(package, inputs, template, dest_dir, check_conflicts)?;
+    overwrite_conflicts(&[&staged], inputs.setup.force)?;
+    apply_package(package, staged, inputs.setup)
+}
+
+/// `stage_setup` for one workspace package, rendering into its template copy
+/// and checking `<dest_dir>/<package>/` for conflicts.
+pub fn stage_package(
+    package: &str,
+    inputs: &RenderInputs,
+    template: &Path,
+    dest_dir: &Path,
+    check_conflicts: bool,
+) -> Result<StagedSetup> {
+    println!("\nSetting up package {package}/...");
+    stage_setup(
+        inputs,
+        template,
+        &dest_dir.join(package),
+        check_conflicts,
+        false,
+    )
+    .with_context(|| format!("Failed to set up package '{package}'"))
 }
 
-/// Normalize all multi-value fields in-place (replaces clap's comma-only split
-/// with comma+whitespace splitting).
-pub fn normalize_setup_args(args: &mut SetupArgs) {
-    args.hooks = split_multi_values(std::mem::take(&mut args.hooks));
-    args.mcp = split_multi_values(std::mem::take(&mut args.mcp));
-    args.commands = split_multi_values(std::mem::take(&mut args.commands));
-    args.githooks = split_multi_values(std::mem::take(&mut args.githooks));
-    args.drop_mcp = split_multi_values(std::mem::take(&mut args.drop_mcp));
-    args.drop_hooks = split_multi_values(std::mem::take(&mut args.drop_hooks));
+/// Write a package staged by `stage_package` and compute its manifest, as
+/// `render_package` returns them.
+pub fn apply_package(
+    package: &str,
+    staged: StagedSetup,
+    args: &SetupArgs,
+) -> Result<(SetupOutcome, BTreeMap<String, String>)> {
+    println!("\nWriting package {package}/...");
+    let template = staged.clone_dir.clone();
+    let package_dir = staged.dest_dir.clone();
+    fs::create_dir_all(&package_dir)?;
+    let outcome = staged
+        .apply()
+        .with_context(|| format!("Failed to set up package '{package}'"))?;
+    let manifest = compute_manifest(
+        args,
+        &outcome.conditional_subdirs(),
+        &template,
+        &package_dir,
+    )?
+    .into_iter()
+    .map(|(path, hash)| (format!("{package}/{path}"), hash))
+    .collect();
+    Ok((outcome, manifest))
 }
 
 // ── Update flow ──────────────────────────────────────────────────────────
@@ -2206,138 +5561,654 @@ pub fn classify_update_path(
     }
 }
 
+/// Three-way merge of `mcpServers` maps, server by server. `base` is what clemp
+/// last rendered, `ours` the user's file (placeholders restored) and `theirs`
+/// the new render. A server changed on one side only takes that side's entry,
+/// removal included; one changed differently on both sides is a conflict.
+/// Returns the merged map, or the conflicting server keys.
+pub fn merge_mcp_servers(
+    base: &Map<String, Value>,
+    ours: &Map<String, Value>,
+    theirs: &Map<String, Value>,
+) -> std::result::Result<Map<String, Value>, Vec<String>> {
+    let keys: BTreeSet<&String> = base
+        .keys()
+        .chain(ours.keys())
+        .chain(theirs.keys())
+        .collect();
+    let mut merged = Map::new();
+    let mut conflicts = Vec::new();
+    for key in keys {
+        let (b, o, t) = (base.get(key), ours.get(key), theirs.get(key));
+        let pick = if o == t || b == t {
+            o
+        } else if b == o {
+            t
+        } else {
+            conflicts.push(key.clone());
+            continue;
+        };
+        if let Some(entry) = pick {
+            merged.insert(key.clone(), entry.clone());
+        }
+    }
+    if conflicts.is_empty() {
+        Ok(merged)
+    } else {
+        Err(conflicts)
+    }
+}
+
+/// Three-way merge of settings documents, key by key. `base` is what clemp
+/// last rendered, `ours` the user's file and `theirs` the new render. Objects
+/// recurse; lists of strings (`permissions.allow`, `enabledMcpjsonServers`)
+/// merge as sets, so additions and removals from either side both land. Hook
+/// lists merge entry by entry, keyed by `matcher`; other lists of objects
+/// merge as a whole. A value changed differently on both sides is a conflict.
+/// Returns the merged settings, or the dotted paths of the conflicting keys.
+pub fn merge_settings_three_way(
+    base: &Value,
+    ours: &Value,
+    theirs: &Value,
+) -> std::result::Result<Value, Vec<String>> {
+    let mut conflicts = Vec::new();
+    let merged = merge_value_three_way(Some(base), Some(ours), Some(theirs), "", &mut conflicts);
+    if conflicts.is_empty() {
+        Ok(merged.unwrap_or_else(|| Value::Object(Map::new())))
+    } else {
+        Err(conflicts)
+    }
+}
+
+/// Hook-list entries keyed by their `matcher`, in order. `None` unless every
+/// entry is an object with a distinct string `matcher`.
+fn by_matcher(entries: &[Value]) -> Option<Vec<(&str, &Value)>> {
+    let mut keyed: Vec<(&str, &Value)> = Vec::new();
+    for entry in entries {
+        let matcher = entry.get("matcher")?.as_str()?;
+        if keyed.iter().any(|(m, _)| *m == matcher) {
+            return None;
+        }
+        keyed.push((matcher, entry));
+    }
+    Some(keyed)
+}
+
+fn merge_value_three_way(
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    path: &str,
+    conflicts: &mut Vec<String>,
+) -> Option<Value> {
+    if ours == theirs || base == theirs {
+        return ours.cloned();
+    }
+    if base == ours {
+        return theirs.cloned();
+    }
+    match (base, ours, theirs) {
+        (None | Some(Value::Object(_)), Some(Value::Object(o)), Some(Value::Object(t))) => {
+            let empty = Map::new();
+            let b = base.and_then(Value::as_object).unwrap_or(&empty);
+            let keys: BTreeSet<&String> = b.keys().chain(o.keys()).chain(t.keys()).collect();
+            let mut merged = Map::new();
+            for key in keys {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                if let Some(value) =
+                    merge_value_three_way(b.get(key), o.get(key), t.get(key), &child, conflicts)
+                {
+                    merged.insert(key.clone(), value);
+                }
+            }
+            Some(Value::Object(merged))
+        }
+        (None | Some(Value::Array(_)), Some(Value::Array(o)), Some(Value::Array(t))) => {
+            let empty = Vec::new();
+            let b = base.and_then(Value::as_array).unwrap_or(&empty);
+            if b.iter()
+                .chain(o)
+                .chain(t)
+                .all(|v| !v.is_object() && !v.is_array())
+            {
+                let mut merged: Vec<Value> = o
+                    .iter()
+                    .filter(|v| !b.contains(v) || t.contains(v))
+                    .cloned()
+                    .collect();
+                for value in t {
+                    if !b.contains(value) && !merged.contains(value) {
+                        merged.push(value.clone());
+                    }
+                }
+                return Some(Value::Array(merged));
+            }
+            let (Some(b), Some(o), Some(t)) = (by_matcher(b), by_matcher(o), by_matcher(t)) else {
+                conflicts.push(path.to_string());
+                return ours.cloned();
+            };
+            fn get<'a>(entries: &[(&str, &'a Value)], m: &str) -> Option<&'a Value> {
+                entries.iter().find(|(key, _)| *key == m).map(|(_, v)| *v)
+            }
+            let mut matchers: Vec<&str> = o.iter().map(|(m, _)| *m).collect();
+            matchers.extend(t.iter().map(|(m, _)| *m).filter(|m| get(&o, m).is_none()));
+            let merged = matchers
+                .into_iter()
+                .filter_map(|m| {
+                    let child = format!("{path}[{m}]");
+                    merge_value_three_way(get(&b, m), get(&o, m), get(&t, m), &child, conflicts)
+                })
+                .collect();
+            Some(Value::Array(merged))
+        }
+        _ => {
+            conflicts.push(path.to_string());
+            ours.cloned()
+        }
+    }
+}
+
+/// Structural three-way merge of a conflicting `.mcp.json` (server by server)
+/// or settings file (key by key) against what the `previous` lockfile
+/// recorded for that path and the new render's `mcp_servers` / `settings`. A
+/// settings file clemp never wrote before (the user already had one) merges
+/// against an empty base. `None` when the path isn't one of these, has no
+/// recorded base, or the user's file isn't a JSON object; otherwise the
+/// document to write or the conflicting keys.
+fn merge_rendered_json(
+    path: &str,
+    previous: &Lockfile,
+    mcp_servers: &BTreeMap<String, Map<String, Value>>,
+    settings: &BTreeMap<String, Value>,
+    placeholder_values: &BTreeMap<String, String>,
+    cwd: &Path,
+) -> Result<Option<std::result::Result<Value, Vec<String>>>> {
+    let content = fs::read_to_string(cwd.join(path))?;
+    let Ok(ours @ Value::Object(_)) = serde_json::from_str::<Value>(&content) else {
+        return Ok(None);
+    };
+    if Path::new(path).ends_with(".mcp.json") {
+        let (Some(base), Some(theirs)) = (previous.mcp_servers.get(path), mcp_servers.get(path))
+        else {
+            return Ok(None);
+        };
+        let mut ours =
+            unsubstitute_placeholders(&ours, &mcp_json_template(Some(base)), placeholder_values);
+        let our_servers = ours["mcpServers"].as_object().cloned().unwrap_or_default();
+        return Ok(Some(merge_mcp_servers(base, &our_servers, theirs).map(
+            |servers| {
+                ours["mcpServers"] = Value::Object(servers);
+                substitute_placeholders(&ours, placeholder_values)
+            },
+        )));
+    }
+    if Path::new(path).ends_with(SETTINGS_PATH) || Path::new(path).ends_with(SHARED_SETTINGS_PATH) {
+        let untracked = Value::Object(Map::new());
+        let base = previous
+            .settings
+            .get(path)
+            .or_else(|| (!previous.files.contains_key(path)).then_some(&untracked));
+        let (Some(base), Some(theirs)) = (base, settings.get(path)) else {
+            return Ok(None);
+        };
+        return Ok(Some(merge_settings_three_way(base, &ours, theirs)));
+    }
+    Ok(None)
+}
+
+/// Outcome of the name-level stale pass for one render (the root project or a
+/// workspace package).
+struct ContributorPartition {
+    sticky_mcp: Vec<String>,
+    stale_mcp: Vec<String>,
+    sticky_hooks: Vec<String>,
+    stale_hooks: Vec<String>,
+    sticky_permissions: Vec<String>,
+    stale_permissions: Vec<String>,
+}
+
+impl ContributorPartition {
+    /// Strip accepted/pruned stale stems from the persisted command so
+    /// `setup_args.<kind>` (derived from it) no longer carries them into the
+    /// assembler's user_named path — where they would bail with "MCP/Hook/
+    /// Permission not found" after their contributor disappeared upstream. The stems are
+    /// already gone from `sticky_<kind>`; this closes the symmetric hole in the
+    /// explicit/historical list.
+    fn strip_stale(&self, command: &mut OriginalCommand) {
+        command.mcp.retain(|s| !self.stale_mcp.contains(s));
+        command.hooks.retain(|s| !self.stale_hooks.contains(s));
+        command
+            .permissions
+            .retain(|s| !self.stale_permissions.contains(s));
+    }
+}
+
+/// A workspace package on its way through `run_update`.
+struct PackageUpdate {
+    name: String,
+    command: OriginalCommand,
+    partition: ContributorPartition,
+    /// Conditional subdirs the package's previous render had and this one
+    /// doesn't, for its stale-gitignore pass.
+    removed: Vec<String>,
+}
+
+/// Languages and tags `previous` (a lockfile command) rendered that this
+/// render's `languages` / `tags` no longer include — from `--drop-lang`,
+/// `--without` or an upstream `implies:` change.
+fn removed_languages_and_tags(
+    previous: &OriginalCommand,
+    languages: &[String],
+    tags: &[String],
+    aliases: &LanguageAliases,
+    clone_dir: &Path,
+) -> (Vec<String>, Vec<String>) {
+    let prior_languages =
+        resolve_languages_explained(&previous.languages, &previous.drop_lang, clone_dir)
+            .map(|(langs, _)| langs)
+            .unwrap_or_else(|_| {
+                previous
+                    .languages
+                    .iter()
+                    .map(|l| aliases.canonical(l))
+                    .collect()
+            });
+    let prior_tags = resolve_tags(&previous.tags, &previous.drop_tags, clone_dir)
+        .unwrap_or_else(|_| previous.tags.clone());
+    (
+        prior_languages
+            .into_iter()
+            .filter(|l| !languages.contains(l))
+            .collect(),
+        prior_tags
+            .into_iter()
+            .filter(|t| !tags.contains(t))
+            .collect(),
+    )
+}
+
+/// Name-level stale pass: stems from the lockfile snapshot plus historical
+/// opt-ins from `command.<kind>`, minus anything the command drops, probed
+/// against the current template. Stems with no current contributor become
+/// `stale_<kind>`; survivors become `sticky_<kind>` fed into assembly.
+fn partition_contributors(
+    snapshot: Option<&Resolved>,
+    command: &OriginalCommand,
+    conditional: &[String],
+    clone_dir: &Path,
+) -> ContributorPartition {
+    let candidates = |snap: &[String], named: &[String], dropped: &[String]| -> Vec<String> {
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut out: Vec<String> = Vec::new();
+        for s in snap.iter().chain(named.iter()) {
+            if dropped.contains(s) {
+                continue;
+            }
+            if seen.insert(s.clone()) {
+                out.push(s.clone());
+            }
+        }
+        out
+    };
+    let candidate_mcp = candidates(
+        snapshot.map(|r| r.mcp.as_slice()).unwrap_or(&[]),
+        &command.mcp,
+        &command.drop_mcp,
+    );
+    let candidate_hooks = candidates(
+        snapshot.map(|r| r.hooks.as_slice()).unwrap_or(&[]),
+        &command.hooks,
+        &command.drop_hooks,
+    );
+    let candidate_permissions = candidates(
+        snapshot.map(|r| r.permissions.as_slice()).unwrap_or(&[]),
+        &command.permissions,
+        &command.drop_permissions,
+    );
+
+    let (sticky_mcp, stale_mcp): (Vec<String>, Vec<String>) =
+        candidate_mcp.into_iter().partition(|s| {
+            resolve_contributor("mcp", "json", &MCP_LAYERS, s, conditional, clone_dir).is_some()
+        });
+    let (sticky_hooks, stale_hooks): (Vec<String>, Vec<String>) =
+        candidate_hooks.into_iter().partition(|s| {
+            resolve_contributor("hooks", "json", &HOOKS_LAYERS, s, conditional, clone_dir).is_some()
+        });
+    let (sticky_permissions, stale_permissions): (Vec<String>, Vec<String>) =
+        candidate_permissions.into_iter().partition(|s| {
+            resolve_contributor(
+                "permissions",
+                "json",
+                &PERMISSIONS_LAYERS,
+                s,
+                conditional,
+                clone_dir,
+            )
+            .is_some()
+        });
+    ContributorPartition {
+        sticky_mcp,
+        stale_mcp,
+        sticky_hooks,
+        stale_hooks,
+        sticky_permissions,
+        stale_permissions,
+    }
+}
+
+/// Single confirm-or-bail UX for name-stale contributors. Declining bails
+/// with zero side effects so the user can re-run with `--drop-<kind>` or
+/// restore the contributor upstream. `prefix` is the package directory (with
+/// trailing slash) or empty for the root project; `shared` names the settings
+/// parts written to `SHARED_SETTINGS_PATH`.
+fn confirm_stale_contributors(
+    partition: &ContributorPartition,
+    prefix: &str,
+    shared: &[SettingsPart],
+    prune_stale: bool,
+) -> Result<()> {
+    for (kind, flag, stale, output) in [
+        ("MCP", "mcp", &partition.stale_mcp, ".mcp.json"),
+        (
+            "hook",
+            "hooks",
+            &partition.stale_hooks,
+            settings_path(SettingsPart::Hooks, shared),
+        ),
+        (
+            "permission",
+            "permissions",
+            &partition.stale_permissions,
+            settings_path(SettingsPart::Permissions, shared),
+        ),
+    ] {
+        if stale.is_empty() {
+            continue;
+        }
+        if prune_stale {
+            println!(
+                "Dropping {} stale {} contributors: {}",
+                stale.len(),
+                kind,
+                stale.join(", ")
+            );
+        } else {
+            println!(
+                "The template no longer provides these {} contributors: {}",
+                kind,
+                stale.join(", ")
+            );
+            if !confirm(&format!(
+                "They will be removed from {prefix}{output}. Continue?"
+            ))? {
+                bail!(
+                    "Aborted. Re-run with --prune-stale, or --drop-{flag} the stems explicitly, or restore them upstream."
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Tags `previous` (the lockfile's command) carried that the template no
+/// longer provides. Tags new to `command` are left to `resolve_tags`, which
+/// rejects unknown ones as typos.
+fn stale_tags(
+    previous: &OriginalCommand,
+    command: &OriginalCommand,
+    clone_dir: &Path,
+) -> Vec<String> {
+    command
+        .tags
+        .iter()
+        .filter(|tag| {
+            previous.tags.contains(tag)
+                && !command.drop_tags.contains(tag)
+                && resolve_tags(std::slice::from_ref(tag), &[], clone_dir).is_err()
+        })
+        .cloned()
+        .collect()
+}
+
+/// Confirm-or-bail for tags removed upstream, like
+/// `confirm_stale_contributors`. On success the tags are stripped from
+/// `command`, so their outputs fall out of the render and go through the
+/// file-level stale pass.
+fn confirm_stale_tags(
+    command: &mut OriginalCommand,
+    stale: &[String],
+    prefix: &str,
+    prune_stale: bool,
+) -> Result<()> {
+    if stale.is_empty() {
+        return Ok(());
+    }
+    if prune_stale {
+        println!("Dropping {} stale tags: {}", stale.len(), stale.join(", "));
+    } else {
+        println!(
+            "The template no longer provides these tags: {}",
+            stale.join(", ")
+        );
+        let target = if prefix.is_empty() {
+            "the project".to_string()
+        } else {
+            prefix.to_string()
+        };
+        if !confirm(&format!(
+            "Their outputs will be removed from {target}. Continue?"
+        ))? {
+            bail!(
+                "Aborted. Re-run with --prune-stale, or --without the tags explicitly, or restore them upstream."
+            );
+        }
+    }
+    command.tags.retain(|tag| !stale.contains(tag));
+    Ok(())
+}
+
 /// Drive `clemp update`: diff the new template render against the lockfile +
 /// current working tree, apply non-conflicting changes, route conflicts to
 /// Claude (or `--force` overwrite), and persist an updated lockfile.
 pub fn run_update(
     args: &UpdateArgs,
     clone_dir: &Path,
-    template_sha: &str,
-    template_repo: &str,
+    revisions: &[TemplateRevision],
 ) -> Result<()> {
     let cwd = Path::new(".");
+    let (revision, overlays) = revisions
+        .split_first()
+        .context("No template sources to update from")?;
 
     let lockfile = Lockfile::load(cwd)?.with_context(|| format!(
         "No {LOCKFILE_NAME} found in current directory.\nThis doesn't look like a clemp-configured project — run `clemp <args>` to set one up first."
     ))?;
 
+    let aliases = LanguageAliases::load(clone_dir)?;
+    // `.clemp.yaml` sits under the lockfile's command, so a persisted drop
+    // outlives a config entry that still lists the item; the CLI goes on top.
     let mut merged_command = {
         let mut m = lockfile.original_command.clone();
-        m.merge_additive(&OriginalCommand::from_setup(&args.setup))?;
+        if let Some(defaults) = project_defaults(cwd, &aliases)? {
+            let overridden = m.merge_defaults(&defaults, &aliases)?;
+            if !overridden.is_empty() {
+                eprintln!(
+                    "Warning: {PROJECT_CONFIG_NAME} lists entries a later flag in {LOCKFILE_NAME} overrides; pass them again to restore them:\n  {}",
+                    overridden.join("\n  ")
+                );
+            }
+        }
+        m.merge_additive(&OriginalCommand::from_setup(&args.setup), &aliases)?;
         m
     };
 
-    let sha_unchanged = template_sha == lockfile.template_sha;
+    // Workspace packages: those `.clemp.yaml` declares, merged on top of the
+    // lockfile's. Package flags only ever come from `.clemp.yaml`, so unlike
+    // the root's it is their newest invocation. A locked package it no longer
+    // lists isn't rendered, so its files go through the stale pass.
+    let mut package_commands: BTreeMap<String, OriginalCommand> = BTreeMap::new();
+    for (name, command) in load_workspace_packages(cwd)? {
+        let mut merged = lockfile
+            .packages
+            .get(&name)
+            .map(|p| p.original_command.clone())
+            .unwrap_or_default();
+        merged.merge_additive(&command, &aliases)?;
+        package_commands.insert(name, merged);
+    }
+    let removed_packages: Vec<&String> = lockfile
+        .packages
+        .keys()
+        .filter(|name| !package_commands.contains_key(*name))
+        .collect();
+    let packages_unchanged = package_commands.len() == lockfile.packages.len()
+        && package_commands.iter().all(|(name, command)| {
+            lockfile
+                .packages
+                .get(name)
+                .is_some_and(|p| &p.original_command == command && p.resolved.is_some())
+        });
+
+    let sha_unchanged = revision.sha == lockfile.template_sha
+        && revision.parents == lockfile.template_parents
+        && overlays == lockfile.template_overlays.as_slice();
     let command_unchanged = merged_command == lockfile.original_command;
+    let profile_unchanged =
+        revision.profile.is_none() || revision.profile == lockfile.template_profile;
+    let followed_ref = lockfile.followed_ref(&args.setup);
+    let ref_unchanged = followed_ref == lockfile.template_ref;
     // Pre-snapshot lockfiles must do a full re-render even when nothing has
     // otherwise changed, so `resolved` can be captured. Pins reproducibility
     // for aggregation-output contributors (see PLAN_snapshot.md).
     let snapshot_missing = lockfile.resolved.is_none();
     // `--restore-deleted` must inspect the working tree even when nothing in the
     // template has changed, so it cannot share the unchanged-template fast path.
-    if sha_unchanged && command_unchanged && !args.restore_deleted && !snapshot_missing {
+    if sha_unchanged
+        && command_unchanged
+        && packages_unchanged
+        && profile_unchanged
+        && ref_unchanged
+        && !args.restore_deleted
+        && !snapshot_missing
+    {
         println!("Already up to date.");
         return Ok(());
     }
 
-    // Resolve languages once up front — drives fresh-addition validation, the
-    // name-stale pass, and `resolve_contributor` layer walks.
-    let resolved_languages = resolve_all_languages(&merged_command.languages, clone_dir)?;
+    let stale = stale_tags(&lockfile.original_command, &merged_command, clone_dir);
+    confirm_stale_tags(&mut merged_command, &stale, "", args.prune_stale)?;
+
+    // Resolve languages and tags once up front — drives fresh-addition
+    // validation, the name-stale pass, and `resolve_contributor` layer walks.
+    let (resolved_languages, implied_languages) = resolve_languages_explained(
+        &merged_command.languages,
+        &merged_command.drop_lang,
+        clone_dir,
+    )?;
+    let resolved_tags = resolve_tags(&merged_command.tags, &merged_command.drop_tags, clone_dir)?;
+    let conditional = conditional_subdirs(&resolved_languages, &resolved_tags);
 
     validate_fresh_additions(
         &lockfile.original_command,
         &merged_command,
-        &resolved_languages,
+        &conditional,
         lockfile.resolved.as_ref(),
         clone_dir,
     )?;
 
-    // Name-level stale pass: stems from the lockfile snapshot plus historical
-    // opt-ins from original_command.<kind>, minus anything the merged command
-    // drops, probed against the current template. Stems with no current
-    // contributor become `stale_<kind>`; survivors become `sticky_<kind>` fed
-    // into assembly.
-    let drop_mcp_set: HashSet<String> = merged_command.drop_mcp.iter().cloned().collect();
-    let drop_hooks_set: HashSet<String> = merged_command.drop_hooks.iter().cloned().collect();
-
-    let candidate_mcp: Vec<String> = {
-        let snap = lockfile.resolved.as_ref().map(|r| r.mcp.as_slice()).unwrap_or(&[]);
-        let mut seen: HashSet<String> = HashSet::new();
-        let mut out: Vec<String> = Vec::new();
-        for s in snap.iter().chain(merged_command.mcp.iter()) {
-            if drop_mcp_set.contains(s) { continue; }
-            if seen.insert(s.clone()) { out.push(s.clone()); }
-        }
-        out
-    };
-    let candidate_hooks: Vec<String> = {
-        let snap = lockfile.resolved.as_ref().map(|r| r.hooks.as_slice()).unwrap_or(&[]);
-        let mut seen: HashSet<String> = HashSet::new();
-        let mut out: Vec<String> = Vec::new();
-        for s in snap.iter().chain(merged_command.hooks.iter()) {
-            if drop_hooks_set.contains(s) { continue; }
-            if seen.insert(s.clone()) { out.push(s.clone()); }
-        }
-        out
-    };
-
-    let (sticky_mcp, stale_mcp): (Vec<String>, Vec<String>) = candidate_mcp
-        .into_iter()
-        .partition(|s| {
-            resolve_contributor("mcp", "json", &MCP_LAYERS, s, &resolved_languages, clone_dir)
-                .is_some()
-        });
-    let (sticky_hooks, stale_hooks): (Vec<String>, Vec<String>) = candidate_hooks
-        .into_iter()
-        .partition(|s| {
-            resolve_contributor("hooks", "json", &HOOKS_LAYERS, s, &[], clone_dir).is_some()
-        });
+    // Languages and tags the previous render had that this one doesn't. Their
+    // files fall out of the new manifest and go through the stale pass below;
+    // their gitignore fragment lines are collected separately since
+    // `.gitignore` isn't tracked.
+    let (removed_languages, removed_tags) = removed_languages_and_tags(
+        &lockfile.original_command,
+        &resolved_languages,
+        &resolved_tags,
+        &aliases,
+        clone_dir,
+    );
 
-    // Single confirm-or-bail UX for name-stale contributors. Declining bails
-    // with zero side effects so the user can re-run with `--drop-<kind>` or
-    // restore the contributor upstream.
-    for (kind, stale, output) in [
-        ("MCP", &stale_mcp, ".mcp.json"),
-        ("hook", &stale_hooks, ".claude/settings.local.json"),
-    ] {
-        if stale.is_empty() {
-            continue;
+    let partition = partition_contributors(
+        lockfile.resolved.as_ref(),
+        &merged_command,
+        &conditional,
+        clone_dir,
+    );
+    let shared = resolve_shared_settings(
+        &merged_command.shared_settings,
+        &merged_command.local_settings,
+        clone_dir,
+    )?;
+    confirm_stale_contributors(&partition, "", &shared, args.prune_stale)?;
+    partition.strip_stale(&mut merged_command);
+
+    // Workspace packages: the lockfile's, plus new or changed entries from
+    // `.clemp.yaml`. CLI args only ever apply to the root project. Each gets
+    // the same validation and name-stale pass as the root.
+    let mut packages: Vec<PackageUpdate> = Vec::new();
+    for (name, mut command) in package_commands {
+        let previous = lockfile.packages.get(&name);
+        if let Some(previous) = previous {
+            let stale = stale_tags(&previous.original_command, &command, clone_dir);
+            confirm_stale_tags(&mut command, &stale, &format!("{name}/"), args.prune_stale)?;
         }
-        if args.prune_stale {
-            println!(
-                "Dropping {} stale {} contributors: {}",
-                stale.len(),
-                kind,
-                stale.join(", ")
-            );
-        } else {
-            println!(
-                "The template no longer provides these {} contributors: {}",
-                kind,
-                stale.join(", ")
-            );
-            if !confirm(&format!("They will be removed from {output}. Continue?"))? {
-                bail!("Aborted. Re-run with --prune-stale, or --drop-{kind} the stems explicitly, or restore them upstream.",
-                    kind = if kind == "MCP" { "mcp" } else { "hooks" });
+        let (languages, _) =
+            resolve_languages_explained(&command.languages, &command.drop_lang, clone_dir)
+                .with_context(|| format!("In package '{name}'"))?;
+        let tags = resolve_tags(&command.tags, &command.drop_tags, clone_dir)
+            .with_context(|| format!("In package '{name}'"))?;
+        let conditional = conditional_subdirs(&languages, &tags);
+        let removed = match previous {
+            Some(previous) => {
+                let (languages, tags) = removed_languages_and_tags(
+                    &previous.original_command,
+                    &languages,
+                    &tags,
+                    &aliases,
+                    clone_dir,
+                );
+                conditional_subdirs(&languages, &tags)
             }
-        }
-    }
-
-    // Strip accepted/pruned stale stems from the persisted command so
-    // `setup_args.mcp`/`hooks` (derived from merged_command below) no longer
-    // carries them into the assembler's user_named path — where they would
-    // bail with "MCP/Hook not found" after their contributor disappeared
-    // upstream. The stems are already gone from sticky_mcp/hooks above; this
-    // closes the symmetric hole in the explicit/historical list.
-    if !stale_mcp.is_empty() {
-        merged_command.mcp.retain(|s| !stale_mcp.contains(s));
-    }
-    if !stale_hooks.is_empty() {
-        merged_command.hooks.retain(|s| !stale_hooks.contains(s));
+            None => Vec::new(),
+        };
+        let snapshot = previous.and_then(|p| p.resolved.as_ref());
+        validate_fresh_additions(
+            &previous
+                .map(|p| p.original_command.clone())
+                .unwrap_or_default(),
+            &command,
+            &conditional,
+            snapshot,
+            clone_dir,
+        )
+        .with_context(|| format!("In package '{name}'"))?;
+        let partition = partition_contributors(snapshot, &command, &conditional, clone_dir);
+        let shared =
+            resolve_shared_settings(&command.shared_settings, &command.local_settings, clone_dir)?;
+        confirm_stale_contributors(&partition, &format!("{name}/"), &shared, args.prune_stale)?;
+        partition.strip_stale(&mut command);
+        packages.push(PackageUpdate {
+            name,
+            command,
+            partition,
+            removed,
+        });
     }
-
-    // Render the new template into a temp staging directory.
-    let staging = env::temp_dir().join(format!("clemp-update-{}", std::process::id()));
+    let package_templates = PackageTemplates::new(clone_dir, packages.iter().map(|p| &p.name))?;
+
+    // Render the new template into a staging directory next to the clone, so
+    // it shares the clone's scratch space and cleanup.
+    let staging = {
+        let name = clone_dir
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        clone_dir.with_file_name(format!("{name}.update-staging"))
+    };
     if staging.exists() {
         fs::remove_dir_all(&staging)?;
     }
@@ -2346,18 +6217,58 @@ pub fn run_update(
     let setup_args = {
         let mut s = merged_command.clone().into_setup();
         s.force = args.setup.force;
+        s.no_validate = args.setup.no_validate;
+        s.strict_mcp = args.setup.strict_mcp;
         s
     };
 
+    let placeholders = Placeholders::new(cwd);
     let render_inputs = RenderInputs {
         setup: &setup_args,
-        sticky_mcp: &sticky_mcp,
-        sticky_hooks: &sticky_hooks,
+        sticky_mcp: &partition.sticky_mcp,
+        sticky_hooks: &partition.sticky_hooks,
+        sticky_permissions: &partition.sticky_permissions,
+        placeholders: &placeholders,
     };
 
     let outcome = run_setup(&render_inputs, clone_dir, &staging, false, true)?;
-    let resolved = outcome.resolved_languages;
-    let new_manifest = compute_manifest(&setup_args, &resolved, clone_dir, &staging)?;
+    let rendered_conditional = outcome.conditional_subdirs();
+    let mut new_manifest =
+        compute_manifest(&setup_args, &rendered_conditional, clone_dir, &staging)?;
+
+    let mut package_outcomes: Vec<(PackageUpdate, SetupOutcome)> = Vec::new();
+    for package in packages {
+        let mut setup = package.command.clone().into_setup();
+        setup.force = args.setup.force;
+        setup.no_validate = args.setup.no_validate;
+        setup.strict_mcp = args.setup.strict_mcp;
+        let inputs = RenderInputs {
+            setup: &setup,
+            sticky_mcp: &package.partition.sticky_mcp,
+            sticky_hooks: &package.partition.sticky_hooks,
+            sticky_permissions: &package.partition.sticky_permissions,
+            placeholders: &placeholders,
+        };
+        let template = package_templates.path(&package.name);
+        let (outcome, manifest) =
+            render_package(&package.name, &inputs, &template, &staging, false)?;
+        new_manifest.extend(manifest);
+        package_outcomes.push((package, outcome));
+    }
+
+    // Every render shares one set of placeholder values, so one map undoes
+    // substitution in any `.mcp.json`.
+    let placeholder_values = placeholders.values();
+    // What this render produced for `.mcp.json` / settings: the base of the
+    // structural merge on the next update.
+    let mut mcp_servers = BTreeMap::from([(".mcp.json".to_string(), outcome.mcp_servers.clone())]);
+    let mut settings = outcome.settings.clone();
+    for (package, o) in &package_outcomes {
+        mcp_servers.insert(format!("{}/.mcp.json", package.name), o.mcp_servers.clone());
+        for (path, doc) in &o.settings {
+            settings.insert(format!("{}/{path}", package.name), doc.clone());
+        }
+    }
 
     // Classify every file in the new render.
     let mut clean: Vec<String> = Vec::new();
@@ -2371,7 +6282,24 @@ pub fn run_update(
     for (path, new_hash) in &new_manifest {
         let cwd_path = cwd.join(path);
         let cwd_is_dir = cwd_path.is_dir();
-        let cur_hash = if cwd_path.is_file() { Some(hash_file(&cwd_path)?) } else { None };
+        let cur_hash = if !cwd_path.is_file() {
+            None
+        } else if cwd_path.file_name().is_some_and(|n| n == ".mcp.json") {
+            // Undo substitution where the last render put placeholders; a
+            // lockfile from before `mcp-servers` was recorded falls back to
+            // this render's.
+            let servers = lockfile
+                .mcp_servers
+                .get(path)
+                .or_else(|| mcp_servers.get(path));
+            Some(hash_mcp_json(
+                &cwd_path,
+                &mcp_json_template(servers),
+                &placeholder_values,
+            )?)
+        } else {
+            Some(hash_file(&cwd_path)?)
+        };
         let old_hash = lockfile.files.get(path).map(String::as_str);
 
         match classify_update_path(old_hash, cur_hash.as_deref(), new_hash, cwd_is_dir) {
@@ -2392,14 +6320,125 @@ pub fn run_update(
         }
     }
 
+    // A settings file setup merged into one the project already had was
+    // hashed as written, so it reads clean; it still holds keys the recorded
+    // render doesn't, so it merges structurally rather than being replaced.
+    let (settings_clean, rest): (Vec<String>, Vec<String>) =
+        std::mem::take(&mut clean).into_iter().partition(|path| {
+            lockfile.settings.get(path).is_some_and(|recorded| {
+                fs::read_to_string(cwd.join(path))
+                    .ok()
+                    .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+                    .is_some_and(|on_disk| on_disk != *recorded)
+            })
+        });
+    clean = rest;
+    conflicts.extend(settings_clean);
+
+    // A conflicting `.mcp.json` or settings file is merged structurally
+    // against what clemp last rendered there, so only keys both sides changed
+    // stay in conflict; so is a settings file the user already had when the
+    // template starts writing it. `--force` still replaces the whole file.
+    let mut structural_merged: Vec<(String, Value)> = Vec::new();
+    let mut structural_conflicts: Vec<(String, Vec<String>)> = Vec::new();
+    if !args.setup.force {
+        for paths in [&mut conflicts, &mut collisions] {
+            let mut remaining = Vec::new();
+            for path in std::mem::take(paths) {
+                let merged = merge_rendered_json(
+                    &path,
+                    &lockfile,
+                    &mcp_servers,
+                    &settings,
+                    &placeholder_values,
+                    cwd,
+                )?;
+                match merged {
+                    Some(Ok(doc)) => structural_merged.push((path, doc)),
+                    Some(Err(keys)) => {
+                        structural_conflicts.push((path.clone(), keys));
+                        remaining.push(path);
+                    }
+                    None => remaining.push(path),
+                }
+            }
+            *paths = remaining;
+        }
+    }
+
     let stale: Vec<String> = lockfile
         .files
         .keys()
         .filter(|p| !new_manifest.contains_key(*p))
         .cloned()
         .collect();
+    // Stale gitignore lines per directory: the root's, then each package's.
+    let mut stale_gitignore: Vec<(PathBuf, Vec<String>)> = vec![(
+        cwd.to_path_buf(),
+        stale_gitignore_lines(
+            clone_dir,
+            cwd,
+            &conditional_subdirs(&removed_languages, &removed_tags),
+            &rendered_conditional,
+        )?,
+    )];
+    for (package, o) in &package_outcomes {
+        let dir = cwd.join(&package.name);
+        let lines = stale_gitignore_lines(
+            &package_templates.path(&package.name),
+            &dir,
+            &package.removed,
+            &o.conditional_subdirs(),
+        )?;
+        stale_gitignore.push((dir, lines));
+    }
+    stale_gitignore.retain(|(_, lines)| !lines.is_empty());
+    let stale_gitignore_report: Vec<String> = stale_gitignore
+        .iter()
+        .flat_map(|(dir, lines)| {
+            let gitignore = dir.join(".gitignore");
+            let gitignore = gitignore
+                .strip_prefix(cwd)
+                .unwrap_or(&gitignore)
+                .display()
+                .to_string();
+            lines.iter().map(move |line| format!("{gitignore}: {line}"))
+        })
+        .collect();
 
     println!("\nUpdate plan:");
+    if !implied_languages.is_empty() {
+        let languages: Vec<String> = resolved_languages
+            .iter()
+            .map(
+                |lang| match implied_languages.iter().find(|i| &i.language == lang) {
+                    Some(i) => format!("{lang} (implied by {})", i.implied_by),
+                    None => lang.clone(),
+                },
+            )
+            .collect();
+        println!("  languages: {}", languages.join(", "));
+    }
+    if !removed_languages.is_empty() {
+        println!("  dropped languages: {}", removed_languages.join(", "));
+    }
+    if !removed_tags.is_empty() {
+        println!("  dropped tags: {}", removed_tags.join(", "));
+    }
+    if !package_outcomes.is_empty() {
+        let names: Vec<&str> = package_outcomes
+            .iter()
+            .map(|(p, _)| p.name.as_str())
+            .collect();
+        println!("  packages: {}", names.join(", "));
+    }
+    if !removed_packages.is_empty() {
+        let names: Vec<&str> = removed_packages.iter().map(|n| n.as_str()).collect();
+        println!(
+            "  removed packages (no longer in {PROJECT_CONFIG_NAME}): {}",
+            names.join(", ")
+        );
+    }
     let report = |paths: &[String], label: &str| {
         if paths.is_empty() { return; }
         println!("  {:>3} {}", paths.len(), label);
@@ -2408,6 +6447,11 @@ pub fn run_update(
         }
     };
     report(&clean, "cleanly updated");
+    let merged_paths: Vec<String> = structural_merged.iter().map(|(p, _)| p.clone()).collect();
+    report(
+        &merged_paths,
+        "merged key by key (user + template both changed)",
+    );
     report(&new_files, "new");
     report(&skipped, "preserved (user-modified, template unchanged)");
     report(&conflicts, if args.merge {
@@ -2415,14 +6459,31 @@ pub fn run_update(
     } else {
         "kept (user + template both changed, use --merge to reconcile)"
     });
+    let report_keys = |paths: &[String]| {
+        for (path, keys) in structural_conflicts
+            .iter()
+            .filter(|(p, _)| paths.contains(p))
+        {
+            println!("        {path}: changed on both sides: {}", keys.join(", "));
+        }
+    };
+    report_keys(&conflicts);
     report(&collisions, if args.merge {
         "collisions — will merge with Claude (template introduced file, you already have one)"
     } else {
         "kept (template introduced file, you already have one, use --merge to reconcile)"
     });
+    report_keys(&collisions);
     report(&shape_collisions, "shape collisions (directory exists where template wants a file)");
     report(&stale, "stale (template no longer produces)");
-    report(&restore_pending, "missing (use --restore-deleted to re-add)");
+    report(
+        &stale_gitignore_report,
+        ".gitignore entries stale (dropped language/tag fragments)",
+    );
+    report(
+        &restore_pending,
+        "missing (use --restore-deleted to re-add)",
+    );
 
     // Shape collisions can only be resolved by --force (Claude can't merge into a directory).
     if !shape_collisions.is_empty() && !args.setup.force {
@@ -2521,6 +6582,16 @@ pub fn run_update(
         }
     }
 
+    if !stale_gitignore.is_empty() {
+        let remove =
+            args.prune_stale || confirm("Remove the stale .gitignore entries listed above?")?;
+        if remove {
+            for (dir, lines) in &stale_gitignore {
+                remove_gitignore_lines(dir, lines)?;
+            }
+        }
+    }
+
     // Finally, apply non-conflicting writes. These are straight copies and
     // shouldn't fail on a healthy filesystem; doing them last means the rest of
     // the update is already committed before we touch paths the user considers
@@ -2528,23 +6599,59 @@ pub fn run_update(
     for path in clean.iter().chain(new_files.iter()) {
         apply_one(path, &staging, cwd)?;
     }
+    for (path, doc) in &structural_merged {
+        fs::write(cwd.join(path), serde_json::to_string_pretty(doc)?)
+            .with_context(|| format!("Failed to write {path}"))?;
+    }
 
     // Always re-apply gitignore additions to the real CWD.
-    update_gitignore(clone_dir, cwd, &resolved)?;
+    update_gitignore(clone_dir, cwd, &rendered_conditional)?;
+    for (package, outcome) in &package_outcomes {
+        update_gitignore(
+            &package_templates.path(&package.name),
+            &cwd.join(&package.name),
+            &outcome.conditional_subdirs(),
+        )?;
+    }
 
     // Persist new lockfile. Use the template-side manifest (template hashes) as
     // the source of truth so future updates can detect user modifications.
     let new_lockfile = Lockfile {
-        template_repo: template_repo.to_string(),
-        template_sha: template_sha.to_string(),
+        template_repo: revision.repo.clone(),
+        template_sha: revision.sha.clone(),
+        template_profile: revision
+            .profile
+            .clone()
+            .or_else(|| lockfile.template_profile.clone()),
+        template_ref: followed_ref,
+        template_parents: revision.parents.clone(),
+        template_overlays: overlays.to_vec(),
         original_command: merged_command,
         resolved: Some(Resolved {
             mcp: outcome.mcp_snapshottable_stems,
             hooks: outcome.hooks_snapshottable_stems,
+            permissions: outcome.permissions_snapshottable_stems,
         }),
+        packages: package_outcomes
+            .into_iter()
+            .map(|(package, outcome)| {
+                let lock = PackageLock {
+                    original_command: package.command,
+                    resolved: Some(Resolved {
+                        mcp: outcome.mcp_snapshottable_stems,
+                        hooks: outcome.hooks_snapshottable_stems,
+                        permissions: outcome.permissions_snapshottable_stems,
+                    }),
+                };
+                (package.name, lock)
+            })
+            .collect(),
+        mcp_servers,
+        settings,
         files: new_manifest,
     };
     new_lockfile.save(cwd)?;
+    placeholders.offer_to_save()?;
 
     // Cleanup staging.
     let _ = fs::remove_dir_all(&staging);
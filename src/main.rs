@@ -1,23 +1,37 @@
 //! clemp — CLI entry point. Dispatches to the default setup, `update`, or
-//! `list` subcommand and owns clone + cleanup around each.
+//! `list` subcommand and owns the scratch directory the template is cloned
+//! into for each.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
 use clemp::{
-    checkout_sha, cleanup, clone_repo, compute_manifest, get_repo_url, list_available,
-    normalize_setup_args, reject_add_drop_overlap, resolve_all_languages, run_setup, run_update,
-    validate_fresh_additions, Cli, CliCommand, Lockfile, OriginalCommand, RenderInputs, Resolved,
-    CLONE_DIR, LOCKFILE_NAME,
+    apply_detected_languages, apply_package, compute_manifest, conditional_subdirs,
+    get_template_sources, list_available, load_workspace_packages, local_template_path,
+    normalize_setup_args, overwrite_conflicts, prepare_template_stack, reject_add_drop_overlap,
+    resolve_languages_explained, resolve_tags, run_update, stage_package, stage_setup,
+    validate_fresh_additions, with_project_config, Cli, CliCommand, LanguageAliases, Lockfile,
+    OriginalCommand, PackageLock, PackageTemplates, Placeholders, RenderInputs, Resolved,
+    ScratchDir, SetupArgs, StagedSetup, TemplateSource, LOCKFILE_NAME,
 };
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
-    let clone_dir = Path::new(CLONE_DIR);
+    // Dropped (and deleted) when `main` returns or unwinds; the interrupt
+    // handler covers Ctrl-C.
+    let scratch = ScratchDir::new()?;
+    scratch.remove_on_interrupt()?;
+    let clone_dir = scratch.clone_dir();
+    let clone_dir = clone_dir.as_path();
 
     match cli.command {
-        Some(CliCommand::List { category }) => run_list(category, clone_dir),
+        Some(CliCommand::List {
+            category,
+            template,
+            offline,
+        }) => run_list(category, template, offline, clone_dir),
         Some(CliCommand::Update(mut args)) => {
             normalize_setup_args(&mut args.setup);
             run_update_cmd(args, clone_dir)
@@ -30,18 +44,35 @@ fn main() -> Result<()> {
     }
 }
 
-fn run_list(category: Option<String>, clone_dir: &Path) -> Result<()> {
-    let repo_url = get_repo_url()?;
-    println!("Cloning {}...", repo_url);
-    clone_repo(&repo_url)?;
+fn run_list(
+    category: Option<String>,
+    template: Vec<String>,
+    offline: bool,
+    clone_dir: &Path,
+) -> Result<()> {
+    let sources = template_sources(&template, None)?;
+    println!("Cloning {}...", sources[0].repo);
+    prepare_template_stack(&sources, &[], clone_dir, offline)?;
     let cat = category.unwrap_or_else(|| "all".to_string());
-    let result = list_available(&cat, clone_dir);
-    let _ = fs::remove_dir_all(clone_dir);
-    print!("{}", result?);
+    print!("{}", list_available(&cat, clone_dir)?);
     Ok(())
 }
 
-fn run_setup_cmd(args: clemp::SetupArgs, clone_dir: &Path) -> Result<()> {
+/// Resolve `--template` values to the stack to fetch; `git_ref` applies to the
+/// first (base) template only.
+fn template_sources(profiles: &[String], git_ref: Option<String>) -> Result<Vec<TemplateSource>> {
+    let mut git_ref = git_ref;
+    Ok(get_template_sources(profiles)?
+        .into_iter()
+        .map(|(profile, repo)| TemplateSource {
+            profile: Some(profile),
+            repo,
+            git_ref: git_ref.take(),
+        })
+        .collect())
+}
+
+fn run_setup_cmd(args: SetupArgs, clone_dir: &Path) -> Result<()> {
     let cwd = Path::new(".");
 
     if Lockfile::path(cwd).exists() {
@@ -51,10 +82,19 @@ fn run_setup_cmd(args: clemp::SetupArgs, clone_dir: &Path) -> Result<()> {
         );
     }
 
-    let repo_url = get_repo_url()?;
+    let sources = template_sources(&args.template, args.git_ref.clone())?;
 
-    println!("Cloning {}...", repo_url);
-    let template_sha = clone_repo(&repo_url)?;
+    println!("Cloning {}...", sources[0].repo);
+    let revisions = prepare_template_stack(&sources, &[], clone_dir, args.offline)?;
+    // Project defaults are merged once the template is here, so its language
+    // aliases apply when deduping them against the CLI args.
+    let aliases = LanguageAliases::load(clone_dir)?;
+    let mut args = with_project_config(&args, cwd, &aliases)?;
+    apply_detected_languages(&mut args, clone_dir, cwd)?;
+    let packages = load_workspace_packages(cwd)?;
+    let (revision, overlays) = revisions
+        .split_first()
+        .expect("at least one template source");
 
     let gitignore_existed = Path::new(".gitignore").exists();
     let install_git_hooks = Path::new(".git").is_dir();
@@ -65,48 +105,132 @@ fn run_setup_cmd(args: clemp::SetupArgs, clone_dir: &Path) -> Result<()> {
         // `validate_fresh_additions` catches typos in --mcp / --hooks /
         // --drop-*.
         let setup_command = OriginalCommand::from_setup(&args);
-        reject_add_drop_overlap(&setup_command)?;
-        let resolved_languages = resolve_all_languages(&args.languages, clone_dir)?;
+        reject_add_drop_overlap(&setup_command, &aliases)?;
+        let (resolved_languages, _) =
+            resolve_languages_explained(&args.languages, &args.drop_lang, clone_dir)?;
+        let resolved_tags = resolve_tags(&args.tags, &args.drop_tags, clone_dir)?;
         validate_fresh_additions(
             &OriginalCommand::default(),
             &setup_command,
-            &resolved_languages,
+            &conditional_subdirs(&resolved_languages, &resolved_tags),
             None,
             clone_dir,
         )?;
+        // Workspace packages get the same checks up front, so a typo in one
+        // package fails before the root is written.
+        for (package, command) in &packages {
+            reject_add_drop_overlap(command, &aliases)
+                .with_context(|| format!("In package '{package}'"))?;
+            let (languages, _) =
+                resolve_languages_explained(&command.languages, &command.drop_lang, clone_dir)
+                    .with_context(|| format!("In package '{package}'"))?;
+            let tags = resolve_tags(&command.tags, &command.drop_tags, clone_dir)
+                .with_context(|| format!("In package '{package}'"))?;
+            validate_fresh_additions(
+                &OriginalCommand::default(),
+                command,
+                &conditional_subdirs(&languages, &tags),
+                None,
+                clone_dir,
+            )
+            .with_context(|| format!("In package '{package}'"))?;
+        }
+        let package_templates = PackageTemplates::new(clone_dir, packages.keys())?;
+        let placeholders = Placeholders::new(cwd);
+
         let inputs = RenderInputs {
             setup: &args,
             sticky_mcp: &[],
             sticky_hooks: &[],
+            sticky_permissions: &[],
+            placeholders: &placeholders,
         };
-        let outcome = run_setup(&inputs, clone_dir, cwd, true, install_git_hooks)?;
-        let files = compute_manifest(&args, &outcome.resolved_languages, clone_dir, cwd)?;
+        // Render the root and every package before writing any of them, so
+        // a conflict in one package leaves the project untouched.
+        let staged_root = stage_setup(&inputs, clone_dir, cwd, true, install_git_hooks)?;
+        let package_setups: Vec<SetupArgs> = packages
+            .values()
+            .map(|command| SetupArgs {
+                force: args.force,
+                no_validate: args.no_validate,
+                strict_mcp: args.strict_mcp,
+                ..command.clone().into_setup()
+            })
+            .collect();
+        let mut staged_packages = Vec::new();
+        for (package, setup) in packages.keys().zip(&package_setups) {
+            let inputs = RenderInputs {
+                setup,
+                sticky_mcp: &[],
+                sticky_hooks: &[],
+                sticky_permissions: &[],
+                placeholders: &placeholders,
+            };
+            let template = package_templates.path(package);
+            staged_packages.push(stage_package(package, &inputs, &template, cwd, true)?);
+        }
+        let all_staged: Vec<&StagedSetup> = std::iter::once(&staged_root)
+            .chain(&staged_packages)
+            .collect();
+        overwrite_conflicts(&all_staged, args.force)?;
+
+        let outcome = staged_root.apply()?;
+        let mut files = compute_manifest(&args, &outcome.conditional_subdirs(), clone_dir, cwd)?;
+
+        let mut mcp_servers = BTreeMap::from([(".mcp.json".to_string(), outcome.mcp_servers)]);
+        let mut settings = outcome.settings;
+        let mut package_locks = BTreeMap::new();
+        for (((package, command), setup), staged) in
+            packages.iter().zip(&package_setups).zip(staged_packages)
+        {
+            let (outcome, manifest) = apply_package(package, staged, setup)?;
+            files.extend(manifest);
+            mcp_servers.insert(format!("{package}/.mcp.json"), outcome.mcp_servers);
+            for (path, doc) in outcome.settings {
+                settings.insert(format!("{package}/{path}"), doc);
+            }
+            package_locks.insert(
+                package.clone(),
+                PackageLock {
+                    original_command: command.clone(),
+                    resolved: Some(Resolved {
+                        mcp: outcome.mcp_snapshottable_stems,
+                        hooks: outcome.hooks_snapshottable_stems,
+                        permissions: outcome.permissions_snapshottable_stems,
+                    }),
+                },
+            );
+        }
         Lockfile {
-            template_repo: repo_url.clone(),
-            template_sha: template_sha.clone(),
+            template_repo: revision.repo.clone(),
+            template_sha: revision.sha.clone(),
+            template_profile: revision.profile.clone(),
+            template_ref: args.git_ref.clone(),
+            template_parents: revision.parents.clone(),
+            template_overlays: overlays.to_vec(),
             original_command: OriginalCommand::from_setup(&args),
             resolved: Some(Resolved {
                 mcp: outcome.mcp_snapshottable_stems,
                 hooks: outcome.hooks_snapshottable_stems,
+                permissions: outcome.permissions_snapshottable_stems,
             }),
+            packages: package_locks,
+            mcp_servers,
+            settings,
             files,
         }
         .save(cwd)?;
+        placeholders.offer_to_save()?;
         Ok::<_, anyhow::Error>(())
     })();
 
     if let Err(e) = result {
-        eprintln!("Removing {} due to error...", clone_dir.display());
-        let _ = fs::remove_dir_all(clone_dir);
         if !gitignore_existed {
             let _ = fs::remove_file(".gitignore");
         }
         return Err(e);
     }
 
-    println!("Cleaning up...");
-    cleanup(clone_dir)?;
-
     println!("Done! Claude template configured for your project.");
     Ok(())
 }
@@ -119,21 +243,43 @@ fn run_update_cmd(args: clemp::UpdateArgs, clone_dir: &Path) -> Result<()> {
         )
     })?;
 
-    let repo_url = lock.template_repo.clone();
-    println!("Cloning {}...", repo_url);
-    let template_sha = clone_repo(&repo_url)?;
-
-    // --only: pin the clone to the lockfile's SHA so only the argument delta
-    // (e.g. a new --mcp) has an effect — no upstream template changes land.
-    let effective_sha = if args.only {
-        checkout_sha(clone_dir, &lock.template_sha)?;
-        lock.template_sha.clone()
+    // --template switches the project to other configured profiles; otherwise
+    // keep following the sources recorded in the lockfile.
+    let git_ref = lock.followed_ref(&args.setup);
+    let mut sources = if args.setup.template.is_empty() {
+        lock.sources()
+            .into_iter()
+            .map(|r| TemplateSource {
+                profile: r.profile,
+                repo: r.repo,
+                git_ref: None,
+            })
+            .collect()
     } else {
-        template_sha
+        template_sources(&args.setup.template, None)?
     };
+    sources[0].git_ref = git_ref;
+
+    let mut pinned_parents = Vec::new();
+    if args.only {
+        // --only: pin every template (and its parents) to the lockfile's SHAs
+        // so only the argument delta (e.g. a new --mcp) has an effect — no
+        // upstream template changes land.
+        for (source, locked) in sources.iter_mut().zip(lock.sources()) {
+            if local_template_path(&source.repo).is_some() {
+                anyhow::bail!(
+                    "--only pins the template at the lockfile's commit, which isn't possible for the local template directory {}",
+                    source.repo
+                );
+            }
+            source.git_ref = Some(locked.sha);
+            pinned_parents.extend(locked.parents);
+        }
+    }
 
-    let result = run_update(&args, clone_dir, &effective_sha, &repo_url);
+    println!("Cloning {}...", sources[0].repo);
+    let revisions =
+        prepare_template_stack(&sources, &pinned_parents, clone_dir, args.setup.offline)?;
 
-    let _ = cleanup(clone_dir);
-    result
+    run_update(&args, clone_dir, &revisions)
 }
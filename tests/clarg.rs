@@ -3,8 +3,8 @@
 mod common;
 
 use clemp::{
-    assemble_hooks_json, build_settings, run_setup, setup_clarg, RenderInputs, SetupArgs,
-    CLONE_DIR,
+    assemble_hooks_json, build_settings, run_setup, setup_clarg, AssemblyResult, Placeholders, RenderInputs,
+    SetupArgs, CLONE_DIR,
 };
 use common::{CwdGuard, Scaffold};
 use serde_json::Value;
@@ -71,8 +71,8 @@ fn clarg_entry_merged_into_pretooluse_hooks() {
     )]);
 
     let entry = setup_clarg("strict", s.path()).unwrap();
-    let hooks = assemble_hooks_json(&[], &[], &empty(), s.path()).unwrap();
-    build_settings(&hooks, &[entry], &[], s.path()).unwrap();
+    let hooks = assemble_hooks_json(&[], &[], &[], &empty(), s.path()).unwrap();
+    build_settings(&hooks, &AssemblyResult::default(), &[entry], &[], &[], &[], s.path()).unwrap();
 
     let content = fs::read_to_string(s.path().join(".claude/settings.local.json")).unwrap();
     let val: Value = serde_json::from_str(&content).unwrap();
@@ -94,8 +94,8 @@ fn clarg_merges_with_existing_pretooluse_hooks() {
     s.with_clarg_configs(&[("strict", "internal_access_only: true")]);
 
     let entry = setup_clarg("strict", s.path()).unwrap();
-    let hooks = assemble_hooks_json(&[], &[], &empty(), s.path()).unwrap();
-    build_settings(&hooks, &[entry], &[], s.path()).unwrap();
+    let hooks = assemble_hooks_json(&[], &[], &[], &empty(), s.path()).unwrap();
+    build_settings(&hooks, &AssemblyResult::default(), &[entry], &[], &[], &[], s.path()).unwrap();
 
     let content = fs::read_to_string(s.path().join(".claude/settings.local.json")).unwrap();
     let val: Value = serde_json::from_str(&content).unwrap();
@@ -114,8 +114,8 @@ fn clarg_without_clarg_entry_leaves_hooks_unchanged() {
     s.with_default_hooks(&[("sound", r#"{"Notification": [{"command": "beep"}]}"#)]);
 
     // No clarg entries
-    let hooks = assemble_hooks_json(&[], &[], &empty(), s.path()).unwrap();
-    build_settings(&hooks, &[], &[], s.path()).unwrap();
+    let hooks = assemble_hooks_json(&[], &[], &[], &empty(), s.path()).unwrap();
+    build_settings(&hooks, &AssemblyResult::default(), &[], &[], &[], &[], s.path()).unwrap();
 
     let content = fs::read_to_string(s.path().join(".claude/settings.local.json")).unwrap();
     let val: Value = serde_json::from_str(&content).unwrap();
@@ -156,18 +156,18 @@ fn default_yaml_applied_without_clarg_flag() {
 
     let args = SetupArgs {
         languages: vec!["ts".into()],
-        hooks: vec![],
-        mcp: vec![],
-        commands: vec![],
-        githooks: vec![],
-        drop_mcp: vec![],
-        drop_hooks: vec![],
         clarg: None,
-        force: false,
+        ..Default::default()
     };
 
     run_setup(
-        &RenderInputs { setup: &args, sticky_mcp: &[], sticky_hooks: &[] },
+        &RenderInputs {
+            setup: &args,
+            sticky_mcp: &[],
+            sticky_hooks: &[],
+            sticky_permissions: &[],
+            placeholders: &Placeholders::default(),
+        },
         s.path(),
         Path::new("."),
         true,
@@ -206,18 +206,18 @@ fn explicit_clarg_flag_overrides_default() {
 
     let args = SetupArgs {
         languages: vec!["ts".into()],
-        hooks: vec![],
-        mcp: vec![],
-        commands: vec![],
-        githooks: vec![],
-        drop_mcp: vec![],
-        drop_hooks: vec![],
         clarg: Some("strict".into()),
-        force: false,
+        ..Default::default()
     };
 
     run_setup(
-        &RenderInputs { setup: &args, sticky_mcp: &[], sticky_hooks: &[] },
+        &RenderInputs {
+            setup: &args,
+            sticky_mcp: &[],
+            sticky_hooks: &[],
+            sticky_permissions: &[],
+            placeholders: &Placeholders::default(),
+        },
         s.path(),
         Path::new("."),
         true,
@@ -252,18 +252,18 @@ fn no_default_yaml_and_no_flag_skips_clarg() {
 
     let args = SetupArgs {
         languages: vec!["ts".into()],
-        hooks: vec![],
-        mcp: vec![],
-        commands: vec![],
-        githooks: vec![],
-        drop_mcp: vec![],
-        drop_hooks: vec![],
         clarg: None,
-        force: false,
+        ..Default::default()
     };
 
     run_setup(
-        &RenderInputs { setup: &args, sticky_mcp: &[], sticky_hooks: &[] },
+        &RenderInputs {
+            setup: &args,
+            sticky_mcp: &[],
+            sticky_hooks: &[],
+            sticky_permissions: &[],
+            placeholders: &Placeholders::default(),
+        },
         s.path(),
         Path::new("."),
         true,
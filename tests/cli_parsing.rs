@@ -1,7 +1,7 @@
 //! Tests for CLI argument parsing and split_multi_values.
 
 use clap::Parser;
-use clemp::{split_multi_values, Cli};
+use clemp::{split_multi_values, Cli, SettingsPart};
 
 // ── split_multi_values ──────────────────────────────────────────────────
 
@@ -208,3 +208,37 @@ fn cli_clarg_combined_with_other_options() {
     assert_eq!(cli.setup.hooks, vec!["sound"]);
     assert_eq!(cli.setup.mcp, vec!["context7"]);
 }
+
+#[test]
+fn cli_no_validate_flag() {
+    let cli = Cli::try_parse_from(["clemp", "ts", "--no-validate"]).unwrap();
+    assert!(cli.setup.no_validate);
+    assert!(!Cli::try_parse_from(["clemp", "ts"]).unwrap().setup.no_validate);
+}
+
+#[test]
+fn cli_strict_mcp_flag() {
+    let cli = Cli::try_parse_from(["clemp", "ts", "--strict-mcp"]).unwrap();
+    assert!(cli.setup.strict_mcp);
+    assert!(!Cli::try_parse_from(["clemp", "ts"]).unwrap().setup.strict_mcp);
+}
+
+#[test]
+fn cli_permissions_flags() {
+    let cli = Cli::try_parse_from(["clemp", "ts", "--permissions", "git,web", "--drop-permissions", "cargo"])
+        .unwrap();
+    assert_eq!(cli.setup.permissions, vec!["git", "web"]);
+    assert_eq!(cli.setup.drop_permissions, vec!["cargo"]);
+}
+
+#[test]
+fn cli_settings_target_flags() {
+    let cli = Cli::try_parse_from([
+        "clemp", "ts", "--shared-settings", "hooks,permissions", "--local-settings", "clarg",
+    ])
+    .unwrap();
+    assert_eq!(cli.setup.shared_settings, vec![SettingsPart::Hooks, SettingsPart::Permissions]);
+    assert_eq!(cli.setup.local_settings, vec![SettingsPart::Clarg]);
+
+    assert!(Cli::try_parse_from(["clemp", "--shared-settings", "env"]).is_err());
+}
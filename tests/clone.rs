@@ -0,0 +1,296 @@
+//! Tests for template fetching: `clone_repo` against a throwaway local git
+//! repository (default-branch, branch, tag and commit-SHA refs), the
+//! persistent mirror cache and `--offline`, and `fetch_template` for
+//! local-directory sources.
+
+mod common;
+
+use clemp::{
+    clone_repo, fetch_template, hash_template_tree, local_template_path, looks_like_sha,
+    mirror_path, CLONE_DIR,
+};
+use common::{CwdGuard, EnvVarGuard};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn git(dir: &Path, args: &[&str]) -> String {
+    let out = Command::new("git")
+        .args(["-c", "user.name=t", "-c", "user.email=t@t", "-c", "init.defaultBranch=main"])
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "git {args:?}: {}", String::from_utf8_lossy(&out.stderr));
+    String::from_utf8(out.stdout).unwrap().trim().to_string()
+}
+
+fn commit(dir: &Path, content: &str) -> String {
+    fs::write(dir.join("CLAUDE.md.jinja"), content).unwrap();
+    git(dir, &["add", "-A"]);
+    git(dir, &["commit", "-q", "-m", content]);
+    git(dir, &["rev-parse", "HEAD"])
+}
+
+/// Upstream with `main` at "v3", a `stable` branch at "v2" and a `v1` tag.
+/// Returns (repo dir, v1 sha).
+fn upstream() -> (TempDir, String) {
+    let repo = TempDir::new().unwrap();
+    git(repo.path(), &["init", "-q"]);
+    let v1 = commit(repo.path(), "v1");
+    git(repo.path(), &["tag", "v1"]);
+    commit(repo.path(), "v2");
+    git(repo.path(), &["branch", "stable"]);
+    commit(repo.path(), "v3");
+    (repo, v1)
+}
+
+/// Point the template cache at a throwaway directory. Must be created after
+/// the `CwdGuard`, whose lock serializes the env change.
+fn isolated_cache() -> (TempDir, EnvVarGuard) {
+    let cache = TempDir::new().unwrap();
+    let mut env = EnvVarGuard::new();
+    env.set("XDG_CACHE_HOME", &cache.path().display().to_string());
+    (cache, env)
+}
+
+fn clone_dir() -> &'static Path {
+    Path::new(CLONE_DIR)
+}
+
+fn cloned_template() -> String {
+    fs::read_to_string(clone_dir().join("CLAUDE.md.jinja")).unwrap()
+}
+
+#[test]
+fn clone_without_ref_takes_default_branch() {
+    let (repo, _) = upstream();
+    let work = TempDir::new().unwrap();
+    let _g = CwdGuard::new(work.path());
+    let _cache = isolated_cache();
+    let url = format!("file://{}", repo.path().display());
+
+    let sha = clone_repo(&url, None, clone_dir(), false).unwrap();
+    assert_eq!(cloned_template(), "v3");
+    assert_eq!(sha, git(repo.path(), &["rev-parse", "main"]));
+}
+
+#[test]
+fn clone_branch_and_tag_refs() {
+    let (repo, v1) = upstream();
+    let work = TempDir::new().unwrap();
+    let _g = CwdGuard::new(work.path());
+    let _cache = isolated_cache();
+    let url = format!("file://{}", repo.path().display());
+
+    clone_repo(&url, Some("stable"), clone_dir(), false).unwrap();
+    assert_eq!(cloned_template(), "v2");
+
+    let sha = clone_repo(&url, Some("v1"), clone_dir(), false).unwrap();
+    assert_eq!(cloned_template(), "v1");
+    assert_eq!(sha, v1);
+}
+
+#[test]
+fn clone_commit_sha_ref_including_abbreviated() {
+    let (repo, v1) = upstream();
+    let work = TempDir::new().unwrap();
+    let _g = CwdGuard::new(work.path());
+    let _cache = isolated_cache();
+    let url = format!("file://{}", repo.path().display());
+
+    let sha = clone_repo(&url, Some(&v1[..10]), clone_dir(), false).unwrap();
+    assert_eq!(cloned_template(), "v1");
+    assert_eq!(sha, v1);
+}
+
+#[test]
+fn hex_branch_name_wins_over_matching_commit_sha() {
+    let (repo, v1) = upstream();
+    // A branch named like v1's abbreviated SHA, pointing at v2.
+    git(repo.path(), &["branch", &v1[..8], "stable"]);
+    let work = TempDir::new().unwrap();
+    let _g = CwdGuard::new(work.path());
+    let _cache = isolated_cache();
+    let url = format!("file://{}", repo.path().display());
+
+    let sha = clone_repo(&url, Some(&v1[..8]), clone_dir(), false).unwrap();
+    assert_eq!(cloned_template(), "v2");
+    assert_eq!(sha, git(repo.path(), &["rev-parse", "stable"]));
+}
+
+#[test]
+fn clone_unknown_ref_fails_and_leaves_no_clone_dir() {
+    let (repo, _) = upstream();
+    let work = TempDir::new().unwrap();
+    let _g = CwdGuard::new(work.path());
+    let _cache = isolated_cache();
+    let url = format!("file://{}", repo.path().display());
+
+    assert!(clone_repo(&url, Some("no-such-branch"), clone_dir(), false).is_err());
+    assert!(!Path::new(CLONE_DIR).exists());
+    assert!(clone_repo(&url, Some("0123456789abcdef"), clone_dir(), false).is_err());
+    assert!(!Path::new(CLONE_DIR).exists());
+}
+
+#[test]
+fn clone_keeps_a_mirror_and_fetches_incrementally() {
+    let (repo, _) = upstream();
+    let work = TempDir::new().unwrap();
+    let _g = CwdGuard::new(work.path());
+    let (cache, _env) = isolated_cache();
+    let url = format!("file://{}", repo.path().display());
+
+    clone_repo(&url, None, clone_dir(), false).unwrap();
+    let mirror = mirror_path(&url).unwrap();
+    assert!(mirror.starts_with(cache.path().join("clemp")), "got: {}", mirror.display());
+    assert!(mirror.join("HEAD").is_file(), "a bare mirror should be cached");
+
+    let v4 = commit(repo.path(), "v4");
+    let sha = clone_repo(&url, None, clone_dir(), false).unwrap();
+    assert_eq!(sha, v4);
+    assert_eq!(cloned_template(), "v4");
+}
+
+#[test]
+fn offline_renders_from_cached_sha_without_fetching() {
+    let (repo, v1) = upstream();
+    let work = TempDir::new().unwrap();
+    let _g = CwdGuard::new(work.path());
+    let _cache = isolated_cache();
+    let url = format!("file://{}", repo.path().display());
+
+    let cached = clone_repo(&url, None, clone_dir(), false).unwrap();
+    commit(repo.path(), "v4");
+
+    let sha = clone_repo(&url, None, clone_dir(), true).unwrap();
+    assert_eq!(sha, cached, "--offline must not pick up upstream commits");
+    assert_eq!(cloned_template(), "v3");
+    assert_eq!(clone_repo(&url, Some("v1"), clone_dir(), true).unwrap(), v1);
+
+    let err = clone_repo(&url, Some("no-such-branch"), clone_dir(), true).unwrap_err().to_string();
+    assert!(err.contains("--offline") && err.contains("no-such-branch"), "got: {err}");
+}
+
+#[test]
+fn offline_without_cache_fails_clearly() {
+    let (repo, _) = upstream();
+    let work = TempDir::new().unwrap();
+    let _g = CwdGuard::new(work.path());
+    let _cache = isolated_cache();
+    let url = format!("file://{}", repo.path().display());
+
+    let err = clone_repo(&url, None, clone_dir(), true).unwrap_err().to_string();
+    assert!(err.contains("no cached copy"), "got: {err}");
+    assert!(err.contains("without --offline"), "got: {err}");
+    assert!(!Path::new(CLONE_DIR).exists());
+}
+
+#[test]
+fn mirror_paths_are_distinct_per_url() {
+    let work = TempDir::new().unwrap();
+    let _g = CwdGuard::new(work.path());
+    let _cache = isolated_cache();
+
+    let a = mirror_path("https://github.com/acme/claude-template.git").unwrap();
+    let b = mirror_path("https://github.com/other/claude-template").unwrap();
+    assert_ne!(a, b);
+    let name = a.file_name().unwrap().to_string_lossy().into_owned();
+    assert!(name.starts_with("claude-template-") && name.ends_with(".git"), "got: {name}");
+}
+
+#[test]
+fn looks_like_sha_distinguishes_hex_from_names() {
+    assert!(looks_like_sha("deadbeef"));
+    assert!(looks_like_sha(&"a".repeat(40)));
+    assert!(!looks_like_sha("abc12"));
+    assert!(!looks_like_sha("stable"));
+    assert!(!looks_like_sha("v1.2.0"));
+    assert!(!looks_like_sha(&"a".repeat(41)));
+}
+
+// ── Local-directory sources ─────────────────────────────────────────────
+
+#[test]
+fn local_plain_directory_is_copied_and_hashed() {
+    let tpl = TempDir::new().unwrap();
+    fs::write(tpl.path().join("CLAUDE.md.jinja"), "local").unwrap();
+    fs::create_dir_all(tpl.path().join("mcp/default")).unwrap();
+    fs::write(tpl.path().join("mcp/default/c7.json"), "{}").unwrap();
+    let work = TempDir::new().unwrap();
+    let _g = CwdGuard::new(work.path());
+
+    let rev = fetch_template(&tpl.path().display().to_string(), None, clone_dir(), false).unwrap();
+    assert_eq!(cloned_template(), "local");
+    assert!(Path::new(CLONE_DIR).join("mcp/default/c7.json").is_file());
+    assert_eq!(rev, hash_template_tree(tpl.path()).unwrap());
+    // Rendering happens inside the copy; the source dir stays pristine.
+    assert!(!tpl.path().join(CLONE_DIR).exists());
+}
+
+#[test]
+fn local_file_url_is_accepted() {
+    let tpl = TempDir::new().unwrap();
+    fs::write(tpl.path().join("CLAUDE.md.jinja"), "via url").unwrap();
+    let work = TempDir::new().unwrap();
+    let _g = CwdGuard::new(work.path());
+
+    fetch_template(&format!("file://{}", tpl.path().display()), None, clone_dir(), false).unwrap();
+    assert_eq!(cloned_template(), "via url");
+}
+
+#[test]
+fn local_tree_hash_tracks_edits_and_renames() {
+    let tpl = TempDir::new().unwrap();
+    fs::write(tpl.path().join("a.md"), "one").unwrap();
+    let h1 = hash_template_tree(tpl.path()).unwrap();
+    assert_eq!(h1, hash_template_tree(tpl.path()).unwrap(), "hash must be stable");
+
+    fs::write(tpl.path().join("a.md"), "two").unwrap();
+    let h2 = hash_template_tree(tpl.path()).unwrap();
+    assert_ne!(h1, h2);
+
+    fs::rename(tpl.path().join("a.md"), tpl.path().join("b.md")).unwrap();
+    assert_ne!(h2, hash_template_tree(tpl.path()).unwrap());
+}
+
+#[test]
+fn local_git_checkout_uses_head_when_clean_and_hash_when_dirty() {
+    let (repo, _) = upstream();
+    let head = git(repo.path(), &["rev-parse", "HEAD"]);
+    let work = TempDir::new().unwrap();
+    let _g = CwdGuard::new(work.path());
+    let source = repo.path().display().to_string();
+
+    assert_eq!(fetch_template(&source, None, clone_dir(), false).unwrap(), head);
+    assert!(!Path::new(CLONE_DIR).join(".git").exists(), ".git must not be copied");
+
+    fs::write(repo.path().join("CLAUDE.md.jinja"), "uncommitted").unwrap();
+    let rev = fetch_template(&source, None, clone_dir(), false).unwrap();
+    assert_ne!(rev, head, "uncommitted edits must change the recorded revision");
+    assert_eq!(cloned_template(), "uncommitted");
+}
+
+#[test]
+fn local_source_rejects_ref_and_missing_dir() {
+    let tpl = TempDir::new().unwrap();
+    let work = TempDir::new().unwrap();
+    let _g = CwdGuard::new(work.path());
+
+    let source = tpl.path().display().to_string();
+    let err = fetch_template(&source, Some("main"), clone_dir(), false).unwrap_err();
+    assert!(err.to_string().contains("--ref"), "got: {err}");
+    assert!(fetch_template("file:///definitely/not/here", None, clone_dir(), false).is_err());
+}
+
+#[test]
+fn local_template_path_detection() {
+    assert!(local_template_path("file:///tmp/t").is_some());
+    assert!(local_template_path("/abs/path").is_some());
+    assert!(local_template_path("./rel").is_some());
+    assert!(local_template_path("../rel").is_some());
+    assert!(local_template_path("https://github.com/acme/t").is_none());
+    assert!(local_template_path("git@github.com:acme/t.git").is_none());
+}
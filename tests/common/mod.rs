@@ -1,5 +1,7 @@
 //! Shared test helpers: scaffold for fake clone directories and CWD guard.
 
+#![allow(dead_code)]
+
 use clemp::CLONE_DIR;
 use std::env;
 use std::fs;
@@ -69,6 +71,14 @@ impl Scaffold {
         }
     }
 
+    pub fn with_lang_hooks(&self, lang: &str, hooks: &[(&str, &str)]) {
+        let dir = self.path().join("hooks").join(lang);
+        fs::create_dir_all(&dir).unwrap();
+        for (name, content) in hooks {
+            fs::write(dir.join(format!("{}.json", name)), content).unwrap();
+        }
+    }
+
     pub fn with_named_hooks(&self, hooks: &[(&str, &str)]) {
         let dir = self.path().join("hooks");
         fs::create_dir_all(&dir).unwrap();
@@ -77,6 +87,28 @@ impl Scaffold {
         }
     }
 
+    // ── Permissions ──────────────────────────────────────────────────
+
+    pub fn with_default_permissions(&self, files: &[(&str, &str)]) {
+        self.with_lang_permissions("default", files);
+    }
+
+    pub fn with_lang_permissions(&self, lang: &str, files: &[(&str, &str)]) {
+        let dir = self.path().join("permissions").join(lang);
+        fs::create_dir_all(&dir).unwrap();
+        for (name, content) in files {
+            fs::write(dir.join(format!("{}.json", name)), content).unwrap();
+        }
+    }
+
+    pub fn with_named_permissions(&self, files: &[(&str, &str)]) {
+        let dir = self.path().join("permissions");
+        fs::create_dir_all(&dir).unwrap();
+        for (name, content) in files {
+            fs::write(dir.join(format!("{}.json", name)), content).unwrap();
+        }
+    }
+
     // ── Settings ─────────────────────────────────────────────────────
 
     pub fn with_settings(&self, content: &str) {
@@ -283,7 +315,7 @@ impl EnvVarGuard {
 
 impl Drop for EnvVarGuard {
     fn drop(&mut self) {
-        for (k, v) in self.keys.drain(..) {
+        for (k, v) in self.keys.drain(..).rev() {
             unsafe {
                 match v {
                     Some(val) => env::set_var(&k, val),
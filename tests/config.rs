@@ -0,0 +1,150 @@
+//! Tests for template profile and stack resolution in `Config`.
+
+use clemp::{Config, DEFAULT_PROFILE};
+use std::collections::BTreeMap;
+
+fn profiles(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+    entries
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[test]
+fn empty_config_resolves_to_none_so_caller_prompts() {
+    let config = Config::default();
+    assert!(config.resolve_template(None).unwrap().is_none());
+}
+
+#[test]
+fn explicit_profile_resolves_through_map() {
+    let config = Config {
+        templates: profiles(&[("org", "https://org/t.git"), ("exp", "https://exp/t.git")]),
+        default_template: Some("org".into()),
+        ..Default::default()
+    };
+    let (name, url) = config.resolve_template(Some("exp")).unwrap().unwrap();
+    assert_eq!(name, "exp");
+    assert_eq!(url, "https://exp/t.git");
+}
+
+#[test]
+fn default_template_used_without_flag() {
+    let config = Config {
+        templates: profiles(&[("org", "https://org/t.git"), ("exp", "https://exp/t.git")]),
+        default_template: Some("org".into()),
+        ..Default::default()
+    };
+    let (name, url) = config.resolve_template(None).unwrap().unwrap();
+    assert_eq!(name, "org");
+    assert_eq!(url, "https://org/t.git");
+}
+
+#[test]
+fn unknown_profile_errors_with_available_names() {
+    let config = Config {
+        templates: profiles(&[("org", "https://org/t.git")]),
+        ..Default::default()
+    };
+    let err = config.resolve_template(Some("nope")).unwrap_err().to_string();
+    assert!(err.contains("Unknown template profile 'nope'"), "got: {err}");
+    assert!(err.contains("org"), "got: {err}");
+    assert!(err.contains("\nAdd it under `templates:`"), "no stray indentation: {err:?}");
+}
+
+#[test]
+fn legacy_gh_repo_acts_as_default_profile() {
+    let config: Config = serde_yaml::from_str("gh-repo: https://legacy/t.git\n").unwrap();
+    let (name, url) = config.resolve_template(None).unwrap().unwrap();
+    assert_eq!(name, DEFAULT_PROFILE);
+    assert_eq!(url, "https://legacy/t.git");
+    let (_, url) = config.resolve_template(Some(DEFAULT_PROFILE)).unwrap().unwrap();
+    assert_eq!(url, "https://legacy/t.git");
+}
+
+#[test]
+fn single_profile_without_default_is_used() {
+    let config = Config {
+        templates: profiles(&[("org", "https://org/t.git")]),
+        ..Default::default()
+    };
+    let (name, _) = config.resolve_template(None).unwrap().unwrap();
+    assert_eq!(name, "org");
+}
+
+#[test]
+fn multiple_profiles_without_default_is_ambiguous() {
+    let config = Config {
+        templates: profiles(&[("org", "https://org/t.git"), ("exp", "https://exp/t.git")]),
+        ..Default::default()
+    };
+    let err = config.resolve_template(None).unwrap_err().to_string();
+    assert!(err.contains("default-template"), "got: {err}");
+}
+
+#[test]
+fn config_yaml_round_trip() {
+    let config = Config {
+        templates: profiles(&[("org", "https://org/t.git")]),
+        default_template: Some("org".into()),
+        ..Default::default()
+    };
+    let yaml = serde_yaml::to_string(&config).unwrap();
+    assert!(yaml.contains("default-template: org"), "got: {yaml}");
+    assert!(!yaml.contains("gh-repo"), "unset legacy key must not be written: {yaml}");
+    let back: Config = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(back.templates, config.templates);
+    assert_eq!(back.default_template, config.default_template);
+}
+
+fn names(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+#[test]
+fn repeated_templates_resolve_in_order() {
+    let config = Config {
+        templates: profiles(&[("org", "https://org/t.git"), ("team", "https://team/t.git")]),
+        default_template: Some("org".into()),
+        ..Default::default()
+    };
+    let sources = config.resolve_templates(&names(&["org", "team"])).unwrap().unwrap();
+    assert_eq!(
+        sources,
+        vec![
+            ("org".to_string(), "https://org/t.git".to_string()),
+            ("team".to_string(), "https://team/t.git".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn default_stack_expands_to_its_members() {
+    let config: Config = serde_yaml::from_str(
+        "templates:\n  org: https://org/t.git\n  team: https://team/t.git\n\
+         stacks:\n  full: [org, team]\ndefault-template: full\n",
+    )
+    .unwrap();
+    let sources = config.resolve_templates(&[]).unwrap().unwrap();
+    let profiles: Vec<&str> = sources.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(profiles, ["org", "team"]);
+
+    let sources = config.resolve_templates(&names(&["team"])).unwrap().unwrap();
+    assert_eq!(sources.len(), 1);
+}
+
+#[test]
+fn stacking_the_same_template_twice_errors() {
+    let config = Config {
+        templates: profiles(&[("org", "https://org/t.git")]),
+        stacks: [("full".to_string(), names(&["org"]))].into_iter().collect(),
+        ..Default::default()
+    };
+    let err = config.resolve_templates(&names(&["full", "org"])).unwrap_err().to_string();
+    assert!(err.contains("more than once"), "got: {err}");
+}
+
+#[test]
+fn no_templates_configured_resolves_stack_to_none() {
+    assert!(Config::default().resolve_templates(&[]).unwrap().is_none());
+}
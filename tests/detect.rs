@@ -0,0 +1,88 @@
+//! Tests for detecting project languages from marker files in the working
+//! tree, including template-declared detection rules.
+
+mod common;
+
+use clemp::{
+    apply_detected_languages, detect_languages, detection_rules, SetupArgs, TemplateManifest,
+};
+use common::Scaffold;
+use std::fs;
+use tempfile::TempDir;
+
+fn project(files: &[&str]) -> TempDir {
+    let dir = TempDir::new().unwrap();
+    for f in files {
+        fs::write(dir.path().join(f), "").unwrap();
+    }
+    dir
+}
+
+fn builtin(files: &[&str]) -> Vec<String> {
+    let dir = project(files);
+    detect_languages(dir.path(), &detection_rules(&TemplateManifest::default()))
+}
+
+#[test]
+fn single_marker_files() {
+    assert_eq!(builtin(&["Cargo.toml"]), vec!["rust"]);
+    assert_eq!(builtin(&["pyproject.toml"]), vec!["python"]);
+    assert_eq!(builtin(&["go.mod"]), vec!["go"]);
+    assert_eq!(builtin(&["Gemfile"]), vec!["ruby"]);
+    assert_eq!(builtin(&["Package.swift"]), vec!["swift"]);
+    assert!(builtin(&["README.md"]).is_empty());
+}
+
+#[test]
+fn package_json_is_typescript_only_with_tsconfig() {
+    assert_eq!(builtin(&["package.json", "tsconfig.json"]), vec!["typescript"]);
+    assert_eq!(builtin(&["package.json"]), vec!["javascript"]);
+    assert!(builtin(&["tsconfig.json"]).is_empty());
+}
+
+#[test]
+fn wildcard_markers_match_file_names() {
+    assert_eq!(builtin(&["App.csproj"]), vec!["csharp"]);
+    assert!(builtin(&["csproj"]).is_empty());
+}
+
+#[test]
+fn several_languages_detected_once_each() {
+    let detected = builtin(&["Cargo.toml", "pyproject.toml", "requirements.txt"]);
+    assert_eq!(detected, vec!["rust", "python"]);
+}
+
+#[test]
+fn template_rules_extend_and_precede_builtin_ones() {
+    let manifest: TemplateManifest = serde_yaml::from_str(
+        "detect:\n  - language: kotlin\n    markers: [build.gradle.kts]\n\
+         \x20 - language: bun\n    markers: [package.json, bun.lockb]\n",
+    )
+    .unwrap();
+    let dir = project(&["build.gradle.kts", "Cargo.toml", "package.json", "bun.lockb"]);
+    let detected = detect_languages(dir.path(), &detection_rules(&manifest));
+    assert_eq!(detected, vec!["kotlin", "bun", "rust", "javascript"]);
+}
+
+#[test]
+fn explicit_languages_skip_detection() {
+    let s = Scaffold::new();
+    s.with_template("", &[("rust.md", "rust rules")]);
+    let cwd = project(&["Cargo.toml", "go.mod"]);
+
+    let mut args = SetupArgs { languages: vec!["python".into()], ..Default::default() };
+    apply_detected_languages(&mut args, s.path(), cwd.path()).unwrap();
+    assert_eq!(args.languages, vec!["python"]);
+}
+
+#[test]
+fn languages_the_template_lacks_are_not_offered() {
+    let s = Scaffold::new();
+    s.with_template("", &[("typescript.md", "ts rules")]);
+    let cwd = project(&["go.mod"]);
+
+    // Nothing supported → no confirmation prompt, nothing filled in.
+    let mut args = SetupArgs::default();
+    apply_detected_languages(&mut args, s.path(), cwd.path()).unwrap();
+    assert!(args.languages.is_empty());
+}
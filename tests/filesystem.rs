@@ -1,16 +1,22 @@
-//! Tests for filesystem operations: copy, gitignore, conflicts, cleanup,
+//! Tests for filesystem operations: copy, gitignore, conflicts, ScratchDir,
 //! copy_conditional_dir, and run_setup integration.
 
 mod common;
 
 use clemp::{
-    cleanup, collect_conditional_dir_sources, collect_conflicts, collect_copy_files_sources,
-    copy_conditional_dir, copy_dir_recursive, copy_files, run_setup, update_gitignore,
-    RenderInputs, SetupArgs, CLONE_DIR,
+    collect_conditional_dir_sources, collect_conflicts, collect_copy_files_sources,
+    copy_conditional_dir, copy_dir_recursive, copy_files, remove_gitignore_lines, run_setup,
+    stale_gitignore_lines, update_gitignore, Placeholders, RenderInputs, ScratchDir, SetupArgs, CLONE_DIR,
 };
 
-fn ri<'a>(args: &'a SetupArgs) -> RenderInputs<'a> {
-    RenderInputs { setup: args, sticky_mcp: &[], sticky_hooks: &[] }
+fn ri<'a>(args: &'a SetupArgs, placeholders: &'a Placeholders) -> RenderInputs<'a> {
+    RenderInputs {
+        setup: args,
+        sticky_mcp: &[],
+        sticky_hooks: &[],
+        sticky_permissions: &[],
+        placeholders,
+    }
 }
 use common::{setup_gitignore_test, setup_gitignore_test_with_langs, CwdGuard, Scaffold};
 use serde_json::Value;
@@ -456,6 +462,50 @@ fn gitignore_subdirectory_ignored() {
     assert!(!content.contains("node_modules/"));
 }
 
+// ── stale_gitignore_lines / remove_gitignore_lines ──────────────────────
+
+#[test]
+fn stale_gitignore_only_touches_lines_clemp_appended() {
+    let tpl = TempDir::new().unwrap();
+    let additions = tpl.path().join("gitignore-additions");
+    fs::create_dir_all(&additions).unwrap();
+    fs::write(additions.join("default.gitignore"), ".claude/\n").unwrap();
+    fs::write(additions.join("python.gitignore"), "__pycache__/\n.venv/\n").unwrap();
+
+    let project = TempDir::new().unwrap();
+    fs::write(project.path().join(".gitignore"), ".venv/\n").unwrap();
+    update_gitignore(tpl.path(), project.path(), &["python".into()]).unwrap();
+    let before = fs::read_to_string(project.path().join(".gitignore")).unwrap();
+    assert_eq!(before, ".venv/\n\n# Claude related\n.claude/\n__pycache__/\n");
+
+    // `.venv/` is in the python fragment, but the user wrote it.
+    let stale = stale_gitignore_lines(tpl.path(), project.path(), &["python".into()], &[]).unwrap();
+    assert_eq!(stale, ["__pycache__/"]);
+
+    // A user-written copy outside the block survives removal.
+    fs::write(project.path().join(".gitignore"), format!("__pycache__/\n{before}")).unwrap();
+    remove_gitignore_lines(project.path(), &stale).unwrap();
+    assert_eq!(
+        fs::read_to_string(project.path().join(".gitignore")).unwrap(),
+        "__pycache__/\n.venv/\n\n# Claude related\n.claude/\n"
+    );
+}
+
+#[test]
+fn remove_gitignore_lines_drops_emptied_blocks() {
+    let project = TempDir::new().unwrap();
+    fs::write(
+        project.path().join(".gitignore"),
+        "target/\n\n# Claude related\n.claude/\n\n# Claude related\n__pycache__/\n",
+    )
+    .unwrap();
+    remove_gitignore_lines(project.path(), &["__pycache__/".into()]).unwrap();
+    assert_eq!(
+        fs::read_to_string(project.path().join(".gitignore")).unwrap(),
+        "target/\n\n# Claude related\n.claude/\n"
+    );
+}
+
 // ── copy_files ──────────────────────────────────────────────────────────
 
 #[test]
@@ -610,23 +660,66 @@ fn copy_conditional_skills_recursive() {
     assert!(dest.path().join("my-skill/README.md").exists());
 }
 
-// ── cleanup ─────────────────────────────────────────────────────────────
+// ── ScratchDir ──────────────────────────────────────────────────────────
 
 #[test]
-fn cleanup_removes_directory() {
-    let tmp = TempDir::new().unwrap();
-    let dir = tmp.path().join("to_remove");
-    fs::create_dir_all(&dir).unwrap();
-    fs::write(dir.join("file.txt"), "data").unwrap();
-
-    cleanup(&dir).unwrap();
-    assert!(!dir.exists());
+fn scratch_dirs_are_private_unique_and_outside_cwd() {
+    let a = ScratchDir::new().unwrap();
+    let b = ScratchDir::new().unwrap();
+    assert_ne!(a.path(), b.path(), "concurrent runs must not share a clone dir");
+    assert!(a.path().starts_with(std::env::temp_dir()));
+    assert_eq!(a.clone_dir(), a.path().join(CLONE_DIR));
+    assert!(!a.clone_dir().exists(), "the checkout is created by the fetch");
 }
 
 #[test]
-fn cleanup_nonexistent_errors() {
-    let result = cleanup(Path::new("/tmp/clemp_test_nonexistent_dir_12345"));
-    assert!(result.is_err());
+fn scratch_dir_is_removed_on_drop_and_on_panic() {
+    let scratch = ScratchDir::new().unwrap();
+    let root = scratch.path().to_path_buf();
+    fs::create_dir_all(scratch.clone_dir()).unwrap();
+    fs::write(scratch.clone_dir().join("CLAUDE.md.jinja"), "x").unwrap();
+    drop(scratch);
+    assert!(!root.exists());
+
+    let root = std::panic::catch_unwind(|| {
+        let scratch = ScratchDir::new().unwrap();
+        fs::create_dir_all(scratch.clone_dir()).unwrap();
+        let root = scratch.path().to_path_buf();
+        std::panic::panic_any(root);
+    })
+    .unwrap_err()
+    .downcast::<PathBuf>()
+    .unwrap();
+    assert!(!root.exists(), "unwinding must delete the scratch dir");
+}
+
+#[test]
+fn scratch_dir_drop_tolerates_contents_already_removed() {
+    let scratch = ScratchDir::new().unwrap();
+    let nested = scratch.clone_dir().join("mcp/default");
+    fs::create_dir_all(&nested).unwrap();
+    fs::write(nested.join("c7.json"), "{}").unwrap();
+    fs::remove_dir_all(scratch.clone_dir()).unwrap();
+    let root = scratch.path().to_path_buf();
+    drop(scratch);
+    assert!(!root.exists());
+
+    // Removed out from under us entirely (e.g. by the interrupt handler).
+    let scratch = ScratchDir::new().unwrap();
+    fs::remove_dir_all(scratch.path()).unwrap();
+    drop(scratch);
+}
+
+#[test]
+fn several_scratch_dirs_can_register_for_interrupt_cleanup() {
+    let a = ScratchDir::new().unwrap();
+    let b = ScratchDir::new().unwrap();
+    a.remove_on_interrupt().unwrap();
+    b.remove_on_interrupt().unwrap();
+    let root = b.path().to_path_buf();
+    drop(b);
+    assert!(!root.exists());
+    ScratchDir::new().unwrap().remove_on_interrupt().unwrap();
 }
 
 // ── Error cleanup: gitignore removal ────────────────────────────────────
@@ -709,7 +802,14 @@ fn run_setup_full_flow() {
         ..Default::default()
     };
 
-    run_setup(&ri(&args), s.path(), Path::new("."), true, false).unwrap();
+    run_setup(
+        &ri(&args, &Placeholders::default()),
+        s.path(),
+        Path::new("."),
+        true,
+        false,
+    )
+    .unwrap();
 
     // .gitignore created with additions
     let gitignore = fs::read_to_string(workdir.path().join(".gitignore")).unwrap();
@@ -809,7 +909,13 @@ fn run_setup_aborts_cleanly_on_copy_files_conflict() {
 
     let args = SetupArgs::default();
 
-    let result = run_setup(&ri(&args), s.path(), Path::new("."), true, false);
+    let result = run_setup(
+        &ri(&args, &Placeholders::default()),
+        s.path(),
+        Path::new("."),
+        true,
+        false,
+    );
     assert!(result.is_err());
     assert!(result.unwrap_err().to_string().contains("CLAUDE.md"));
 
@@ -841,7 +947,13 @@ fn run_setup_aborts_cleanly_on_copied_dir_conflict() {
 
     let args = SetupArgs::default();
 
-    let result = run_setup(&ri(&args), s.path(), Path::new("."), true, false);
+    let result = run_setup(
+        &ri(&args, &Placeholders::default()),
+        s.path(),
+        Path::new("."),
+        true,
+        false,
+    );
     assert!(result.is_err());
     assert!(result.unwrap_err().to_string().contains("AGENTS.md"));
 
@@ -959,7 +1071,14 @@ fn run_setup_with_named_hooks_and_mcps() {
         ..Default::default()
     };
 
-    run_setup(&ri(&args), s.path(), Path::new("."), true, false).unwrap();
+    run_setup(
+        &ri(&args, &Placeholders::default()),
+        s.path(),
+        Path::new("."),
+        true,
+        false,
+    )
+    .unwrap();
 
     // Settings has both default + blocker hooks merged
     let settings: Value = serde_json::from_str(
@@ -1017,7 +1136,14 @@ fn run_setup_with_lang_conditionals() {
         ..Default::default()
     };
 
-    run_setup(&ri(&args), s.path(), Path::new("."), true, false).unwrap();
+    run_setup(
+        &ri(&args, &Placeholders::default()),
+        s.path(),
+        Path::new("."),
+        true,
+        false,
+    )
+    .unwrap();
 
     // Commands
     assert!(workdir.path().join(".claude/commands/base.md").exists());
@@ -1067,7 +1193,14 @@ fn run_setup_multiple_languages() {
         ..Default::default()
     };
 
-    run_setup(&ri(&args), s.path(), Path::new("."), true, false).unwrap();
+    run_setup(
+        &ri(&args, &Placeholders::default()),
+        s.path(),
+        Path::new("."),
+        true,
+        false,
+    )
+    .unwrap();
 
     // CLAUDE.md contains both language rules
     let claude = fs::read_to_string(workdir.path().join("CLAUDE.md")).unwrap();
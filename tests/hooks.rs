@@ -2,7 +2,7 @@
 
 mod common;
 
-use clemp::{assemble_hooks_json, build_settings};
+use clemp::{assemble_hooks_json, build_settings, AssemblyResult};
 use common::Scaffold;
 use serde_json::Value;
 use std::collections::HashSet;
@@ -18,8 +18,8 @@ fn default_hooks_always_applied() {
     s.with_settings(r#"{"permissions": {"allow": []}}"#);
     s.with_default_hooks(&[("sound", r#"{"Notification": [{"command": "beep"}]}"#)]);
 
-    let hooks = assemble_hooks_json(&[], &[], &empty(), s.path()).unwrap();
-    build_settings(&hooks, &[], &[], s.path()).unwrap();
+    let hooks = assemble_hooks_json(&[], &[], &[], &empty(), s.path()).unwrap();
+    build_settings(&hooks, &AssemblyResult::default(), &[], &[], &[], &[], s.path()).unwrap();
 
     let content = fs::read_to_string(s.path().join(".claude/settings.local.json")).unwrap();
     let val: Value = serde_json::from_str(&content).unwrap();
@@ -39,8 +39,8 @@ fn named_hooks_merged_with_defaults() {
         r#"{"PreToolUse": [{"command": "block"}], "Notification": [{"command": "notify-block"}]}"#,
     )]);
 
-    let hooks = assemble_hooks_json(&["blocker".into()], &[], &empty(), s.path()).unwrap();
-    build_settings(&hooks, &[], &[], s.path()).unwrap();
+    let hooks = assemble_hooks_json(&[], &["blocker".into()], &[], &empty(), s.path()).unwrap();
+    build_settings(&hooks, &AssemblyResult::default(), &[], &[], &[], &[], s.path()).unwrap();
 
     let content = fs::read_to_string(s.path().join(".claude/settings.local.json")).unwrap();
     let val: Value = serde_json::from_str(&content).unwrap();
@@ -58,7 +58,7 @@ fn named_hook_not_found_errors() {
     s.with_settings("{}");
     fs::create_dir_all(s.path().join("hooks")).unwrap();
 
-    let result = assemble_hooks_json(&["nonexistent".into()], &[], &empty(), s.path());
+    let result = assemble_hooks_json(&[], &["nonexistent".into()], &[], &empty(), s.path());
     assert!(result.is_err());
     assert!(result.unwrap_err().to_string().contains("not found"));
 }
@@ -68,8 +68,8 @@ fn no_hooks_dir_empty_hooks() {
     let s = Scaffold::new();
     s.with_settings(r#"{"existing": true}"#);
 
-    let hooks = assemble_hooks_json(&[], &[], &empty(), s.path()).unwrap();
-    build_settings(&hooks, &[], &[], s.path()).unwrap();
+    let hooks = assemble_hooks_json(&[], &[], &[], &empty(), s.path()).unwrap();
+    build_settings(&hooks, &AssemblyResult::default(), &[], &[], &[], &[], s.path()).unwrap();
 
     let content = fs::read_to_string(s.path().join(".claude/settings.local.json")).unwrap();
     let val: Value = serde_json::from_str(&content).unwrap();
@@ -82,8 +82,8 @@ fn enabled_mcp_servers_set() {
     let s = Scaffold::new();
     s.with_settings("{}");
 
-    let hooks = assemble_hooks_json(&[], &[], &empty(), s.path()).unwrap();
-    build_settings(&hooks, &[], &["context7".into(), "textbelt".into()], s.path()).unwrap();
+    let hooks = assemble_hooks_json(&[], &[], &[], &empty(), s.path()).unwrap();
+    build_settings(&hooks, &AssemblyResult::default(), &[], &["context7".into(), "textbelt".into()], &[], &[], s.path()).unwrap();
 
     let content = fs::read_to_string(s.path().join(".claude/settings.local.json")).unwrap();
     let val: Value = serde_json::from_str(&content).unwrap();
@@ -99,8 +99,8 @@ fn settings_created_if_no_base_file() {
     // No settings.local.json at root
     s.with_default_hooks(&[("sound", r#"{"Notification": [{"command": "beep"}]}"#)]);
 
-    let hooks = assemble_hooks_json(&[], &[], &empty(), s.path()).unwrap();
-    build_settings(&hooks, &[], &["ctx7".into()], s.path()).unwrap();
+    let hooks = assemble_hooks_json(&[], &[], &[], &empty(), s.path()).unwrap();
+    build_settings(&hooks, &AssemblyResult::default(), &[], &["ctx7".into()], &[], &[], s.path()).unwrap();
 
     let content = fs::read_to_string(s.path().join(".claude/settings.local.json")).unwrap();
     let val: Value = serde_json::from_str(&content).unwrap();
@@ -119,7 +119,7 @@ fn excluded_hooks_filter_default_layer() {
     let mut excluded = HashSet::new();
     excluded.insert("unwanted".into());
 
-    let hooks = assemble_hooks_json(&[], &[], &excluded, s.path()).unwrap();
+    let hooks = assemble_hooks_json(&[], &[], &[], &excluded, s.path()).unwrap();
     assert!(!hooks.snapshottable_stems.contains(&"unwanted".to_string()));
     assert!(hooks.snapshottable_stems.contains(&"sound".to_string()));
     assert!(!hooks
@@ -128,3 +128,49 @@ fn excluded_hooks_filter_default_layer() {
         .unwrap()
         .contains_key("PreToolUse"));
 }
+
+#[test]
+fn language_hooks_applied_for_resolved_languages_only() {
+    let s = Scaffold::new();
+    s.with_settings("{}");
+    s.with_default_hooks(&[("sound", r#"{"Notification": [{"command": "beep"}]}"#)]);
+    s.with_lang_hooks("rust", &[("clippy", r#"{"PostToolUse": [{"command": "cargo clippy"}]}"#)]);
+
+    let hooks = assemble_hooks_json(&["rust".into()], &[], &[], &empty(), s.path()).unwrap();
+    assert_eq!(hooks.rendered["PostToolUse"].as_array().unwrap().len(), 1);
+    assert!(
+        !hooks.snapshottable_stems.contains(&"clippy".to_string()),
+        "language stems stay dynamic"
+    );
+
+    let hooks = assemble_hooks_json(&["typescript".into()], &[], &[], &empty(), s.path()).unwrap();
+    assert!(!hooks.rendered.as_object().unwrap().contains_key("PostToolUse"));
+
+    let mut excluded = HashSet::new();
+    excluded.insert("clippy".into());
+    let hooks = assemble_hooks_json(&["rust".into()], &[], &[], &excluded, s.path()).unwrap();
+    assert!(!hooks.rendered.as_object().unwrap().contains_key("PostToolUse"));
+}
+
+#[test]
+fn language_hook_replaces_default_hook_with_the_same_stem() {
+    let s = Scaffold::new();
+    s.with_settings("{}");
+    s.with_default_hooks(&[("format", r#"{"PostToolUse": [{"command": "prettier"}]}"#)]);
+    s.with_lang_hooks("rust", &[("format", r#"{"PostToolUse": [{"command": "cargo fmt"}]}"#)]);
+    s.with_lang_hooks("python", &[("format", r#"{"PostToolUse": [{"command": "black"}]}"#)]);
+
+    let hooks = assemble_hooks_json(&["rust".into()], &[], &[], &empty(), s.path()).unwrap();
+    assert_eq!(hooks.rendered["PostToolUse"], serde_json::json!([{"command": "cargo fmt"}]));
+
+    // Each language's file still applies alongside the other's.
+    let hooks =
+        assemble_hooks_json(&["rust".into(), "python".into()], &[], &[], &empty(), s.path()).unwrap();
+    assert_eq!(
+        hooks.rendered["PostToolUse"],
+        serde_json::json!([{"command": "cargo fmt"}, {"command": "black"}])
+    );
+
+    let hooks = assemble_hooks_json(&[], &["format".into()], &[], &empty(), s.path()).unwrap();
+    assert_eq!(hooks.rendered["PostToolUse"], serde_json::json!([{"command": "prettier"}]));
+}
@@ -0,0 +1,274 @@
+//! Tests for template inheritance via `clemp-template.yaml` `extends:` and
+//! `--template` stacking — overlay semantics, ancestor chains, cycle
+//! detection, and manifest handling.
+
+use clemp::{
+    collect_copy_files_sources, detect_languages, hash_template_tree, prepare_template,
+    prepare_template_stack, resolve_shared_settings, LanguageAliases, LockedSource, SettingsPart,
+    TemplateManifest, TemplateSource, TEMPLATE_MANIFEST_NAME,
+};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn write(root: &Path, rel: &str, content: &str) {
+    let path = root.join(rel);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+fn read(root: &Path, rel: &str) -> String {
+    fs::read_to_string(root.join(rel)).unwrap()
+}
+
+fn extends(repo: &str) -> String {
+    format!("extends:\n  repo: {repo}\n")
+}
+
+#[test]
+fn template_without_manifest_has_no_parents() {
+    let tpl = TempDir::new().unwrap();
+    write(tpl.path(), "CLAUDE.md.jinja", "solo");
+    let work = TempDir::new().unwrap();
+    let dest = work.path().join("clone");
+
+    let rev = prepare_template(&tpl.path().display().to_string(), None, &[], &dest, false).unwrap();
+    assert!(rev.parents.is_empty());
+    assert_eq!(read(&dest, "CLAUDE.md.jinja"), "solo");
+}
+
+#[test]
+fn child_overrides_parent_files_and_inherits_the_rest() {
+    let parent = TempDir::new().unwrap();
+    write(parent.path(), "CLAUDE.md.jinja", "parent template");
+    write(parent.path(), "mcp/default/a.json", r#"{"a": {"from": "parent"}}"#);
+    write(parent.path(), "mcp/default/b.json", r#"{"b": {"from": "parent"}}"#);
+    write(parent.path(), "commands/default/review.md", "parent review");
+
+    let child = TempDir::new().unwrap();
+    write(child.path(), TEMPLATE_MANIFEST_NAME, &extends(&parent.path().display().to_string()));
+    write(child.path(), "mcp/default/b.json", r#"{"b": {"from": "child"}}"#);
+    write(child.path(), "mcp/default/c.json", r#"{"c": {"from": "child"}}"#);
+
+    let work = TempDir::new().unwrap();
+    let dest = work.path().join("clone");
+    let rev = prepare_template(&child.path().display().to_string(), None, &[], &dest, false).unwrap();
+
+    assert_eq!(read(&dest, "CLAUDE.md.jinja"), "parent template");
+    assert!(read(&dest, "mcp/default/a.json").contains("parent"));
+    assert!(read(&dest, "mcp/default/b.json").contains("child"), "child must win per stem");
+    assert!(read(&dest, "mcp/default/c.json").contains("child"));
+    assert_eq!(read(&dest, "commands/default/review.md"), "parent review");
+
+    assert_eq!(rev.sha, hash_template_tree(child.path()).unwrap());
+    assert_eq!(
+        rev.parents,
+        vec![LockedSource {
+            repo: parent.path().display().to_string(),
+            git_ref: None,
+            sha: hash_template_tree(parent.path()).unwrap(),
+        }]
+    );
+
+    // Only the assembled tree is left behind — no per-layer scratch dirs.
+    let leftovers: Vec<_> = fs::read_dir(work.path()).unwrap().collect();
+    assert_eq!(leftovers.len(), 1);
+}
+
+#[test]
+fn ancestor_chain_with_relative_local_parents() {
+    let root = TempDir::new().unwrap();
+    write(root.path(), "company/CLAUDE.md.jinja", "company");
+    write(root.path(), "company/hooks/default/sound.json", "{}");
+    write(root.path(), "org/clemp-template.yaml", &extends("../company"));
+    write(root.path(), "org/CLAUDE.md.jinja", "org");
+    write(root.path(), "team/clemp-template.yaml", &extends("../org"));
+    write(root.path(), "team/skills/default/x.md", "team skill");
+
+    let work = TempDir::new().unwrap();
+    let dest = work.path().join("clone");
+    let source = root.path().join("team").display().to_string();
+    let rev = prepare_template(&source, None, &[], &dest, false).unwrap();
+
+    assert_eq!(read(&dest, "CLAUDE.md.jinja"), "org", "nearest ancestor wins");
+    assert!(dest.join("hooks/default/sound.json").is_file());
+    assert_eq!(read(&dest, "skills/default/x.md"), "team skill");
+    assert_eq!(rev.parents.len(), 2);
+    assert!(rev.parents[0].repo.ends_with("org"), "nearest parent first: {:?}", rev.parents);
+    assert!(rev.parents[1].repo.ends_with("company"));
+}
+
+#[test]
+fn file_in_child_replaces_directory_in_parent() {
+    let root = TempDir::new().unwrap();
+    write(root.path(), "base/docs/guide.md", "dir entry");
+    write(root.path(), "child/clemp-template.yaml", &extends("../base"));
+    write(root.path(), "child/docs", "now a file");
+
+    let work = TempDir::new().unwrap();
+    let dest = work.path().join("clone");
+    prepare_template(&root.path().join("child").display().to_string(), None, &[], &dest, false).unwrap();
+    assert_eq!(read(&dest, "docs"), "now a file");
+}
+
+#[test]
+fn inheritance_cycle_is_rejected_and_cleaned_up() {
+    let root = TempDir::new().unwrap();
+    write(root.path(), "a/clemp-template.yaml", &extends("../b"));
+    write(root.path(), "b/clemp-template.yaml", &extends("../a"));
+
+    let work = TempDir::new().unwrap();
+    let dest = work.path().join("clone");
+    let source = root.path().join("a").display().to_string();
+    let err = prepare_template(&source, None, &[], &dest, false).unwrap_err().to_string();
+    assert!(err.contains("cycle"), "got: {err}");
+    assert_eq!(fs::read_dir(work.path()).unwrap().count(), 0, "nothing may be left behind");
+}
+
+#[test]
+fn manifest_parses_ref_and_is_never_copied_into_projects() {
+    let tpl = TempDir::new().unwrap();
+    write(
+        tpl.path(),
+        TEMPLATE_MANIFEST_NAME,
+        "extends:\n  repo: https://example.test/base.git\n  ref: v3\n",
+    );
+    let manifest = TemplateManifest::load(tpl.path()).unwrap();
+    let parent = manifest.extends.unwrap();
+    assert_eq!(parent.repo, "https://example.test/base.git");
+    assert_eq!(parent.git_ref.as_deref(), Some("v3"));
+
+    let sources = collect_copy_files_sources(tpl.path()).unwrap();
+    assert!(sources.is_empty(), "manifest must be excluded from copy_files: {sources:?}");
+}
+
+#[test]
+fn child_manifest_layers_over_the_parent_tables() {
+    let parent = TempDir::new().unwrap();
+    write(
+        parent.path(),
+        TEMPLATE_MANIFEST_NAME,
+        "name: company\n\
+         aliases:\n  kotlin: [kt]\n\
+         implies:\n  svelte: [typescript, html]\n  astro: [typescript]\n\
+         detect:\n  - language: kotlin\n    markers: [build.gradle.kts]\n",
+    );
+    let child = TempDir::new().unwrap();
+    write(
+        child.path(),
+        TEMPLATE_MANIFEST_NAME,
+        &format!(
+            "{}aliases:\n  kotlin: [kts]\n  zig: [ziglang]\nimplies:\n  astro: [javascript]\n",
+            extends(&parent.path().display().to_string())
+        ),
+    );
+
+    let work = TempDir::new().unwrap();
+    let dest = work.path().join("clone");
+    prepare_template(&child.path().display().to_string(), None, &[], &dest, false).unwrap();
+
+    let aliases = LanguageAliases::load(&dest).unwrap();
+    assert_eq!(aliases.canonical("kt"), "kotlin", "parent alias survives the child's manifest");
+    assert_eq!(aliases.canonical("kts"), "kotlin");
+    assert_eq!(aliases.canonical("ziglang"), "zig");
+
+    let manifest = TemplateManifest::load(&dest).unwrap();
+    assert_eq!(manifest.name.as_deref(), Some("company"));
+    assert_eq!(manifest.implies["svelte"], ["typescript", "html"]);
+    assert_eq!(manifest.implies["astro"], ["javascript"], "child entry replaces the parent's");
+    assert!(manifest.extends.is_none());
+
+    let project = TempDir::new().unwrap();
+    write(project.path(), "build.gradle.kts", "");
+    assert_eq!(detect_languages(project.path(), &manifest.detect), ["kotlin"]);
+}
+
+#[test]
+fn parent_shared_settings_apply_unless_the_child_sets_its_own() {
+    let parent = TempDir::new().unwrap();
+    write(parent.path(), TEMPLATE_MANIFEST_NAME, "shared-settings: [hooks, permissions]\n");
+    let child = TempDir::new().unwrap();
+    let repo = parent.path().display().to_string();
+    write(child.path(), TEMPLATE_MANIFEST_NAME, &format!("{}name: team\n", extends(&repo)));
+
+    let work = TempDir::new().unwrap();
+    let dest = work.path().join("clone");
+    prepare_template(&child.path().display().to_string(), None, &[], &dest, false).unwrap();
+    assert_eq!(
+        resolve_shared_settings(&[], &[], &dest).unwrap(),
+        [SettingsPart::Hooks, SettingsPart::Permissions]
+    );
+
+    // An explicit empty list clears the inherited default.
+    write(child.path(), TEMPLATE_MANIFEST_NAME, &format!("{}shared-settings: []\n", extends(&repo)));
+    let dest = work.path().join("clone2");
+    prepare_template(&child.path().display().to_string(), None, &[], &dest, false).unwrap();
+    assert!(resolve_shared_settings(&[], &[], &dest).unwrap().is_empty());
+}
+
+// ── Stacked templates ───────────────────────────────────────────────────
+
+fn source(profile: &str, dir: &Path) -> TemplateSource {
+    TemplateSource {
+        profile: Some(profile.into()),
+        repo: dir.display().to_string(),
+        git_ref: None,
+    }
+}
+
+#[test]
+fn stacked_templates_overlay_in_order_with_one_revision_each() {
+    let base = TempDir::new().unwrap();
+    write(base.path(), "CLAUDE.md.jinja", "org template");
+    write(base.path(), "mcp/default/a.json", r#"{"a": {"from": "org"}}"#);
+    write(base.path(), "hooks/default/fmt.json", "org hook");
+
+    let parent = TempDir::new().unwrap();
+    write(parent.path(), "commands/default/review.md", "team-base review");
+
+    let team = TempDir::new().unwrap();
+    write(team.path(), TEMPLATE_MANIFEST_NAME, &extends(&parent.path().display().to_string()));
+    write(team.path(), "mcp/default/a.json", r#"{"a": {"from": "team"}}"#);
+    write(team.path(), "mcp/default/b.json", r#"{"b": {"from": "team"}}"#);
+
+    let me = TempDir::new().unwrap();
+    write(me.path(), "hooks/default/fmt.json", "my hook");
+    write(me.path(), TEMPLATE_MANIFEST_NAME, "aliases:\n  zig: [ziglang]\n");
+    write(base.path(), TEMPLATE_MANIFEST_NAME, "aliases:\n  kotlin: [kt]\n");
+
+    let work = TempDir::new().unwrap();
+    let dest = work.path().join("clone");
+    let sources = [source("org", base.path()), source("team", team.path()), source("me", me.path())];
+    let revisions = prepare_template_stack(&sources, &[], &dest, false).unwrap();
+
+    assert_eq!(revisions.len(), 3);
+    let profiles: Vec<_> = revisions.iter().map(|r| r.profile.as_deref().unwrap()).collect();
+    assert_eq!(profiles, ["org", "team", "me"]);
+    assert!(revisions[0].parents.is_empty());
+    assert_eq!(revisions[1].parents.len(), 1, "overlay keeps its own ancestors");
+    assert_eq!(revisions[1].sha, hash_template_tree(team.path()).unwrap());
+
+    assert_eq!(read(&dest, "CLAUDE.md.jinja"), "org template");
+    assert!(read(&dest, "mcp/default/a.json").contains("team"), "later source wins per stem");
+    assert!(read(&dest, "mcp/default/b.json").contains("team"));
+    assert_eq!(read(&dest, "hooks/default/fmt.json"), "my hook");
+    assert_eq!(read(&dest, "commands/default/review.md"), "team-base review");
+    let aliases = LanguageAliases::load(&dest).unwrap();
+    assert_eq!(aliases.canonical("kt"), "kotlin", "earlier sources' manifests still apply");
+    assert_eq!(aliases.canonical("ziglang"), "zig");
+
+    let leftovers: Vec<_> = fs::read_dir(work.path()).unwrap().collect();
+    assert_eq!(leftovers.len(), 1, "per-source staging dirs must be removed");
+}
+
+#[test]
+fn failed_stack_source_cleans_up() {
+    let base = TempDir::new().unwrap();
+    write(base.path(), "CLAUDE.md.jinja", "org template");
+    let work = TempDir::new().unwrap();
+    let dest = work.path().join("clone");
+
+    let sources = [source("org", base.path()), source("gone", &work.path().join("missing"))];
+    assert!(prepare_template_stack(&sources, &[], &dest, false).is_err());
+    assert_eq!(fs::read_dir(work.path()).unwrap().count(), 0);
+}
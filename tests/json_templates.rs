@@ -0,0 +1,175 @@
+//! Tests for `*.json.jinja` contributors in `mcp/`, `hooks/` and the base
+//! `settings.local.json.jinja`.
+
+mod common;
+
+use clemp::{list_category, render_json_templates, run_setup, Placeholders, RenderInputs, SetupArgs};
+use common::{CwdGuard, Scaffold};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn write(root: &Path, rel: &str, content: &str) {
+    let path = root.join(rel);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+fn read_json(rel: &str) -> Value {
+    serde_json::from_str(&fs::read_to_string(rel).unwrap()).unwrap()
+}
+
+fn scaffold() -> Scaffold {
+    let s = Scaffold::new();
+    s.with_template("{{ lang_rules }}", &[("rust.md", "rust rules"), ("typescript.md", "ts rules")]);
+    s.with_default_mcps(&[("context7", r#"{"context7": {"url": "c7"}}"#)]);
+    write(
+        s.path(),
+        "mcp/default/lsp.json.jinja",
+        r#"{"lsp": {"command": "lsp", "args": [{% if lang.rust %}"--rust"{% else %}"--generic"{% endif %}]}}"#,
+    );
+    write(s.path(), "mcp/pg.json.jinja", r#"{"pg": {"url": "{{ 'with-c7' if mcp_contributors.context7 else 'alone' }}"}}"#);
+    write(
+        s.path(),
+        "hooks/default/fmt.json.jinja",
+        r#"{"PostToolUse": [{"command": "{% if lang.rust %}cargo fmt{% else %}prettier{% endif %}"}]}"#,
+    );
+    write(
+        s.path(),
+        "settings.local.json.jinja",
+        r#"{"permissions": {"allow": [{% if lang.typescript %}"Bash(npm test)"{% endif %}]}{% if mcp.pg %}, "pg": true{% endif %}}"#,
+    );
+    s
+}
+
+fn setup(s: &Scaffold, args: &SetupArgs) {
+    run_setup(
+        &RenderInputs {
+            setup: args,
+            sticky_mcp: &[],
+            sticky_hooks: &[],
+            sticky_permissions: &[],
+            placeholders: &Placeholders::default(),
+        },
+        s.path(),
+        Path::new("."),
+        true,
+        false,
+    )
+    .unwrap();
+}
+
+#[test]
+fn contributors_render_with_language_and_mcp_context() {
+    let s = scaffold();
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+
+    let args = SetupArgs {
+        languages: vec!["rust".into()],
+        mcp: vec!["pg".into()],
+        ..Default::default()
+    };
+    setup(&s, &args);
+
+    let mcp = read_json(".mcp.json");
+    assert_eq!(mcp["mcpServers"]["lsp"]["args"], json!(["--rust"]));
+    assert_eq!(mcp["mcpServers"]["pg"]["url"], "with-c7");
+
+    let settings = read_json(".claude/settings.local.json");
+    assert_eq!(settings["hooks"]["PostToolUse"][0]["command"], "cargo fmt");
+    assert_eq!(settings["permissions"]["allow"], json!([]));
+    assert_eq!(settings["pg"], true, "settings see the assembled server names");
+    assert!(!Path::new("settings.local.json.jinja").exists());
+    assert!(!Path::new("settings.local.json").exists());
+}
+
+#[test]
+fn other_languages_render_their_own_branch() {
+    let s = scaffold();
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+
+    setup(&s, &SetupArgs { languages: vec!["ts".into()], ..Default::default() });
+
+    let mcp = read_json(".mcp.json");
+    assert_eq!(mcp["mcpServers"]["lsp"]["args"], json!(["--generic"]));
+    assert!(mcp["mcpServers"].get("pg").is_none());
+    let settings = read_json(".claude/settings.local.json");
+    assert_eq!(settings["hooks"]["PostToolUse"][0]["command"], "prettier");
+    assert_eq!(settings["permissions"]["allow"], json!(["Bash(npm test)"]));
+}
+
+#[test]
+fn render_errors_name_the_file() {
+    let s = Scaffold::new();
+    write(s.path(), "hooks/rust/broken.json.jinja", "{% if %}");
+    let err = format!("{:#}", render_json_templates(&s.path().join("hooks"), &["rust".into()], s.path(), &json!({})).unwrap_err());
+    assert!(err.contains("Failed to render hooks/rust/broken.json.jinja"), "got: {err}");
+
+    let s = Scaffold::new();
+    write(s.path(), "mcp/bad.json.jinja", "{{ 'not json' }}");
+    let err = render_json_templates(&s.path().join("mcp"), &[], s.path(), &json!({})).unwrap_err().to_string();
+    assert!(err.contains("mcp/bad.json.jinja did not render to valid JSON"), "got: {err}");
+}
+
+#[test]
+fn templates_for_unused_languages_are_not_rendered() {
+    let s = Scaffold::new();
+    write(s.path(), "hooks/default/fmt.json.jinja", r#"{"Stop": []}"#);
+    write(s.path(), "hooks/python/broken.json.jinja", "{% if %}");
+    write(s.path(), "hooks/tags/react/broken.json.jinja", "{% if %}");
+    render_json_templates(&s.path().join("hooks"), &["rust".into()], s.path(), &json!({})).unwrap();
+    assert!(s.path().join("hooks/default/fmt.json").is_file());
+    assert!(!s.path().join("hooks/python/broken.json").exists());
+    fs::remove_file(s.path().join("hooks/default/fmt.json")).unwrap();
+
+    let err = render_json_templates(&s.path().join("hooks"), &["tags/react".into()], s.path(), &json!({}))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("hooks/tags/react/broken.json.jinja"), "got: {err}");
+}
+
+#[test]
+fn mcp_templates_see_contributors_not_server_names() {
+    let s = scaffold();
+    write(s.path(), "mcp/default/lsp.json.jinja", r#"{"lsp": {"command": "{{ mcp.context7 }}"}}"#);
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    let err = format!(
+        "{:#}",
+        run_setup(
+            &RenderInputs {
+                setup: &SetupArgs::default(),
+                sticky_mcp: &[],
+                sticky_hooks: &[],
+                sticky_permissions: &[],
+                placeholders: &Placeholders::default(),
+            },
+            s.path(),
+            Path::new("."),
+            true,
+            false,
+        )
+        .unwrap_err()
+    );
+    assert!(err.contains("mcp/default/lsp.json.jinja"), "got: {err}");
+}
+
+#[test]
+fn plain_and_templated_contributor_with_same_stem_is_rejected() {
+    let s = Scaffold::new();
+    write(s.path(), "mcp/default/x.json", "{}");
+    write(s.path(), "mcp/default/x.json.jinja", "{}");
+    let err = render_json_templates(&s.path().join("mcp"), &[], s.path(), &json!({})).unwrap_err().to_string();
+    assert!(err.contains("mcp/default/x.json.jinja and mcp/default/x.json both exist"), "got: {err}");
+}
+
+#[test]
+fn list_includes_templated_contributors() {
+    let s = scaffold();
+    assert_eq!(list_category("mcp", s.path()).unwrap(), vec!["pg"]);
+    write(s.path(), "hooks/rust/clippy.json.jinja", "{}");
+    assert_eq!(list_category("hooks", s.path()).unwrap(), vec!["rust/clippy"]);
+}
@@ -4,7 +4,8 @@ mod common;
 
 use clemp::{
     build_language_rules, build_mcp_rules, normalize_language, render_claude_md,
-    resolve_all_languages, resolve_language, LanguageResolution,
+    resolve_all_languages, resolve_language, resolve_languages_explained, ImpliedLanguage,
+    LanguageAliases, LanguageResolution,
 };
 use common::Scaffold;
 use std::fs;
@@ -52,7 +53,7 @@ fn resolve_with_rules_file() {
     let s = Scaffold::new();
     s.with_template("", &[("typescript.md", "ts rules")]);
 
-    match resolve_language("ts", s.path()) {
+    match resolve_language("ts", &LanguageAliases::default(), s.path()) {
         LanguageResolution::HasRulesFile(name) => assert_eq!(name, "typescript"),
         _ => panic!("Expected HasRulesFile"),
     }
@@ -64,7 +65,7 @@ fn resolve_conditional_only() {
     // No rules file, but svelte has a commands directory
     s.with_commands("svelte", &[("svelte.md", "cmd")]);
 
-    match resolve_language("svelte", s.path()) {
+    match resolve_language("svelte", &LanguageAliases::default(), s.path()) {
         LanguageResolution::ConditionalOnly(name) => assert_eq!(name, "svelte"),
         _ => panic!("Expected ConditionalOnly"),
     }
@@ -75,7 +76,7 @@ fn resolve_conditional_via_skills_dir() {
     let s = Scaffold::new();
     s.with_skills("svelte", &[("skill.md", "sk")]);
 
-    match resolve_language("svelte", s.path()) {
+    match resolve_language("svelte", &LanguageAliases::default(), s.path()) {
         LanguageResolution::ConditionalOnly(name) => assert_eq!(name, "svelte"),
         _ => panic!("Expected ConditionalOnly"),
     }
@@ -86,7 +87,7 @@ fn resolve_conditional_via_copied_dir() {
     let s = Scaffold::new();
     s.with_copied("swift", &[("guide.md", "g")]);
 
-    match resolve_language("swift", s.path()) {
+    match resolve_language("swift", &LanguageAliases::default(), s.path()) {
         LanguageResolution::ConditionalOnly(name) => assert_eq!(name, "swift"),
         _ => panic!("Expected ConditionalOnly"),
     }
@@ -97,7 +98,7 @@ fn resolve_conditional_via_mcp_dir() {
     let s = Scaffold::new();
     s.with_lang_mcps("svelte", &[("svelte", r#"{"svelte": {}}"#)]);
 
-    match resolve_language("svelte", s.path()) {
+    match resolve_language("svelte", &LanguageAliases::default(), s.path()) {
         LanguageResolution::ConditionalOnly(name) => assert_eq!(name, "svelte"),
         _ => panic!("Expected ConditionalOnly"),
     }
@@ -108,7 +109,7 @@ fn resolve_conditional_via_gitignore_fragment() {
     let s = Scaffold::new();
     s.with_gitignore_for_lang("ziglang", "zig-cache/\n");
 
-    match resolve_language("ziglang", s.path()) {
+    match resolve_language("ziglang", &LanguageAliases::default(), s.path()) {
         LanguageResolution::ConditionalOnly(name) => assert_eq!(name, "ziglang"),
         _ => panic!("Expected ConditionalOnly via gitignore fragment"),
     }
@@ -120,7 +121,7 @@ fn resolve_rules_file_preferred_over_gitignore_fragment() {
     s.with_template("", &[("typescript.md", "ts rules")]);
     s.with_gitignore_for_lang("typescript", "*.tsbuildinfo\n");
 
-    match resolve_language("ts", s.path()) {
+    match resolve_language("ts", &LanguageAliases::default(), s.path()) {
         LanguageResolution::HasRulesFile(name) => assert_eq!(name, "typescript"),
         _ => panic!("Expected HasRulesFile, gitignore fragment should not override rules file"),
     }
@@ -130,7 +131,7 @@ fn resolve_rules_file_preferred_over_gitignore_fragment() {
 fn resolve_no_match() {
     let s = Scaffold::new();
 
-    match resolve_language("brainfuck", s.path()) {
+    match resolve_language("brainfuck", &LanguageAliases::default(), s.path()) {
         LanguageResolution::NoMatch => {}
         _ => panic!("Expected NoMatch"),
     }
@@ -141,12 +142,58 @@ fn resolve_unknown_input_falls_back_to_lowercase() {
     let s = Scaffold::new();
     s.with_template("", &[("zig.md", "zig stuff")]);
 
-    match resolve_language("ZIG", s.path()) {
+    match resolve_language("ZIG", &LanguageAliases::default(), s.path()) {
         LanguageResolution::HasRulesFile(name) => assert_eq!(name, "zig"),
         _ => panic!("Expected HasRulesFile for unknown-but-present language"),
     }
 }
 
+// ── Template-defined aliases ────────────────────────────────────────────
+
+fn with_aliases(s: &Scaffold, yaml: &str) {
+    fs::write(s.path().join("clemp-template.yaml"), yaml).unwrap();
+}
+
+#[test]
+fn template_aliases_resolve_to_canonical_rules_file() {
+    let s = Scaffold::new();
+    s.with_template("", &[("kotlin.md", "kotlin rules")]);
+    with_aliases(&s, "aliases:\n  kotlin: [kt, KTS]\n");
+    let aliases = LanguageAliases::load(s.path()).unwrap();
+
+    for input in ["kt", "kts", "Kotlin"] {
+        match resolve_language(input, &aliases, s.path()) {
+            LanguageResolution::HasRulesFile(name) => assert_eq!(name, "kotlin"),
+            _ => panic!("Expected {input} to resolve to kotlin"),
+        }
+    }
+}
+
+#[test]
+fn template_aliases_override_builtin_table_which_stays_as_fallback() {
+    let s = Scaffold::new();
+    with_aliases(&s, "aliases:\n  js-legacy: [js]\n");
+    let aliases = LanguageAliases::load(s.path()).unwrap();
+
+    assert_eq!(aliases.canonical("js"), "js-legacy");
+    assert_eq!(aliases.canonical("ts"), "typescript");
+    assert_eq!(aliases.canonical("Zig"), "zig");
+    assert_eq!(LanguageAliases::default().canonical("js"), "javascript");
+}
+
+#[test]
+fn resolve_all_dedupes_through_template_aliases() {
+    let s = Scaffold::new();
+    s.with_template("", &[("zig.md", "zig rules")]);
+    with_aliases(&s, "aliases:\n  zig: [ziglang]\n");
+
+    let result = resolve_all_languages(&["ziglang".into(), "zig".into()], s.path()).unwrap();
+    assert_eq!(result, vec!["zig"]);
+
+    let err = resolve_all_languages(&["kt".into()], s.path()).unwrap_err().to_string();
+    assert!(err.contains("lang-rules/kt.md"), "got: {err}");
+}
+
 // ── resolve_all_languages ───────────────────────────────────────────────
 
 #[test]
@@ -168,6 +215,52 @@ fn resolve_all_includes_conditional_only() {
     assert_eq!(resolved, vec!["typescript", "svelte"]);
 }
 
+// ── Language implications ───────────────────────────────────────────────
+
+#[test]
+fn implications_expand_transitively_after_the_implying_language() {
+    let s = Scaffold::new();
+    s.with_template(
+        "",
+        &[("svelte.md", "s"), ("typescript.md", "t"), ("javascript.md", "j"), ("html.md", "h"), ("python.md", "p")],
+    );
+    with_aliases(&s, "implies:\n  svelte: [ts, html]\n  typescript: [javascript]\n");
+
+    let (resolved, implied) =
+        resolve_languages_explained(&["svelte".into(), "python".into()], &[], s.path()).unwrap();
+    assert_eq!(resolved, vec!["svelte", "typescript", "javascript", "html", "python"]);
+    assert_eq!(
+        implied,
+        vec![
+            ImpliedLanguage { language: "typescript".into(), implied_by: "svelte".into() },
+            ImpliedLanguage { language: "javascript".into(), implied_by: "typescript".into() },
+            ImpliedLanguage { language: "html".into(), implied_by: "svelte".into() },
+        ]
+    );
+}
+
+#[test]
+fn typed_languages_are_not_reported_as_implied_and_cycles_terminate() {
+    let s = Scaffold::new();
+    s.with_template("", &[("svelte.md", "s"), ("typescript.md", "t")]);
+    with_aliases(&s, "implies:\n  svelte: [typescript]\n  typescript: [svelte]\n");
+
+    let (resolved, implied) =
+        resolve_languages_explained(&["ts".into(), "svelte".into()], &[], s.path()).unwrap();
+    assert_eq!(resolved, vec!["typescript", "svelte"]);
+    assert!(implied.is_empty(), "got: {implied:?}");
+}
+
+#[test]
+fn unknown_implied_language_names_the_implying_language() {
+    let s = Scaffold::new();
+    s.with_template("", &[("svelte.md", "s")]);
+    with_aliases(&s, "implies:\n  svelte: [html]\n");
+
+    let err = resolve_all_languages(&["svelte".into()], s.path()).unwrap_err().to_string();
+    assert!(err.contains("'html' implied by 'svelte'"), "got: {err}");
+}
+
 // ── build_language_rules ────────────────────────────────────────────────
 
 #[test]
@@ -254,7 +347,7 @@ fn render_basic_with_lang_rules() {
         &[("typescript.md", "Use strict mode")],
     );
 
-    let rendered = render_claude_md(&["typescript".into()], &[], s.path()).unwrap();
+    let rendered = render_claude_md(&["typescript".into()], &[], &[], s.path()).unwrap();
     assert!(rendered.contains("typescript"));
     assert!(rendered.contains("<typescript-rules>"));
     assert!(rendered.contains("Use strict mode"));
@@ -268,7 +361,7 @@ fn render_lang_dict_conditional() {
         &[("typescript.md", "ts")],
     );
 
-    let rendered = render_claude_md(&["typescript".into()], &[], s.path()).unwrap();
+    let rendered = render_claude_md(&["typescript".into()], &[], &[], s.path()).unwrap();
     assert!(rendered.contains("HAS_LANG"));
     assert!(rendered.contains("HAS_TS"));
     assert!(!rendered.contains("HAS_RUST"));
@@ -282,7 +375,7 @@ fn render_mcp_dict_conditional() {
         &[],
     );
 
-    let rendered = render_claude_md(&[], &["maps".into()], s.path()).unwrap();
+    let rendered = render_claude_md(&[], &[], &["maps".into()], s.path()).unwrap();
     assert!(rendered.contains("HAS_MCP"));
     assert!(rendered.contains("HAS_MAPS"));
     assert!(!rendered.contains("HAS_DOCKER"));
@@ -296,7 +389,7 @@ fn render_no_lang_no_mcp() {
         &[],
     );
 
-    let rendered = render_claude_md(&[], &[], s.path()).unwrap();
+    let rendered = render_claude_md(&[], &[], &[], s.path()).unwrap();
     assert!(!rendered.contains("LANG"));
     assert!(!rendered.contains("MCP"));
     assert!(rendered.contains("base"));
@@ -308,7 +401,7 @@ fn render_misc_plain_file() {
     s.with_template("{{ general }}", &[]);
     s.with_misc_files(&[("general.md", "Be helpful")]);
 
-    let rendered = render_claude_md(&[], &[], s.path()).unwrap();
+    let rendered = render_claude_md(&[], &[], &[], s.path()).unwrap();
     assert!(rendered.contains("<general>"));
     assert!(rendered.contains("Be helpful"));
     assert!(rendered.contains("</general>"));
@@ -323,7 +416,7 @@ fn render_misc_jinja_file() {
         "{% if lang.swift %}Swift: check packages{% endif %}",
     )]);
 
-    let rendered = render_claude_md(&["swift".into()], &[], s.path()).unwrap();
+    let rendered = render_claude_md(&["swift".into()], &[], &[], s.path()).unwrap();
     assert!(rendered.contains("<lang-hygiene>"));
     assert!(rendered.contains("Swift: check packages"));
 }
@@ -337,7 +430,7 @@ fn render_misc_jinja_conditional_false() {
         "base{% if lang.swift %}\nSwift stuff{% endif %}",
     )]);
 
-    let rendered = render_claude_md(&[], &[], s.path()).unwrap();
+    let rendered = render_claude_md(&[], &[], &[], s.path()).unwrap();
     assert!(rendered.contains("<lang-hygiene>"));
     assert!(rendered.contains("base"));
     assert!(!rendered.contains("Swift stuff"));
@@ -349,7 +442,7 @@ fn render_misc_underscore_filename() {
     s.with_template("{{ web_search_tutorial }}", &[]);
     s.with_misc_files(&[("web-search-tutorial.md", "Search tips")]);
 
-    let rendered = render_claude_md(&[], &[], s.path()).unwrap();
+    let rendered = render_claude_md(&[], &[], &[], s.path()).unwrap();
     assert!(rendered.contains("<web-search-tutorial>"));
     assert!(rendered.contains("Search tips"));
 }
@@ -360,7 +453,7 @@ fn render_mcp_rules_included() {
     s.with_template("{{ mcp_rules }}", &[]);
     s.with_mcp_rules(&[("textbelt.md", "Use textbelt for search")]);
 
-    let rendered = render_claude_md(&[], &["textbelt".into()], s.path()).unwrap();
+    let rendered = render_claude_md(&[], &[], &["textbelt".into()], s.path()).unwrap();
     assert!(rendered.contains("<textbelt-mcp-rules>"));
     assert!(rendered.contains("Use textbelt for search"));
 }
@@ -380,7 +473,7 @@ fn render_full_template() {
     s.with_mcp_rules(&[("textbelt.md", "Use textbelt")]);
 
     let rendered =
-        render_claude_md(&["typescript".into()], &["textbelt".into()], s.path()).unwrap();
+        render_claude_md(&["typescript".into()], &[], &["textbelt".into()], s.path()).unwrap();
 
     assert!(rendered.contains("<general>"));
     assert!(rendered.contains("Be helpful"));
@@ -431,7 +524,7 @@ fn render_misc_underscore_in_filename() {
     s.with_template("{{ my_notes }}", &[]);
     s.with_misc_files(&[("my_notes.md", "Personal notes here")]);
 
-    let rendered = render_claude_md(&[], &[], s.path()).unwrap();
+    let rendered = render_claude_md(&[], &[], &[], s.path()).unwrap();
     assert!(rendered.contains("<my_notes>"));
     assert!(rendered.contains("Personal notes here"));
     assert!(rendered.contains("</my_notes>"));
@@ -443,7 +536,7 @@ fn render_misc_underscore_in_filename() {
 fn render_error_no_template() {
     let s = Scaffold::new();
     // No CLAUDE.md.jinja created
-    let result = render_claude_md(&[], &[], s.path());
+    let result = render_claude_md(&[], &[], &[], s.path());
     assert!(result.is_err());
     assert!(result.unwrap_err().to_string().contains("CLAUDE.md.jinja"));
 }
@@ -453,6 +546,6 @@ fn render_ok_no_claude_md_dir() {
     let s = Scaffold::new();
     s.with_template("just text", &[]);
 
-    let result = render_claude_md(&[], &[], s.path()).unwrap();
+    let result = render_claude_md(&[], &[], &[], s.path()).unwrap();
     assert_eq!(result, "just text");
 }
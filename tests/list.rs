@@ -76,6 +76,18 @@ fn list_languages() {
     assert_eq!(names, vec!["python", "typescript"]);
 }
 
+#[test]
+fn list_tags() {
+    let s = Scaffold::new();
+    let dir = s.path().join("claude-md/tag-rules");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("react.md"), "react rules").unwrap();
+    fs::write(dir.join("docker.md"), "docker rules").unwrap();
+
+    let names = list_category("tags", s.path()).unwrap();
+    assert_eq!(names, vec!["docker", "react"]);
+}
+
 #[test]
 fn list_gitignore_category_lists_langs() {
     let s = Scaffold::new();
@@ -267,3 +279,35 @@ fn list_invalid_category_via_list_available() {
         .to_string()
         .contains("Unknown category"));
 }
+
+#[test]
+fn list_all_shows_template_metadata_from_manifest() {
+    let s = Scaffold::new();
+    s.with_named_mcps(&[("maps", r#"{"maps": {}}"#)]);
+    fs::write(
+        s.path().join("clemp-template.yaml"),
+        "name: acme\ndescription: Acme's shared config\nmaintainers: [ann, bo]\n",
+    )
+    .unwrap();
+
+    let output = list_available("all", s.path()).unwrap();
+    assert!(
+        output.starts_with("template: acme\n  Acme's shared config\n  maintainers: ann, bo\n\nmcp:\n"),
+        "metadata header should precede categories:\n{output}"
+    );
+
+    let output = list_available("mcp", s.path()).unwrap();
+    assert_eq!(output, "maps\n", "single-category output stays bare");
+}
+
+#[test]
+fn list_hooks_shows_language_layer() {
+    let s = Scaffold::new();
+    s.with_default_hooks(&[("sound", r#"{"Notification": []}"#)]);
+    s.with_named_hooks(&[("blocker", r#"{"PreToolUse": []}"#)]);
+    s.with_lang_hooks("rust", &[("clippy", r#"{"PostToolUse": []}"#)]);
+    s.with_lang_hooks("tags/react", &[("eslint", r#"{"PostToolUse": []}"#)]);
+
+    let names = list_category("hooks", s.path()).unwrap();
+    assert_eq!(names, vec!["blocker", "rust/clippy", "tags/react/eslint"]);
+}
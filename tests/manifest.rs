@@ -0,0 +1,85 @@
+//! Tests for `clemp-template.yaml` metadata and compatibility checks
+//! (`schema`, `min-clemp-version`) and their enforcement before rendering.
+
+use clemp::{prepare_template, TemplateManifest, TEMPLATE_MANIFEST_NAME, TEMPLATE_SCHEMA_VERSION};
+use std::fs;
+use tempfile::TempDir;
+
+fn manifest(yaml: &str) -> TemplateManifest {
+    serde_yaml::from_str(yaml).unwrap()
+}
+
+#[test]
+fn metadata_fields_parse() {
+    let m = manifest(
+        "schema: 1\nmin-clemp-version: 0.8.0\nname: acme\ndescription: Shared config\n\
+         maintainers: [ann@acme.test, bo@acme.test]\n",
+    );
+    assert_eq!(m.schema, Some(1));
+    assert_eq!(m.min_clemp_version.as_deref(), Some("0.8.0"));
+    assert_eq!(m.name.as_deref(), Some("acme"));
+    assert_eq!(m.description.as_deref(), Some("Shared config"));
+    assert_eq!(m.maintainers, vec!["ann@acme.test", "bo@acme.test"]);
+    m.validate("tpl").unwrap();
+}
+
+#[test]
+fn missing_manifest_is_valid() {
+    TemplateManifest::default().validate("tpl").unwrap();
+}
+
+#[test]
+fn older_or_partial_min_version_is_accepted() {
+    manifest("min-clemp-version: 0.1\n").validate("tpl").unwrap();
+    manifest("min-clemp-version: v0.8\n").validate("tpl").unwrap();
+    manifest(&format!("min-clemp-version: {}\n", env!("CARGO_PKG_VERSION")))
+        .validate("tpl")
+        .unwrap();
+}
+
+#[test]
+fn newer_min_version_refuses_with_upgrade_message() {
+    let err = manifest("name: acme\nmin-clemp-version: 99.0\n")
+        .validate("https://example.test/t.git")
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("requires clemp 99.0 or newer"), "got: {err}");
+    assert!(err.contains(env!("CARGO_PKG_VERSION")), "got: {err}");
+    assert!(err.contains("'acme' (https://example.test/t.git)"), "got: {err}");
+    assert!(err.contains("Upgrade clemp"), "got: {err}");
+}
+
+#[test]
+fn newer_schema_refuses_with_upgrade_message() {
+    let err = manifest(&format!("schema: {}\n", TEMPLATE_SCHEMA_VERSION + 1))
+        .validate("tpl")
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("schema"), "got: {err}");
+    assert!(err.contains("Upgrade clemp"), "got: {err}");
+}
+
+#[test]
+fn malformed_min_version_names_the_manifest() {
+    let err = manifest("min-clemp-version: soon\n").validate("tpl").unwrap_err().to_string();
+    assert!(err.contains(TEMPLATE_MANIFEST_NAME) && err.contains("soon"), "got: {err}");
+}
+
+#[test]
+fn incompatible_parent_is_refused_before_rendering() {
+    let root = TempDir::new().unwrap();
+    let parent = root.path().join("parent");
+    let child = root.path().join("child");
+    fs::create_dir_all(&parent).unwrap();
+    fs::create_dir_all(&child).unwrap();
+    fs::write(parent.join(TEMPLATE_MANIFEST_NAME), "min-clemp-version: 99.0\n").unwrap();
+    fs::write(child.join(TEMPLATE_MANIFEST_NAME), "extends:\n  repo: ../parent\n").unwrap();
+
+    let work = TempDir::new().unwrap();
+    let dest = work.path().join("clone");
+    let err = prepare_template(&child.display().to_string(), None, &[], &dest, false)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("requires clemp 99.0"), "got: {err}");
+    assert_eq!(fs::read_dir(work.path()).unwrap().count(), 0, "nothing left to render from");
+}
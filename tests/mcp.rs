@@ -2,10 +2,12 @@
 
 mod common;
 
-use clemp::assemble_mcp_json;
-use common::Scaffold;
+use clemp::{assemble_mcp_json, run_setup, Placeholders, RenderInputs, ServerShadow, SetupArgs};
+use common::{CwdGuard, Scaffold};
 use std::collections::HashSet;
 use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
 
 fn empty_excluded() -> HashSet<String> {
     HashSet::new()
@@ -16,10 +18,10 @@ fn default_mcps_always_included() {
     let s = Scaffold::new();
     s.with_default_mcps(&[
         ("context7", r#"{"context7": {"type": "http", "url": "c7"}}"#),
-        ("textbelt", r#"{"textbelt": {"type": "stdio", "cmd": "tb"}}"#),
+        ("textbelt", r#"{"textbelt": {"type": "stdio", "command": "tb"}}"#),
     ]);
 
-    let r = assemble_mcp_json(&[], &[], &[], &empty_excluded(), s.path()).unwrap();
+    let r = assemble_mcp_json(&[], &[], &[], &empty_excluded(), true, s.path()).unwrap();
     let servers = r.rendered["mcpServers"].as_object().unwrap();
     assert_eq!(servers.len(), 2);
     assert!(servers.contains_key("context7"));
@@ -36,7 +38,7 @@ fn language_mcps_added() {
     s.with_default_mcps(&[("context7", r#"{"context7": {"url": "c7"}}"#)]);
     s.with_lang_mcps("svelte", &[("svelte", r#"{"svelte": {"url": "sv"}}"#)]);
 
-    let r = assemble_mcp_json(&["svelte".into()], &[], &[], &empty_excluded(), s.path()).unwrap();
+    let r = assemble_mcp_json(&["svelte".into()], &[], &[], &empty_excluded(), true, s.path()).unwrap();
     let servers = r.rendered["mcpServers"].as_object().unwrap();
     assert_eq!(servers.len(), 2);
     assert!(servers.contains_key("context7"));
@@ -53,7 +55,7 @@ fn named_mcps_added() {
     s.with_default_mcps(&[("context7", r#"{"context7": {"url": "c7"}}"#)]);
     s.with_named_mcps(&[("maps", r#"{"maps": {"url": "maps"}}"#)]);
 
-    let r = assemble_mcp_json(&[], &["maps".into()], &[], &empty_excluded(), s.path()).unwrap();
+    let r = assemble_mcp_json(&[], &["maps".into()], &[], &empty_excluded(), true, s.path()).unwrap();
     let servers = r.rendered["mcpServers"].as_object().unwrap();
     assert_eq!(servers.len(), 2);
     assert!(servers.contains_key("context7"));
@@ -66,9 +68,9 @@ fn named_mcps_added() {
 #[test]
 fn named_mcp_not_found_errors() {
     let s = Scaffold::new();
-    s.with_default_mcps(&[("context7", r#"{"context7": {}}"#)]);
+    s.with_default_mcps(&[("context7", r#"{"context7": {"command": "c7"}}"#)]);
 
-    let result = assemble_mcp_json(&[], &["doesnt-exist".into()], &[], &empty_excluded(), s.path());
+    let result = assemble_mcp_json(&[], &["doesnt-exist".into()], &[], &empty_excluded(), true, s.path());
     assert!(result.is_err());
     let msg = result.unwrap_err().to_string();
     assert!(msg.contains("doesnt-exist"));
@@ -78,7 +80,7 @@ fn named_mcp_not_found_errors() {
 #[test]
 fn no_mcp_dir_is_ok() {
     let s = Scaffold::new();
-    let r = assemble_mcp_json(&[], &[], &[], &empty_excluded(), s.path()).unwrap();
+    let r = assemble_mcp_json(&[], &[], &[], &empty_excluded(), true, s.path()).unwrap();
     assert!(r.rendered["mcpServers"].as_object().unwrap().is_empty());
     assert!(r.rendered_keys.is_empty());
     assert!(r.snapshottable_stems.is_empty());
@@ -87,7 +89,7 @@ fn no_mcp_dir_is_ok() {
 #[test]
 fn no_mcp_dir_with_named_mcp_errors() {
     let s = Scaffold::new();
-    let result = assemble_mcp_json(&[], &["maps".into()], &[], &empty_excluded(), s.path());
+    let result = assemble_mcp_json(&[], &["maps".into()], &[], &empty_excluded(), true, s.path());
     assert!(result.is_err());
     assert!(result.unwrap_err().to_string().contains("--mcp specified"));
 }
@@ -104,6 +106,7 @@ fn all_three_sources_merged() {
         &["maps".into()],
         &[],
         &empty_excluded(),
+        true,
         s.path(),
     )
     .unwrap();
@@ -117,7 +120,7 @@ fn missing_lang_dir_silently_skipped() {
     let s = Scaffold::new();
     s.with_default_mcps(&[("context7", r#"{"context7": {"url": "c7"}}"#)]);
 
-    let r = assemble_mcp_json(&["rust".into()], &[], &[], &empty_excluded(), s.path()).unwrap();
+    let r = assemble_mcp_json(&["rust".into()], &[], &[], &empty_excluded(), true, s.path()).unwrap();
     let servers = r.rendered["mcpServers"].as_object().unwrap();
     assert_eq!(servers.len(), 1);
 }
@@ -127,7 +130,7 @@ fn empty_mcp_json_when_no_servers() {
     let s = Scaffold::new();
     fs::create_dir_all(s.path().join("mcp/default")).unwrap();
 
-    let r = assemble_mcp_json(&[], &[], &[], &empty_excluded(), s.path()).unwrap();
+    let r = assemble_mcp_json(&[], &[], &[], &empty_excluded(), true, s.path()).unwrap();
     assert!(r.rendered["mcpServers"].as_object().unwrap().is_empty());
     assert!(r.rendered_keys.is_empty());
 }
@@ -139,7 +142,7 @@ fn sticky_stem_resolves_from_default_layer() {
     let s = Scaffold::new();
     s.with_default_mcps(&[("foo", r#"{"foo": {"url": "x"}}"#)]);
 
-    let r = assemble_mcp_json(&[], &[], &["foo".into()], &empty_excluded(), s.path()).unwrap();
+    let r = assemble_mcp_json(&[], &[], &["foo".into()], &empty_excluded(), true, s.path()).unwrap();
     let servers = r.rendered["mcpServers"].as_object().unwrap();
     assert!(servers.contains_key("foo"));
     assert!(r.snapshottable_stems.contains(&"foo".to_string()));
@@ -149,13 +152,13 @@ fn sticky_stem_resolves_from_default_layer() {
 fn excluded_stems_filter_default_layer() {
     let s = Scaffold::new();
     s.with_default_mcps(&[
-        ("context7", r#"{"context7": {"url": "c7"}}"#),
+        ("context7", r#"{"context7": {"type": "http", "url": "c7"}}"#),
         ("unwanted", r#"{"unwanted": {"url": "u"}}"#),
     ]);
     let mut excluded = HashSet::new();
     excluded.insert("unwanted".into());
 
-    let r = assemble_mcp_json(&[], &[], &[], &excluded, s.path()).unwrap();
+    let r = assemble_mcp_json(&[], &[], &[], &excluded, true, s.path()).unwrap();
     let servers = r.rendered["mcpServers"].as_object().unwrap();
     assert!(servers.contains_key("context7"));
     assert!(!servers.contains_key("unwanted"));
@@ -169,7 +172,154 @@ fn root_override_replaces_default_keys() {
     s.with_default_mcps(&[("foo", r#"{"foo": {"url": "from-default"}}"#)]);
     s.with_named_mcps(&[("foo", r#"{"foo": {"url": "from-root"}}"#)]);
 
-    let r = assemble_mcp_json(&[], &["foo".into()], &[], &empty_excluded(), s.path()).unwrap();
+    let r = assemble_mcp_json(&[], &["foo".into()], &[], &empty_excluded(), true, s.path()).unwrap();
     let servers = r.rendered["mcpServers"].as_object().unwrap();
     assert_eq!(servers["foo"]["url"], "from-root");
+    assert_eq!(r.key_sources["foo"], "mcp/foo.json");
+    assert_eq!(
+        r.shadowed,
+        vec![ServerShadow {
+            key: "foo".into(),
+            winner: "mcp/foo.json".into(),
+            shadowed: "mcp/default/foo.json".into(),
+            intended: true,
+        }]
+    );
+}
+
+// ── Key sources and shadowing ───────────────────────────────────────────
+
+#[test]
+fn language_layer_shadowing_default_is_reported() {
+    let s = Scaffold::new();
+    s.with_default_mcps(&[
+        ("lsp", r#"{"lsp": {"command": "generic-lsp"}}"#),
+        ("context7", r#"{"context7": {"type": "http", "url": "c7"}}"#),
+    ]);
+    s.with_lang_mcps(
+        "rust",
+        &[
+            ("rust-lsp", r#"{"lsp": {"command": "rust-analyzer"}}"#),
+            ("same", r#"{"context7": {"type": "http", "url": "c7"}}"#),
+        ],
+    );
+
+    let r = assemble_mcp_json(&["rust".into()], &[], &[], &empty_excluded(), true, s.path()).unwrap();
+    assert_eq!(r.rendered["mcpServers"]["lsp"]["command"], "rust-analyzer");
+    assert_eq!(r.key_sources["lsp"], "mcp/rust/rust-lsp.json");
+    assert_eq!(r.key_sources["context7"], "mcp/rust/same.json");
+    // Identical re-declarations are not shadowing.
+    assert_eq!(
+        r.shadowed,
+        vec![ServerShadow {
+            key: "lsp".into(),
+            winner: "mcp/rust/rust-lsp.json".into(),
+            shadowed: "mcp/default/lsp.json".into(),
+            intended: false,
+        }]
+    );
+}
+
+#[test]
+fn strict_mcp_turns_unexpected_shadowing_into_an_error() {
+    let scaffold = || {
+        let s = Scaffold::new();
+        s.with_template("{{ lang_rules }}", &[("rust.md", "rust rules")]);
+        s.with_default_mcps(&[("lsp", r#"{"lsp": {"command": "generic-lsp"}}"#)]);
+        s.with_lang_mcps("rust", &[("rust-lsp", r#"{"lsp": {"command": "rust-analyzer"}}"#)]);
+        s
+    };
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    let setup = |s: &Scaffold, strict_mcp: bool| {
+        let args = SetupArgs { languages: vec!["rust".into()], strict_mcp, ..Default::default() };
+        run_setup(
+            &RenderInputs {
+                setup: &args,
+                sticky_mcp: &[],
+                sticky_hooks: &[],
+                sticky_permissions: &[],
+                placeholders: &Placeholders::default(),
+            },
+            s.path(),
+            Path::new("."),
+            true,
+            false,
+        )
+        .map(|_| ())
+    };
+
+    let err = setup(&scaffold(), true).unwrap_err().to_string();
+    assert!(
+        err.contains("'lsp' in mcp/default/lsp.json is shadowed by mcp/rust/rust-lsp.json"),
+        "got: {err}"
+    );
+    assert!(!Path::new(".mcp.json").exists());
+
+    setup(&scaffold(), false).unwrap();
+    assert!(fs::read_to_string(".mcp.json").unwrap().contains("rust-analyzer"));
+}
+
+// ── Server validation ───────────────────────────────────────────────────
+
+#[test]
+fn invalid_server_entries_name_file_and_key() {
+    for (entry, problem) in [
+        (r#"{"comand": "pg-mcp"}"#, "stdio server is missing `command`"),
+        (r#"{"type": "http", "headers": {}}"#, "http server is missing `url`"),
+        (r#"{"type": "sse", "url": 42}"#, "`url` must be a non-empty string"),
+        (r#"{"type": "websocket", "url": "wss://x"}"#, "unknown `type`"),
+    ] {
+        let s = Scaffold::new();
+        s.with_default_mcps(&[("postgres", &format!(r#"{{"pg": {entry}}}"#))]);
+        let err = assemble_mcp_json(&[], &[], &[], &empty_excluded(), true, s.path())
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("Invalid MCP server 'pg' in mcp/default/postgres.json") && err.contains(problem),
+            "{entry}: got {err}"
+        );
+    }
+}
+
+#[test]
+fn unknown_or_malformed_optional_fields_only_warn() {
+    let s = Scaffold::new();
+    s.with_default_mcps(&[(
+        "postgres",
+        r#"{"pg": {"command": "pg", "args": "--verbose", "timeout": 30},
+            "remote": {"type": "sse", "url": "https://x", "env": {}},
+            "untyped": {"url": "https://y"}}"#,
+    )]);
+    let r = assemble_mcp_json(&[], &[], &[], &empty_excluded(), true, s.path()).unwrap();
+    assert_eq!(r.rendered_keys, vec!["pg", "remote", "untyped"]);
+    assert_eq!(
+        r.warnings,
+        vec![
+            "MCP server 'pg' in mcp/default/postgres.json: unknown field `timeout` for a stdio server (expected one of: type, command, args, env)",
+            "MCP server 'pg' in mcp/default/postgres.json: `args` should be an array of strings",
+            "MCP server 'remote' in mcp/default/postgres.json: unknown field `env` for a sse server (expected one of: type, url, headers)",
+            "MCP server 'untyped' in mcp/default/postgres.json: has `url` but no `type`; treating it as \"http\"",
+        ]
+    );
+
+    let r = assemble_mcp_json(&[], &[], &[], &empty_excluded(), false, s.path()).unwrap();
+    assert!(r.warnings.is_empty());
+}
+
+#[test]
+fn known_server_shapes_validate_and_no_validate_skips() {
+    let s = Scaffold::new();
+    s.with_default_mcps(&[(
+        "servers",
+        r#"{"local": {"command": "mcp", "args": ["--port", "${PORT}"], "env": {"K": "v"}},
+            "remote": {"type": "http", "url": "https://x", "headers": {"Authorization": "Bearer ${TOKEN}"}},
+            "events": {"type": "sse", "url": "https://y"}}"#,
+    )]);
+    assert!(assemble_mcp_json(&[], &[], &[], &empty_excluded(), true, s.path()).is_ok());
+
+    s.with_named_mcps(&[("typo", r#"{"typo": {"comand": "x"}}"#)]);
+    assert!(assemble_mcp_json(&[], &["typo".into()], &[], &empty_excluded(), true, s.path()).is_err());
+    let r = assemble_mcp_json(&[], &["typo".into()], &[], &empty_excluded(), false, s.path()).unwrap();
+    assert!(r.rendered_keys.contains(&"typo".to_string()));
 }
@@ -0,0 +1,278 @@
+//! Tests for `${NAME}` placeholders in MCP contributors: discovery,
+//! substitution from the environment / `.env`, and pre-substitution hashing.
+
+mod common;
+
+use clemp::{
+    compute_manifest, hash_file, hash_mcp_json, mcp_placeholders, read_env_file, run_setup,
+    run_update, save_env_file, substitute_placeholders, unsubstitute_placeholders, Lockfile,
+    OriginalCommand, Placeholders, RenderInputs, Resolved, SetupArgs, TemplateRevision, UpdateArgs,
+};
+use common::{CwdGuard, EnvVarGuard, Scaffold};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+const PG: &str = r#"{"postgres": {"command": "pg-mcp", "env": {"DATABASE_URL": "${CLEMP_TEST_PG_URL}"}}}"#;
+
+fn values(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+fn scaffold(extra: &[(&str, &str)]) -> Scaffold {
+    let s = Scaffold::new();
+    s.with_template("{{ lang_rules }}", &[("typescript.md", "ts rules")]);
+    s.with_settings("{}");
+    let mut mcps = vec![("postgres", PG)];
+    mcps.extend_from_slice(extra);
+    s.with_default_mcps(&mcps);
+    s
+}
+
+fn setup_and_lock(s: &Scaffold) {
+    let args = SetupArgs { languages: vec!["ts".into()], ..Default::default() };
+    let outcome = run_setup(
+        &RenderInputs {
+            setup: &args,
+            sticky_mcp: &[],
+            sticky_hooks: &[],
+            sticky_permissions: &[],
+            placeholders: &Placeholders::default(),
+        },
+        s.path(),
+        Path::new("."),
+        true,
+        false,
+    )
+    .unwrap();
+    Lockfile {
+        template_repo: "test://example/template.git".into(),
+        template_sha: "1111".into(),
+        template_profile: None,
+        template_ref: None,
+        template_parents: vec![],
+        template_overlays: vec![],
+        packages: Default::default(),
+        original_command: OriginalCommand::from_setup(&args),
+        resolved: Some(Resolved {
+            mcp: outcome.mcp_snapshottable_stems.clone(),
+            hooks: outcome.hooks_snapshottable_stems.clone(),
+            permissions: outcome.permissions_snapshottable_stems.clone(),
+        }),
+        mcp_servers: Default::default(),
+        settings: Default::default(),
+        files: compute_manifest(&args, &outcome.conditional_subdirs(), s.path(), Path::new("."))
+            .unwrap(),
+    }
+    .save(Path::new("."))
+    .unwrap();
+}
+
+// ── Placeholder helpers ─────────────────────────────────────────────────
+
+#[test]
+fn placeholders_are_found_in_nested_strings_only() {
+    let value = json!({"a": {"args": ["--url=${URL}", "${URL}"], "env": {"K": "${API_KEY}x"}},
+                       "${NOT_A_VALUE}": 1, "b": "$HOME ${} ${1BAD} ${unterminated"});
+    assert_eq!(mcp_placeholders(&value), vec!["API_KEY", "URL"]);
+}
+
+#[test]
+fn substitution_round_trips_and_leaves_unknown_names() {
+    let value = json!({"a": {"url": "https://${HOST}/db", "key": "${KEY}", "other": "${UNSET}"}});
+    let vals = values(&[("HOST", "db.example"), ("KEY", "s3cret")]);
+
+    let substituted = substitute_placeholders(&value, &vals);
+    assert_eq!(
+        substituted,
+        json!({"a": {"url": "https://db.example/db", "key": "s3cret", "other": "${UNSET}"}})
+    );
+    assert_eq!(unsubstitute_placeholders(&substituted, &value, &vals), value);
+}
+
+#[test]
+fn unsubstitution_only_restores_placeholders_the_template_declared() {
+    let template = json!({"mcpServers": {"pg": {"env": {"URL": "${URL}"}, "args": ["${URL}"]}}});
+    let vals = values(&[("URL", "postgres://db")]);
+    // The user added a server with the same literal, and edited the args.
+    let written = json!({"mcpServers": {
+        "pg": {"env": {"URL": "postgres://db"}, "args": ["postgres://db", "postgres://db"]},
+        "mine": {"url": "postgres://db"}
+    }});
+    assert_eq!(
+        unsubstitute_placeholders(&written, &template, &vals),
+        json!({"mcpServers": {
+            "pg": {"env": {"URL": "${URL}"}, "args": ["${URL}", "postgres://db"]},
+            "mine": {"url": "postgres://db"}
+        }})
+    );
+}
+
+#[test]
+fn env_file_parsing_and_saving() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join(".env"),
+        "# secrets\nexport A=1\nB=\"two words\"\nC='x=y'\n\nnot a pair",
+    )
+    .unwrap();
+    let parsed = read_env_file(&dir.path().join(".env")).unwrap();
+    assert_eq!(parsed, values(&[("A", "1"), ("B", "two words"), ("C", "x=y")]));
+
+    save_env_file(dir.path(), &["D".into()], &values(&[("D", "4")])).unwrap();
+    assert!(fs::read_to_string(dir.path().join(".env")).unwrap().ends_with("not a pair\nD=4\n"));
+    assert_eq!(fs::read_to_string(dir.path().join(".gitignore")).unwrap(), ".env\n");
+
+    // Already ignored: .gitignore is left alone.
+    save_env_file(dir.path(), &["E".into()], &values(&[("E", "5")])).unwrap();
+    assert_eq!(fs::read_to_string(dir.path().join(".gitignore")).unwrap(), ".env\n");
+}
+
+#[test]
+fn saved_env_values_read_back_unchanged() {
+    let dir = TempDir::new().unwrap();
+    let saved = values(&[
+        ("PLAIN", "postgres://u@db:5432/app"),
+        ("SPACED", "two words"),
+        ("MULTI", "line one\nline two"),
+        ("QUOTED", "\"already quoted\""),
+        ("SINGLE", "'single'"),
+        ("SLASHES", r"C:\path\n"),
+    ]);
+    let names: Vec<String> = saved.keys().cloned().collect();
+    save_env_file(dir.path(), &names, &saved).unwrap();
+
+    let content = fs::read_to_string(dir.path().join(".env")).unwrap();
+    assert_eq!(content.lines().count(), saved.len(), "{content}");
+    assert!(content.contains("PLAIN=postgres://u@db:5432/app\n"), "{content}");
+    assert_eq!(read_env_file(&dir.path().join(".env")).unwrap(), saved);
+}
+
+#[test]
+fn placeholders_read_the_env_file_of_their_project_dir() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    let project = TempDir::new().unwrap();
+    fs::write(project.path().join(".env"), "CLEMP_TEST_PROJECT_DIR=from-project\n").unwrap();
+    fs::write(".env", "CLEMP_TEST_PROJECT_DIR=from-cwd\n").unwrap();
+
+    let placeholders = Placeholders::new(project.path());
+    assert_eq!(
+        placeholders.resolve(&["CLEMP_TEST_PROJECT_DIR".into()]).unwrap(),
+        values(&[("CLEMP_TEST_PROJECT_DIR", "from-project")])
+    );
+}
+
+#[test]
+fn placeholders_resolve_each_name_once_per_invocation() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    let mut env = EnvVarGuard::new();
+    env.set("CLEMP_TEST_CACHED", "first");
+    let placeholders = Placeholders::default();
+    let names = vec!["CLEMP_TEST_CACHED".to_string()];
+    assert_eq!(placeholders.resolve(&names).unwrap(), values(&[("CLEMP_TEST_CACHED", "first")]));
+
+    // A later render (another package) reuses the value instead of resolving
+    // it again.
+    env.set("CLEMP_TEST_CACHED", "second");
+    assert_eq!(placeholders.resolve(&names).unwrap(), values(&[("CLEMP_TEST_CACHED", "first")]));
+    assert_eq!(placeholders.values(), values(&[("CLEMP_TEST_CACHED", "first")]));
+
+    // Nothing was prompted, so there is nothing to offer to save.
+    placeholders.offer_to_save().unwrap();
+    assert!(!Path::new(".env").exists() && !Path::new(".gitignore").exists());
+}
+
+// ── Setup + update ──────────────────────────────────────────────────────
+
+#[test]
+fn setup_substitutes_from_env_file_and_hashes_template_form() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    fs::write(".env", "CLEMP_TEST_PG_URL=postgres://secret@db\n").unwrap();
+    let s = scaffold(&[]);
+    setup_and_lock(&s);
+
+    let written = fs::read_to_string(".mcp.json").unwrap();
+    assert!(written.contains("postgres://secret@db"), "{written}");
+    assert!(!written.contains("${"), "{written}");
+
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    let pre_substitution = hash_file(&s.path().join(".mcp.json")).unwrap();
+    assert_eq!(lock.files[".mcp.json"], pre_substitution);
+    let vals = values(&[("CLEMP_TEST_PG_URL", "postgres://secret@db")]);
+    let template: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(s.path().join(".mcp.json")).unwrap()).unwrap();
+    assert_eq!(hash_mcp_json(Path::new(".mcp.json"), &template, &vals).unwrap(), pre_substitution);
+    assert!(!fs::read_to_string(".clemp-lock.yaml").unwrap().contains("secret"));
+}
+
+#[test]
+fn environment_takes_precedence_over_env_file() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    let mut env = EnvVarGuard::new();
+    env.set("CLEMP_TEST_PG_URL", "from-env");
+    fs::write(".env", "CLEMP_TEST_PG_URL=from-file\n").unwrap();
+
+    setup_and_lock(&scaffold(&[]));
+    assert!(fs::read_to_string(".mcp.json").unwrap().contains("from-env"));
+}
+
+#[test]
+fn missing_value_without_input_names_the_placeholder() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    let s = scaffold(&[]);
+    let args = SetupArgs { languages: vec!["ts".into()], ..Default::default() };
+    let err = run_setup(
+        &RenderInputs {
+            setup: &args,
+            sticky_mcp: &[],
+            sticky_hooks: &[],
+            sticky_permissions: &[],
+            placeholders: &Placeholders::default(),
+        },
+        s.path(),
+        Path::new("."),
+        true,
+        false,
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(err.contains("${CLEMP_TEST_PG_URL}"), "got: {err}");
+    assert!(!Path::new(".mcp.json").exists(), "nothing written before values resolve");
+}
+
+#[test]
+fn update_treats_substituted_secrets_as_unmodified() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    fs::write(".env", "CLEMP_TEST_PG_URL=postgres://secret@db\n").unwrap();
+    setup_and_lock(&scaffold(&[]));
+
+    // The template adds a server: .mcp.json must update cleanly, not conflict.
+    let v2 = scaffold(&[("context7", r#"{"context7": {"url": "c7"}}"#)]);
+    let update = UpdateArgs {
+        setup: SetupArgs::default(),
+        prune_stale: false,
+        restore_deleted: false,
+        merge: false,
+        only: false,
+    };
+    let revision = TemplateRevision {
+        repo: "test://example/template.git".into(),
+        sha: "2222".into(),
+        ..Default::default()
+    };
+    run_update(&update, v2.path(), &[revision]).unwrap();
+
+    let written = fs::read_to_string(".mcp.json").unwrap();
+    assert!(written.contains("context7"), "{written}");
+    assert!(written.contains("postgres://secret@db"), "{written}");
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert_eq!(lock.files[".mcp.json"], hash_file(&v2.path().join(".mcp.json")).unwrap());
+}
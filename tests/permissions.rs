@@ -0,0 +1,186 @@
+//! Tests for `permissions/` contributors: layered assembly, the union into
+//! `settings.local.json`, and `--permissions` / `--drop-permissions` on update.
+
+mod common;
+
+use clemp::{
+    assemble_permissions_json, build_settings, compute_manifest, list_category, run_setup,
+    run_update, AssemblyResult, Lockfile, OriginalCommand, Placeholders, RenderInputs, Resolved, SetupArgs,
+    TemplateRevision, UpdateArgs, SETTINGS_PATH,
+};
+use common::{CwdGuard, Scaffold};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn empty() -> HashSet<String> {
+    HashSet::new()
+}
+
+fn scaffold() -> Scaffold {
+    let s = Scaffold::new();
+    s.with_template("{{ lang_rules }}", &[("rust.md", "rust rules"), ("typescript.md", "ts rules")]);
+    s.with_settings(r#"{"permissions": {"allow": ["Bash(git status)"]}}"#);
+    s.with_default_permissions(&[(
+        "git",
+        r#"{"allow": ["Bash(git status)", "Bash(git diff:*)"], "deny": ["Bash(git push --force:*)"]}"#,
+    )]);
+    s.with_lang_permissions("rust", &[("cargo", r#"{"allow": ["Bash(cargo test:*)"]}"#)]);
+    s.with_named_permissions(&[("web", r#"{"allow": ["WebFetch(domain:docs.rs)"], "ask": ["WebSearch"]}"#)]);
+    s
+}
+
+fn settings() -> Value {
+    serde_json::from_str(&fs::read_to_string(".claude/settings.local.json").unwrap()).unwrap()
+}
+
+// ── Assembly ────────────────────────────────────────────────────────────
+
+#[test]
+fn layers_union_and_only_default_and_named_are_snapshotted() {
+    let s = scaffold();
+    let r = assemble_permissions_json(&["rust".into()], &["web".into()], &[], &empty(), s.path())
+        .unwrap();
+    assert_eq!(
+        r.rendered,
+        json!({
+            "allow": ["Bash(git status)", "Bash(git diff:*)", "Bash(cargo test:*)", "WebFetch(domain:docs.rs)"],
+            "deny": ["Bash(git push --force:*)"],
+            "ask": ["WebSearch"]
+        })
+    );
+    assert_eq!(r.snapshottable_stems, vec!["git", "web"]);
+
+    let r = assemble_permissions_json(&[], &[], &[], &["git".to_string()].into(), s.path()).unwrap();
+    assert_eq!(r.rendered, json!({}));
+}
+
+#[test]
+fn invalid_or_unknown_contributors_error() {
+    let s = scaffold();
+    let err = assemble_permissions_json(&[], &["nope".into()], &[], &empty(), s.path())
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("Permission 'nope' not found") && err.contains("\"web\""), "got: {err}");
+
+    s.with_default_permissions(&[("bad", r#"{"allow": "Bash(ls)"}"#)]);
+    let err = assemble_permissions_json(&[], &[], &[], &empty(), s.path()).unwrap_err().to_string();
+    assert!(err.contains("'allow' in") && err.contains("not an array of strings"), "got: {err}");
+}
+
+#[test]
+fn build_settings_unions_into_base_permissions() {
+    let s = scaffold();
+    let permissions = assemble_permissions_json(&[], &[], &[], &empty(), s.path()).unwrap();
+    let written =
+        build_settings(&AssemblyResult::default(), &permissions, &[], &[], &[], &[], s.path()).unwrap();
+    assert_eq!(
+        written[SETTINGS_PATH]["permissions"],
+        json!({
+            "allow": ["Bash(git status)", "Bash(git diff:*)"],
+            "deny": ["Bash(git push --force:*)"]
+        })
+    );
+}
+
+#[test]
+fn list_shows_root_and_language_layer_permissions() {
+    let s = scaffold();
+    assert_eq!(list_category("permissions", s.path()).unwrap(), vec!["web", "rust/cargo"]);
+}
+
+// ── Setup + update ──────────────────────────────────────────────────────
+
+#[test]
+fn setup_records_snapshot_and_update_drops_permissions() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+
+    let s = scaffold();
+    let args = SetupArgs {
+        languages: vec!["rust".into()],
+        permissions: vec!["web".into()],
+        ..Default::default()
+    };
+    let outcome = run_setup(
+        &RenderInputs {
+            setup: &args,
+            sticky_mcp: &[],
+            sticky_hooks: &[],
+            sticky_permissions: &[],
+            placeholders: &Placeholders::default(),
+        },
+        s.path(),
+        Path::new("."),
+        true,
+        false,
+    )
+    .unwrap();
+    assert_eq!(outcome.permissions_snapshottable_stems, vec!["git", "web"]);
+    let allow = settings()["permissions"]["allow"].clone();
+    assert!(allow.as_array().unwrap().contains(&json!("Bash(cargo test:*)")), "{allow}");
+    assert!(allow.as_array().unwrap().contains(&json!("WebFetch(domain:docs.rs)")), "{allow}");
+
+    Lockfile {
+        template_repo: "test://example/template.git".into(),
+        template_sha: "1111".into(),
+        template_profile: None,
+        template_ref: None,
+        template_parents: vec![],
+        template_overlays: vec![],
+        packages: Default::default(),
+        original_command: OriginalCommand::from_setup(&args),
+        resolved: Some(Resolved {
+            mcp: outcome.mcp_snapshottable_stems.clone(),
+            hooks: outcome.hooks_snapshottable_stems.clone(),
+            permissions: outcome.permissions_snapshottable_stems.clone(),
+        }),
+        mcp_servers: Default::default(),
+        settings: Default::default(),
+        files: compute_manifest(&args, &outcome.conditional_subdirs(), s.path(), Path::new("."))
+            .unwrap(),
+    }
+    .save(Path::new("."))
+    .unwrap();
+
+    let update = |setup: SetupArgs| UpdateArgs {
+        setup,
+        prune_stale: false,
+        restore_deleted: false,
+        merge: false,
+        only: false,
+    };
+    let revision = TemplateRevision {
+        repo: "test://example/template.git".into(),
+        sha: "2222".into(),
+        ..Default::default()
+    };
+
+    // A typo in a fresh --permissions is rejected before anything renders.
+    let err = run_update(
+        &update(SetupArgs { permissions: vec!["wbe".into()], ..Default::default() }),
+        scaffold().path(),
+        std::slice::from_ref(&revision),
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(err.contains("Permission 'wbe' not found"), "got: {err}");
+
+    let v2 = scaffold();
+    run_update(
+        &update(SetupArgs { drop_permissions: vec!["web".into()], ..Default::default() }),
+        v2.path(),
+        &[revision],
+    )
+    .unwrap();
+    let allow = settings()["permissions"]["allow"].clone();
+    assert!(!allow.as_array().unwrap().contains(&json!("WebFetch(domain:docs.rs)")), "{allow}");
+    assert!(settings()["permissions"].get("ask").is_none());
+
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert_eq!(lock.original_command.drop_permissions, vec!["web"]);
+    assert!(lock.original_command.permissions.is_empty());
+    assert_eq!(lock.resolved.unwrap().permissions, vec!["git"]);
+}
@@ -0,0 +1,84 @@
+//! Tests for the checked-in `.clemp.yaml` project defaults and how CLI args
+//! layer on top of them.
+
+use clemp::{
+    load_project_config, with_project_config, LanguageAliases, SetupArgs, PROJECT_CONFIG_NAME,
+};
+use std::fs;
+use tempfile::TempDir;
+
+fn project(yaml: &str) -> TempDir {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join(PROJECT_CONFIG_NAME), yaml).unwrap();
+    dir
+}
+
+#[test]
+fn missing_project_config_leaves_args_untouched() {
+    let dir = TempDir::new().unwrap();
+    assert!(load_project_config(dir.path()).unwrap().is_none());
+
+    let args = SetupArgs { languages: vec!["ts".into()], force: true, ..Default::default() };
+    let merged = with_project_config(&args, dir.path(), &LanguageAliases::default()).unwrap();
+    assert_eq!(merged.languages, vec!["ts"]);
+    assert!(merged.force);
+}
+
+#[test]
+fn project_config_parses_lockfile_command_shape() {
+    let dir = project(
+        "languages: [rust]\nmcp: [context7]\ndrop-hooks: [sound]\nclarg: strict\n",
+    );
+    let cmd = load_project_config(dir.path()).unwrap().unwrap();
+    assert_eq!(cmd.languages, vec!["rust"]);
+    assert_eq!(cmd.mcp, vec!["context7"]);
+    assert_eq!(cmd.drop_hooks, vec!["sound"]);
+    assert_eq!(cmd.clarg.as_deref(), Some("strict"));
+}
+
+#[test]
+fn empty_project_config_is_no_defaults() {
+    let dir = project("");
+    assert_eq!(load_project_config(dir.path()).unwrap(), Some(Default::default()));
+}
+
+#[test]
+fn cli_args_merge_additively_on_top() {
+    let dir = project("languages: [typescript]\nmcp: [context7, postgres]\nclarg: strict\n");
+    let args = SetupArgs {
+        languages: vec!["ts".into(), "python".into()],
+        drop_mcp: vec!["postgres".into()],
+        clarg: Some("relaxed".into()),
+        template: vec!["org".into()],
+        git_ref: Some("v2".into()),
+        force: true,
+        ..Default::default()
+    };
+    let merged = with_project_config(&args, dir.path(), &LanguageAliases::default()).unwrap();
+
+    // `ts` is the same language as `typescript`; only python is added.
+    assert_eq!(merged.languages, vec!["typescript", "python"]);
+    // The CLI's --drop-mcp is newer than the project default and wins.
+    assert_eq!(merged.mcp, vec!["context7"]);
+    assert_eq!(merged.drop_mcp, vec!["postgres"]);
+    assert_eq!(merged.clarg.as_deref(), Some("relaxed"));
+    // Runtime-only fields come from the CLI.
+    assert_eq!(merged.template, vec!["org"]);
+    assert_eq!(merged.git_ref.as_deref(), Some("v2"));
+    assert!(merged.force);
+}
+
+#[test]
+fn add_drop_overlap_inside_project_config_is_rejected() {
+    let dir = project("mcp: [postgres]\ndrop-mcp: [postgres]\n");
+    let err = format!("{:#}", load_project_config(dir.path()).unwrap_err());
+    assert!(err.contains(PROJECT_CONFIG_NAME), "got: {err}");
+    assert!(err.contains("postgres"), "got: {err}");
+}
+
+#[test]
+fn malformed_project_config_names_the_file() {
+    let dir = project("languages: rust: nope\n");
+    let err = format!("{:#}", load_project_config(dir.path()).unwrap_err());
+    assert!(err.contains(PROJECT_CONFIG_NAME), "got: {err}");
+}
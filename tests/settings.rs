@@ -0,0 +1,408 @@
+//! Tests for `settings/default/` and `settings/<lang>/` fragments deep-merged
+//! into the base `settings.local.json`, routing parts to the shared
+//! `settings.json`, and the three-way settings merge `clemp update` applies.
+
+mod common;
+
+use clemp::{
+    assemble_hooks_json, assemble_permissions_json, build_settings, compute_manifest, hash_file,
+    merge_settings_fragments, Lockfile, OriginalCommand, TemplateRevision, UpdateArgs,
+    run_update,
+    merge_settings_three_way, resolve_shared_settings, run_setup, AssemblyResult, Placeholders, RenderInputs,
+    SettingsConflict, SettingsPart, SetupArgs, SETTINGS_PATH, SHARED_SETTINGS_PATH,
+    TEMPLATE_MANIFEST_NAME,
+};
+use common::{CwdGuard, Scaffold};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn write(root: &Path, rel: &str, content: &str) {
+    let path = root.join(rel);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+fn langs(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+fn fragment_scaffold() -> Scaffold {
+    let s = Scaffold::new();
+    s.with_settings(r#"{"permissions": {"allow": ["Bash(git status)"]}, "env": {"A": "base"}}"#);
+    write(
+        s.path(),
+        "settings/default/common.json",
+        r#"{"permissions": {"allow": ["Bash(git status)", "Bash(ls:*)"]}}"#,
+    );
+    write(
+        s.path(),
+        "settings/rust/cargo.json",
+        r#"{"permissions": {"allow": ["Bash(cargo test:*)"]}, "env": {"RUST_LOG": "info"}}"#,
+    );
+    write(s.path(), "settings/python/pytest.json", r#"{"permissions": {"allow": ["Bash(pytest:*)"]}}"#);
+    s
+}
+
+#[test]
+fn fragments_deep_merge_and_union_arrays() {
+    let s = fragment_scaffold();
+    let mut settings: Value =
+        serde_json::from_str(&fs::read_to_string(s.path().join("settings.local.json")).unwrap())
+            .unwrap();
+
+    let conflicts = merge_settings_fragments(&mut settings, &langs(&["rust"]), s.path()).unwrap();
+    assert!(conflicts.is_empty(), "{conflicts:?}");
+    assert_eq!(
+        settings["permissions"]["allow"],
+        json!(["Bash(git status)", "Bash(ls:*)", "Bash(cargo test:*)"])
+    );
+    assert_eq!(settings["env"], json!({"A": "base", "RUST_LOG": "info"}));
+}
+
+#[test]
+fn scalar_conflicts_report_the_winning_file() {
+    let s = fragment_scaffold();
+    write(s.path(), "settings/default/env.json", r#"{"env": {"A": "default"}}"#);
+    write(s.path(), "settings/rust/log.json", r#"{"env": {"RUST_LOG": "debug"}}"#);
+    let mut settings = json!({"env": {"A": "base"}});
+
+    let conflicts = merge_settings_fragments(&mut settings, &langs(&["rust"]), s.path()).unwrap();
+    assert_eq!(
+        conflicts,
+        vec![
+            SettingsConflict {
+                key: "env.A".into(),
+                winner: "settings/default/env.json".into(),
+                overridden: "settings.local.json".into(),
+            },
+            SettingsConflict {
+                key: "env.RUST_LOG".into(),
+                winner: "settings/rust/log.json".into(),
+                overridden: "settings/rust/cargo.json".into(),
+            },
+        ]
+    );
+    assert_eq!(settings["env"], json!({"A": "default", "RUST_LOG": "debug"}));
+}
+
+#[test]
+fn non_object_fragment_errors_with_its_path() {
+    let s = Scaffold::new();
+    write(s.path(), "settings/default/bad.json", "[1, 2]");
+    let err = merge_settings_fragments(&mut json!({}), &[], s.path()).unwrap_err().to_string();
+    assert!(err.contains("settings/default/bad.json"), "got: {err}");
+}
+
+#[test]
+fn build_settings_applies_fragments_for_active_languages() {
+    let s = fragment_scaffold();
+    let hooks = assemble_hooks_json(&[], &[], &[], &HashSet::new(), s.path()).unwrap();
+    let languages = langs(&["python"]);
+    build_settings(&hooks, &AssemblyResult::default(), &[], &[], &languages, &[], s.path()).unwrap();
+
+    let content = fs::read_to_string(s.path().join(".claude/settings.local.json")).unwrap();
+    let val: Value = serde_json::from_str(&content).unwrap();
+    let allow = val["permissions"]["allow"].as_array().unwrap();
+    assert!(allow.contains(&json!("Bash(pytest:*)")));
+    assert!(!allow.contains(&json!("Bash(cargo test:*)")));
+    assert!(val["env"].get("RUST_LOG").is_none());
+}
+
+#[test]
+fn setup_merges_fragments_and_does_not_copy_settings_dir() {
+    let s = fragment_scaffold();
+    s.with_template("{{ lang_rules }}", &[("rust.md", "rust rules")]);
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+
+    let args = SetupArgs { languages: langs(&["rust"]), ..Default::default() };
+    run_setup(
+        &RenderInputs {
+            setup: &args,
+            sticky_mcp: &[],
+            sticky_hooks: &[],
+            sticky_permissions: &[],
+            placeholders: &Placeholders::default(),
+        },
+        s.path(),
+        Path::new("."),
+        true,
+        false,
+    )
+    .unwrap();
+
+    let content = fs::read_to_string(".claude/settings.local.json").unwrap();
+    assert!(content.contains("Bash(cargo test:*)"), "{content}");
+    assert!(!Path::new("settings").exists());
+}
+
+// ── Shared settings.json ────────────────────────────────────────────────
+
+#[test]
+fn template_default_is_overridden_per_part() {
+    let s = Scaffold::new();
+    write(s.path(), TEMPLATE_MANIFEST_NAME, "shared-settings: [hooks, permissions]\n");
+    use SettingsPart::*;
+
+    assert_eq!(resolve_shared_settings(&[], &[], s.path()).unwrap(), vec![Hooks, Permissions]);
+    assert_eq!(
+        resolve_shared_settings(&[Clarg], &[Permissions], s.path()).unwrap(),
+        vec![Hooks, Clarg]
+    );
+}
+
+#[test]
+fn shared_parts_split_between_the_two_files() {
+    let s = fragment_scaffold();
+    s.with_default_hooks(&[("fmt", r#"{"PostToolUse": [{"command": "fmt"}]}"#)]);
+    s.with_default_permissions(&[("git", r#"{"allow": ["Bash(git diff:*)"]}"#)]);
+    let hooks = assemble_hooks_json(&[], &[], &[], &HashSet::new(), s.path()).unwrap();
+    let permissions = assemble_permissions_json(&[], &[], &[], &HashSet::new(), s.path()).unwrap();
+    let clarg = json!({"matcher": "Bash", "hooks": [{"type": "command", "command": "clarg"}]});
+
+    let written = build_settings(
+        &hooks,
+        &permissions,
+        std::slice::from_ref(&clarg),
+        &["context7".into()],
+        &[],
+        &[SettingsPart::Hooks, SettingsPart::Permissions],
+        s.path(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        written[SHARED_SETTINGS_PATH],
+        json!({
+            "hooks": {"PostToolUse": [{"command": "fmt"}]},
+            "permissions": {"allow": ["Bash(git diff:*)"]}
+        })
+    );
+    let local = &written[SETTINGS_PATH];
+    assert_eq!(local["hooks"], json!({"PreToolUse": [clarg]}), "clarg stays local");
+    assert_eq!(local["permissions"]["allow"], json!(["Bash(git status)", "Bash(ls:*)"]));
+    assert_eq!(local["enabledMcpjsonServers"], json!(["context7"]));
+    for (path, doc) in &written {
+        let on_disk: Value =
+            serde_json::from_str(&fs::read_to_string(s.path().join(path)).unwrap()).unwrap();
+        assert_eq!(&on_disk, doc, "{path}");
+    }
+}
+
+#[test]
+fn nothing_shared_writes_no_settings_json() {
+    let s = fragment_scaffold();
+    let none = AssemblyResult::default();
+    let written = build_settings(&none, &none, &[], &[], &[], &[], s.path()).unwrap();
+    assert_eq!(written.keys().collect::<Vec<_>>(), [SETTINGS_PATH]);
+    assert!(!s.path().join(SHARED_SETTINGS_PATH).exists());
+}
+
+#[test]
+fn shared_file_starts_from_the_template_settings_json() {
+    let s = fragment_scaffold();
+    write(
+        s.path(),
+        SHARED_SETTINGS_PATH,
+        r#"{"model": "opus", "permissions": {"allow": ["Read"]}}"#,
+    );
+    s.with_default_permissions(&[("git", r#"{"allow": ["Bash(git diff:*)"]}"#)]);
+    let permissions = assemble_permissions_json(&[], &[], &[], &HashSet::new(), s.path()).unwrap();
+
+    let none = AssemblyResult::default();
+    let written =
+        build_settings(&none, &permissions, &[], &[], &[], &[SettingsPart::Permissions], s.path())
+            .unwrap();
+    assert_eq!(
+        written[SHARED_SETTINGS_PATH],
+        json!({"model": "opus", "permissions": {"allow": ["Read", "Bash(git diff:*)"]}})
+    );
+}
+
+fn shared_permissions_scaffold(allow: &str) -> Scaffold {
+    let s = fragment_scaffold();
+    s.with_template("{{ lang_rules }}", &[]);
+    write(s.path(), TEMPLATE_MANIFEST_NAME, "shared-settings: [permissions]\n");
+    write(s.path(), SHARED_SETTINGS_PATH, r#"{"model": "opus"}"#);
+    s.with_default_permissions(&[("git", &format!(r#"{{"allow": {allow}}}"#))]);
+    s
+}
+
+#[test]
+fn setup_merges_into_a_settings_json_the_project_already_has() {
+    let s = shared_permissions_scaffold(r#"["Bash(git diff:*)"]"#);
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    write(Path::new("."), SHARED_SETTINGS_PATH, r#"{"env": {"TEAM": "1"}, "model": "opus"}"#);
+    write(Path::new("."), ".claude/agents/reviewer.md", "review");
+
+    let args = SetupArgs::default();
+    let setup = || {
+        run_setup(
+            &RenderInputs {
+                setup: &args,
+                sticky_mcp: &[],
+                sticky_hooks: &[],
+                sticky_permissions: &[],
+                placeholders: &Placeholders::default(),
+            },
+            s.path(),
+            Path::new("."),
+            true,
+            false,
+        )
+    };
+    let outcome = setup().unwrap();
+
+    let on_disk: Value =
+        serde_json::from_str(&fs::read_to_string(SHARED_SETTINGS_PATH).unwrap()).unwrap();
+    assert_eq!(
+        on_disk,
+        json!({"env": {"TEAM": "1"}, "model": "opus", "permissions": {"allow": ["Bash(git diff:*)"]}})
+    );
+    assert_eq!(outcome.settings[SHARED_SETTINGS_PATH]["env"], Value::Null, "the lockfile base is clemp's part");
+    assert_eq!(fs::read_to_string(".claude/agents/reviewer.md").unwrap(), "review");
+
+    // The merged file is tracked as written; the project's other files aren't.
+    let manifest = compute_manifest(&args, &[], s.path(), Path::new(".")).unwrap();
+    assert_eq!(manifest[SHARED_SETTINGS_PATH], hash_file(Path::new(SHARED_SETTINGS_PATH)).unwrap());
+    assert!(!manifest.contains_key(".claude/agents/reviewer.md"));
+
+    // A key both set differently is reported rather than replaced.
+    write(Path::new("."), SHARED_SETTINGS_PATH, r#"{"model": "sonnet"}"#);
+    fs::remove_file(SETTINGS_PATH).unwrap();
+    fs::remove_file("CLAUDE.md").unwrap();
+    let err = setup().unwrap_err().to_string();
+    assert!(err.contains("already exists and conflicts") && err.contains("\n  model"), "got: {err}");
+    assert_eq!(fs::read_to_string(SHARED_SETTINGS_PATH).unwrap(), r#"{"model": "sonnet"}"#);
+}
+
+// ── Three-way merge on update ───────────────────────────────────────────
+
+#[test]
+fn update_keeps_the_keys_setup_merged_into_settings_json() {
+    let s = shared_permissions_scaffold(r#"["Bash(git diff:*)"]"#);
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    write(Path::new("."), SHARED_SETTINGS_PATH, r#"{"env": {"TEAM": "1"}}"#);
+
+    let args = SetupArgs::default();
+    let outcome = run_setup(
+        &RenderInputs {
+            setup: &args,
+            sticky_mcp: &[],
+            sticky_hooks: &[],
+            sticky_permissions: &[],
+            placeholders: &Placeholders::default(),
+        },
+        s.path(),
+        Path::new("."),
+        true,
+        false,
+    )
+    .unwrap();
+    Lockfile {
+        template_repo: "test://example/template.git".into(),
+        template_sha: "1111".into(),
+        template_profile: None,
+        template_ref: None,
+        template_parents: vec![],
+        template_overlays: vec![],
+        packages: Default::default(),
+        original_command: OriginalCommand::from_setup(&args),
+        resolved: None,
+        mcp_servers: Default::default(),
+        settings: outcome.settings.clone(),
+        files: compute_manifest(&args, &outcome.conditional_subdirs(), s.path(), Path::new("."))
+            .unwrap(),
+    }
+    .save(Path::new("."))
+    .unwrap();
+
+    for (sha, allow) in [("2222", r#"["Bash(git diff:*)"]"#), ("3333", r#"["Bash(git diff:*)", "Bash(git log:*)"]"#)] {
+        run_update(
+            &UpdateArgs { setup: SetupArgs::default(), prune_stale: false, restore_deleted: false, merge: false, only: false },
+            shared_permissions_scaffold(allow).path(),
+            &[TemplateRevision { repo: "test://example/template.git".into(), sha: sha.into(), ..Default::default() }],
+        )
+        .unwrap();
+        let on_disk: Value =
+            serde_json::from_str(&fs::read_to_string(SHARED_SETTINGS_PATH).unwrap()).unwrap();
+        assert_eq!(on_disk["env"], json!({"TEAM": "1"}), "{allow}");
+        assert_eq!(on_disk["permissions"]["allow"], serde_json::from_str::<Value>(allow).unwrap());
+    }
+}
+
+#[test]
+fn three_way_merge_keeps_user_approvals_and_adds_template_hooks() {
+    let base = json!({
+        "permissions": {"allow": ["Bash(git status)"]},
+        "hooks": {"PostToolUse": [{"command": "fmt"}]},
+        "enabledMcpjsonServers": ["context7", "old"],
+        "model": "sonnet"
+    });
+    // Claude Code approved a command; the user turned `old` off.
+    let ours = json!({
+        "permissions": {"allow": ["Bash(git status)", "Bash(cargo test)"]},
+        "hooks": {"PostToolUse": [{"command": "fmt"}]},
+        "enabledMcpjsonServers": ["context7"],
+        "model": "sonnet"
+    });
+    // The template added a hook and a server and changed the model.
+    let theirs = json!({
+        "permissions": {"allow": ["Bash(git status)"]},
+        "hooks": {"PostToolUse": [{"command": "fmt"}], "Stop": [{"command": "notify"}]},
+        "enabledMcpjsonServers": ["context7", "old", "docs"],
+        "model": "opus"
+    });
+
+    assert_eq!(
+        merge_settings_three_way(&base, &ours, &theirs).unwrap(),
+        json!({
+            "permissions": {"allow": ["Bash(git status)", "Bash(cargo test)"]},
+            "hooks": {"PostToolUse": [{"command": "fmt"}], "Stop": [{"command": "notify"}]},
+            "enabledMcpjsonServers": ["context7", "docs"],
+            "model": "opus"
+        })
+    );
+}
+
+#[test]
+fn three_way_merge_flags_only_keys_both_sides_changed() {
+    let base = json!({"model": "sonnet", "env": {"A": "1", "B": "1"}, "permissions": {"allow": []}});
+    let ours = json!({"model": "haiku", "env": {"A": "2", "B": "1"}, "permissions": {"allow": ["x"]}});
+    let theirs = json!({"model": "opus", "env": {"A": "3", "B": "2"}, "permissions": {"allow": ["y"]}});
+
+    let conflicts = merge_settings_three_way(&base, &ours, &theirs).unwrap_err();
+    assert_eq!(conflicts, vec!["env.A", "model"]);
+}
+
+#[test]
+fn three_way_merge_keys_hook_entries_by_matcher() {
+    let bash = |command: &str| json!({"matcher": "Bash", "hooks": [{"type": "command", "command": command}]});
+    let edit = json!({"matcher": "Edit", "hooks": [{"type": "command", "command": "fmt"}]});
+    let base = json!({"hooks": {"PreToolUse": [bash("guard")]}});
+
+    // The user edited the Bash hook; the template added an Edit hook.
+    let ours = json!({"hooks": {"PreToolUse": [bash("guard --strict")]}});
+    let theirs = json!({"hooks": {"PreToolUse": [bash("guard"), edit.clone()]}});
+    assert_eq!(
+        merge_settings_three_way(&base, &ours, &theirs).unwrap(),
+        json!({"hooks": {"PreToolUse": [bash("guard --strict"), edit]}})
+    );
+
+    // Both sides edited the Bash hook: one conflict, not two copies.
+    let theirs = json!({"hooks": {"PreToolUse": [bash("guard --v2")]}});
+    let conflicts = merge_settings_three_way(&base, &ours, &theirs).unwrap_err();
+    assert_eq!(conflicts, vec!["hooks.PreToolUse[Bash].hooks"]);
+
+    // Without matchers the list is a single value.
+    let base = json!({"hooks": {"Stop": [{"command": "a"}]}});
+    let ours = json!({"hooks": {"Stop": [{"command": "b"}]}});
+    let theirs = json!({"hooks": {"Stop": [{"command": "c"}]}});
+    let conflicts = merge_settings_three_way(&base, &ours, &theirs).unwrap_err();
+    assert_eq!(conflicts, vec!["hooks.Stop"]);
+}
@@ -0,0 +1,303 @@
+//! Tests for `--with` tags: resolution, CLAUDE.md context, conditional
+//! `tags/<tag>/` directories, and add/drop reconciliation on update.
+
+mod common;
+
+use clap::Parser;
+use clemp::{
+    compute_manifest, conditional_subdirs, normalize_setup_args, render_claude_md, resolve_tags,
+    run_setup, run_update, Cli, LanguageAliases, Lockfile, OriginalCommand, Placeholders, RenderInputs,
+    Resolved, SetupArgs, TemplateRevision, UpdateArgs,
+};
+use common::{CwdGuard, Scaffold};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn write(root: &Path, rel: &str, content: &str) {
+    let path = root.join(rel);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+fn tags(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+/// Template with a `react` tag (rules + command + MCP + gitignore fragment)
+/// and a `docker` tag that only ships a copied file.
+fn tagged_scaffold() -> Scaffold {
+    let s = Scaffold::new();
+    s.with_template(
+        "{{ lang_rules }}\n{% if tags.react %}uses react{% endif %}\n{{ tag_rules }}",
+        &[("typescript.md", "ts rules")],
+    );
+    s.with_settings(r#"{"permissions": {"allow": []}}"#);
+    s.with_gitignore_additions(".claude/\n");
+    write(s.path(), "claude-md/tag-rules/react.md", "react rules");
+    s.with_commands("tags/react", &[("component.md", "make a component")]);
+    s.with_lang_mcps("tags/react", &[("react-docs", r#"{"react-docs": {"url": "rd"}}"#)]);
+    write(s.path(), "gitignore-additions/tags/react.gitignore", ".react-cache/\n");
+    s.with_copied("tags/docker", &[("Dockerfile", "FROM scratch\n")]);
+    s
+}
+
+// ── resolve_tags ────────────────────────────────────────────────────────
+
+#[test]
+fn resolve_tags_lowercases_dedupes_and_skips_dropped() {
+    let s = tagged_scaffold();
+    let resolved = resolve_tags(&tags(&["React", "docker", "react"]), &[], s.path()).unwrap();
+    assert_eq!(resolved, vec!["react", "docker"]);
+
+    let resolved = resolve_tags(&tags(&["react", "docker"]), &tags(&["docker"]), s.path()).unwrap();
+    assert_eq!(resolved, vec!["react"]);
+}
+
+#[test]
+fn unknown_tag_errors_without_language_warning() {
+    let s = tagged_scaffold();
+    let err = resolve_tags(&tags(&["django"]), &[], s.path()).unwrap_err().to_string();
+    assert!(err.contains("Unknown tag 'django'"), "got: {err}");
+    assert!(err.contains("claude-md/tag-rules/django.md"), "got: {err}");
+}
+
+#[test]
+fn conditional_subdirs_put_tags_after_languages() {
+    assert_eq!(
+        conditional_subdirs(&tags(&["typescript"]), &tags(&["react"])),
+        vec!["typescript", "tags/react"]
+    );
+}
+
+// ── Rendering ───────────────────────────────────────────────────────────
+
+#[test]
+fn render_exposes_tags_dict_and_tag_rules() {
+    let s = tagged_scaffold();
+    let rendered = render_claude_md(&[], &tags(&["react"]), &[], s.path()).unwrap();
+    assert!(rendered.contains("uses react"), "{rendered}");
+    assert!(rendered.contains("<react-tag-rules>\nreact rules\n</react-tag-rules>"), "{rendered}");
+
+    let rendered = render_claude_md(&[], &[], &[], s.path()).unwrap();
+    assert!(!rendered.contains("react"), "{rendered}");
+}
+
+#[test]
+fn setup_applies_tag_conditional_dirs() {
+    let s = tagged_scaffold();
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+
+    let args = SetupArgs {
+        languages: vec!["ts".into()],
+        tags: tags(&["react", "docker"]),
+        ..Default::default()
+    };
+    let outcome = run_setup(
+        &RenderInputs {
+            setup: &args,
+            sticky_mcp: &[],
+            sticky_hooks: &[],
+            sticky_permissions: &[],
+            placeholders: &Placeholders::default(),
+        },
+        s.path(),
+        Path::new("."),
+        true,
+        false,
+    )
+    .unwrap();
+
+    assert_eq!(outcome.resolved_tags, vec!["react", "docker"]);
+    assert!(Path::new(".claude/commands/component.md").is_file());
+    assert!(Path::new("Dockerfile").is_file());
+    assert!(fs::read_to_string(".mcp.json").unwrap().contains("react-docs"));
+    assert!(fs::read_to_string(".gitignore").unwrap().contains(".react-cache/"));
+    assert!(
+        !outcome.mcp_snapshottable_stems.contains(&"react-docs".to_string()),
+        "tag-layer MCP stems stay dynamic like language ones"
+    );
+
+    let manifest =
+        compute_manifest(&args, &outcome.conditional_subdirs(), s.path(), Path::new(".")).unwrap();
+    assert!(manifest.contains_key("Dockerfile"));
+    assert!(manifest.contains_key(".claude/commands/component.md"));
+}
+
+// ── CLI / lockfile reconciliation ───────────────────────────────────────
+
+#[test]
+fn with_and_without_flags_parse_and_normalize() {
+    let cli = Cli::try_parse_from(["clemp", "ts", "--with", "React,docker", "--without", "tauri"])
+        .unwrap();
+    let mut args = cli.setup;
+    normalize_setup_args(&mut args);
+    assert_eq!(args.tags, vec!["react", "docker"]);
+    assert_eq!(args.drop_tags, vec!["tauri"]);
+}
+
+#[test]
+fn merge_additive_reconciles_tags_like_mcp() {
+    let mut a = OriginalCommand {
+        tags: tags(&["react", "docker"]),
+        ..Default::default()
+    };
+    let b = OriginalCommand {
+        drop_tags: tags(&["docker"]),
+        ..Default::default()
+    };
+    a.merge_additive(&b, &LanguageAliases::default()).unwrap();
+    assert_eq!(a.tags, vec!["react"]);
+    assert_eq!(a.drop_tags, vec!["docker"]);
+
+    let b = OriginalCommand {
+        tags: tags(&["docker"]),
+        ..Default::default()
+    };
+    a.merge_additive(&b, &LanguageAliases::default()).unwrap();
+    assert_eq!(a.tags, vec!["react", "docker"]);
+    assert!(a.drop_tags.is_empty());
+
+    let both = OriginalCommand {
+        tags: tags(&["react"]),
+        drop_tags: tags(&["react"]),
+        ..Default::default()
+    };
+    let err = a.merge_additive(&both, &LanguageAliases::default()).unwrap_err().to_string();
+    assert!(err.contains("--with and --without"), "got: {err}");
+}
+
+/// Set up the project from `s` and write the lockfile a fresh `clemp` would.
+fn setup_and_lock(s: &Scaffold, args: SetupArgs) {
+    let outcome = run_setup(
+        &RenderInputs {
+            setup: &args,
+            sticky_mcp: &[],
+            sticky_hooks: &[],
+            sticky_permissions: &[],
+            placeholders: &Placeholders::default(),
+        },
+        s.path(),
+        Path::new("."),
+        true,
+        false,
+    )
+    .unwrap();
+    Lockfile {
+        template_repo: "test://example/template.git".into(),
+        template_sha: "1111".into(),
+        template_profile: None,
+        template_ref: None,
+        template_parents: vec![],
+        template_overlays: vec![],
+        packages: Default::default(),
+        original_command: OriginalCommand::from_setup(&args),
+        resolved: Some(Resolved {
+            mcp: outcome.mcp_snapshottable_stems.clone(),
+            hooks: outcome.hooks_snapshottable_stems.clone(),
+            permissions: outcome.permissions_snapshottable_stems.clone(),
+        }),
+        mcp_servers: Default::default(),
+        settings: Default::default(),
+        files: compute_manifest(&args, &outcome.conditional_subdirs(), s.path(), Path::new("."))
+            .unwrap(),
+    }
+    .save(Path::new("."))
+    .unwrap();
+}
+
+#[test]
+fn update_adds_then_drops_a_tag() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    let s = tagged_scaffold();
+
+    setup_and_lock(&s, SetupArgs { languages: vec!["ts".into()], ..Default::default() });
+
+    let revision = TemplateRevision {
+        repo: "test://example/template.git".into(),
+        sha: "1111".into(),
+        ..Default::default()
+    };
+    let update = |setup: SetupArgs| UpdateArgs {
+        setup,
+        prune_stale: true,
+        restore_deleted: false,
+        merge: false,
+        only: false,
+    };
+
+    // Each run renders into its clone dir, so every update gets a fresh one.
+    drop(s);
+    let s = tagged_scaffold();
+    run_update(
+        &update(SetupArgs { tags: tags(&["react"]), ..Default::default() }),
+        s.path(),
+        std::slice::from_ref(&revision),
+    )
+    .unwrap();
+    assert!(fs::read_to_string("CLAUDE.md").unwrap().contains("react rules"));
+    assert!(Path::new(".claude/commands/component.md").is_file());
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert_eq!(lock.original_command.tags, vec!["react"]);
+
+    let s = tagged_scaffold();
+    run_update(
+        &update(SetupArgs { drop_tags: tags(&["react"]), ..Default::default() }),
+        s.path(),
+        std::slice::from_ref(&revision),
+    )
+    .unwrap();
+    assert!(!fs::read_to_string("CLAUDE.md").unwrap().contains("react rules"));
+    assert!(!Path::new(".claude/commands/component.md").exists());
+    assert!(!fs::read_to_string(".mcp.json").unwrap().contains("react-docs"));
+    assert!(!fs::read_to_string(".gitignore").unwrap().contains(".react-cache/"));
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert!(lock.original_command.tags.is_empty());
+    assert_eq!(lock.original_command.drop_tags, vec!["react"]);
+}
+
+#[test]
+fn tag_removed_upstream_goes_through_the_stale_prompt() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    setup_and_lock(
+        &tagged_scaffold(),
+        SetupArgs { languages: vec!["ts".into()], tags: tags(&["docker"]), ..Default::default() },
+    );
+    assert!(Path::new("Dockerfile").is_file());
+
+    let without_docker = || {
+        let s = tagged_scaffold();
+        fs::remove_dir_all(s.path().join("copied/tags/docker")).unwrap();
+        s
+    };
+    let update = |prune_stale| UpdateArgs {
+        setup: SetupArgs::default(),
+        prune_stale,
+        restore_deleted: false,
+        merge: false,
+        only: false,
+    };
+    let revision = TemplateRevision {
+        repo: "test://example/template.git".into(),
+        sha: "2222".into(),
+        ..Default::default()
+    };
+
+    // Declining the prompt (stdin is empty) aborts before anything changes.
+    let s = without_docker();
+    let err = run_update(&update(false), s.path(), std::slice::from_ref(&revision))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("--prune-stale") && err.contains("--without"), "got: {err}");
+    assert!(Path::new("Dockerfile").is_file());
+
+    let s = without_docker();
+    run_update(&update(true), s.path(), &[revision]).unwrap();
+    assert!(!Path::new("Dockerfile").exists());
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert!(lock.original_command.tags.is_empty());
+    assert!(!lock.files.contains_key("Dockerfile"));
+}
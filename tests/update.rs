@@ -6,8 +6,9 @@
 mod common;
 
 use clemp::{
-    compute_manifest, run_setup, run_update, Lockfile, OriginalCommand, RenderInputs, Resolved,
-    SetupArgs, UpdateArgs, LOCKFILE_NAME,
+    compute_manifest, run_setup, run_update, LockedSource, Lockfile, OriginalCommand,
+    Placeholders, RenderInputs, Resolved, SettingsPart, SetupArgs, TemplateRevision, UpdateArgs,
+    LOCKFILE_NAME, PROJECT_CONFIG_NAME, SETTINGS_PATH, SHARED_SETTINGS_PATH,
 };
 use common::{install_fake_claude, CwdGuard, EnvVarGuard, PathGuard, Scaffold};
 use std::fs;
@@ -31,6 +32,14 @@ fn build_scaffold(ts_rules: &str) -> Scaffold {
     s
 }
 
+fn revision(sha: &str) -> TemplateRevision {
+    TemplateRevision {
+        repo: REPO_URL.into(),
+        sha: sha.into(),
+        ..Default::default()
+    }
+}
+
 fn ts_args() -> SetupArgs {
     SetupArgs {
         languages: vec!["ts".into()],
@@ -57,7 +66,13 @@ fn ts_update(force: bool, merge: bool, prune_stale: bool, restore_deleted: bool)
 fn setup_and_lock(s: &Scaffold, sha: &str) -> Vec<String> {
     let args = ts_args();
     let outcome = run_setup(
-        &RenderInputs { setup: &args, sticky_mcp: &[], sticky_hooks: &[] },
+        &RenderInputs {
+            setup: &args,
+            sticky_mcp: &[],
+            sticky_hooks: &[],
+            sticky_permissions: &[],
+            placeholders: &Placeholders::default(),
+        },
         s.path(),
         Path::new("."),
         true,
@@ -68,11 +83,19 @@ fn setup_and_lock(s: &Scaffold, sha: &str) -> Vec<String> {
     Lockfile {
         template_repo: REPO_URL.into(),
         template_sha: sha.into(),
+        template_profile: None,
+        template_ref: None,
+        template_parents: vec![],
+        template_overlays: vec![],
+        packages: Default::default(),
         original_command: OriginalCommand::from_setup(&args),
         resolved: Some(Resolved {
             mcp: outcome.mcp_snapshottable_stems,
             hooks: outcome.hooks_snapshottable_stems,
+            permissions: outcome.permissions_snapshottable_stems,
         }),
+        mcp_servers: [(".mcp.json".to_string(), outcome.mcp_servers)].into_iter().collect(),
+        settings: outcome.settings,
         files: manifest,
     }
     .save(Path::new("."))
@@ -100,7 +123,7 @@ fn clean_update_changes_clean_files_and_updates_lockfile() {
     // Drop a brand-new file in the template that didn't exist in v1.
     fs::write(v2.path().join("NEWFILE.md"), "fresh from template\n").unwrap();
 
-    run_update(&ts_update(false, false, false, false), v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(false, false, false, false), v2.path(), &[revision(V2_SHA)]).unwrap();
 
     let post = fs::read_to_string("CLAUDE.md").unwrap();
     assert!(post.contains("v2 ts rules"), "CLAUDE.md should be updated, got:\n{post}");
@@ -126,7 +149,7 @@ fn no_op_fast_path_leaves_project_byte_for_byte() {
     let lock_before = fs::read(Path::new(LOCKFILE_NAME)).unwrap();
 
     // Same SHA, same args, no flags — must short-circuit.
-    run_update(&ts_update(false, false, false, false), v1.path(), V1_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(false, false, false, false), v1.path(), &[revision(V1_SHA)]).unwrap();
 
     let claude_md_after = fs::read("CLAUDE.md").unwrap();
     let lock_after = fs::read(Path::new(LOCKFILE_NAME)).unwrap();
@@ -150,7 +173,7 @@ fn restore_deleted_works_on_unchanged_template() {
 
     // Same SHA + same args, but --restore-deleted must bypass the fast path
     // and re-copy the missing file.
-    run_update(&ts_update(false, false, false, true), v1.path(), V1_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(false, false, false, true), v1.path(), &[revision(V1_SHA)]).unwrap();
 
     assert!(
         Path::new(".editorconfig").exists(),
@@ -178,7 +201,7 @@ fn user_modified_file_preserved_when_template_did_not_change_it() {
     // v2 changes lang rules but leaves the .editorconfig overlay alone.
     let v2 = build_scaffold("v2 ts rules\n");
 
-    run_update(&ts_update(false, false, false, false), v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(false, false, false, false), v2.path(), &[revision(V2_SHA)]).unwrap();
 
     // user file untouched
     assert_eq!(
@@ -226,7 +249,7 @@ fn keep_own_lifecycle() {
     // Template introduces USERFILE.md → collision with user's file.
     fs::write(v2.path().join("USERFILE.md"), "template USERFILE\n").unwrap();
 
-    run_update(&ts_update(false, false, false, false), v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(false, false, false, false), v2.path(), &[revision(V2_SHA)]).unwrap();
 
     // Conflict → kept.
     assert_eq!(
@@ -254,7 +277,7 @@ fn keep_own_lifecycle() {
     // ── Cycle 2: same template content, different SHA (bypass fast path) ──
     // Template content identical to v2, but V3_SHA forces classification to run.
     // Kept-own files should classify as Skipped (template unchanged, user modified).
-    run_update(&ts_update(false, false, false, false), v2.path(), V3_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(false, false, false, false), v2.path(), &[revision(V3_SHA)]).unwrap();
 
     assert_eq!(
         fs::read_to_string("CLAUDE.md").unwrap(),
@@ -278,7 +301,7 @@ fn keep_own_lifecycle() {
     .unwrap();
     fs::write(v4.path().join("USERFILE.md"), "template USERFILE v4\n").unwrap();
 
-    run_update(&ts_update(false, false, false, false), v4.path(), V4_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(false, false, false, false), v4.path(), &[revision(V4_SHA)]).unwrap();
 
     assert_eq!(
         fs::read_to_string("CLAUDE.md").unwrap(),
@@ -324,7 +347,7 @@ fn merge_flag_lifecycle() {
     let lock_before = fs::read(LOCKFILE_NAME).unwrap();
     let mcp_before = fs::read(".mcp.json").unwrap();
 
-    let err = run_update(&ts_update(false, true, false, false), v2.path(), V2_SHA, REPO_URL)
+    let err = run_update(&ts_update(false, true, false, false), v2.path(), &[revision(V2_SHA)])
         .unwrap_err()
         .to_string();
     assert!(err.contains("aborting update"), "claude failure should bubble: {err}");
@@ -350,7 +373,7 @@ fn merge_flag_lifecycle() {
     unsafe { std::env::set_var("FAKE_CLAUDE_TARGET", target.to_str().unwrap()); }
     unsafe { std::env::set_var("FAKE_CLAUDE_CONTENT", "MERGED-OUTPUT"); }
 
-    run_update(&ts_update(false, true, false, false), v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(false, true, false, false), v2.path(), &[revision(V2_SHA)]).unwrap();
 
     // Merged by Claude.
     assert_eq!(
@@ -386,7 +409,7 @@ fn merge_flag_without_claude_aborts() {
 
     let lock_before = fs::read(LOCKFILE_NAME).unwrap();
 
-    let err = run_update(&ts_update(false, true, false, false), v2.path(), V2_SHA, REPO_URL)
+    let err = run_update(&ts_update(false, true, false, false), v2.path(), &[revision(V2_SHA)])
         .unwrap_err()
         .to_string();
     assert!(err.contains("claude"), "should mention claude: {err}");
@@ -419,13 +442,164 @@ fn force_overrides_conflicts_and_collisions_without_claude() {
     // No claude on PATH at all — --force must skip the gate entirely.
     let _path = PathGuard::system_only();
 
-    run_update(&ts_update(true, false, false, false), v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(true, false, false, false), v2.path(), &[revision(V2_SHA)]).unwrap();
 
     // Both files are now the template's version
     assert!(fs::read_to_string("CLAUDE.md").unwrap().contains("v2 ts rules"));
     assert_eq!(fs::read_to_string("USERFILE.md").unwrap(), "template-USERFILE\n");
 }
 
+// ── 10b. .mcp.json merges server by server ──────────────────────────────
+
+/// Add a personal server to the rendered `.mcp.json`, optionally editing
+/// `context7` too.
+fn add_user_server(context7_url: Option<&str>) {
+    let mut doc: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(".mcp.json").unwrap()).unwrap();
+    doc["mcpServers"]["mine"] = serde_json::json!({"command": "my-mcp"});
+    if let Some(url) = context7_url {
+        doc["mcpServers"]["context7"]["url"] = url.into();
+    }
+    fs::write(".mcp.json", serde_json::to_string_pretty(&doc).unwrap()).unwrap();
+}
+
+/// v1 plus a new `docs` server, with `context7` pointing at `c7_url`.
+fn build_mcp_scaffold(c7_url: &str, docs_url: &str) -> Scaffold {
+    let s = build_scaffold("v1 ts rules\n");
+    s.with_default_mcps(&[
+        ("context7", &format!(r#"{{"context7": {{"type": "http", "url": "{c7_url}"}}}}"#)),
+        ("docs", &format!(r#"{{"docs": {{"type": "http", "url": "{docs_url}"}}}}"#)),
+    ]);
+    s
+}
+
+#[test]
+fn mcp_json_keeps_user_servers_and_takes_template_servers() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    let _path = PathGuard::system_only();
+    setup_and_lock(&build_scaffold("v1 ts rules\n"), V1_SHA);
+    add_user_server(None);
+
+    let v2 = build_mcp_scaffold("c7-v2", "d");
+    run_update(&ts_update(false, false, false, false), v2.path(), &[revision(V2_SHA)]).unwrap();
+
+    let doc: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(".mcp.json").unwrap()).unwrap();
+    assert_eq!(doc["mcpServers"]["context7"]["url"], "c7-v2");
+    assert_eq!(doc["mcpServers"]["docs"]["url"], "d");
+    assert_eq!(doc["mcpServers"]["mine"]["command"], "my-mcp");
+
+    // The new render is the base next time: a further template change still merges.
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert!(lock.mcp_servers[".mcp.json"].contains_key("docs"));
+    let v3 = build_mcp_scaffold("c7-v2", "d3");
+    run_update(&ts_update(false, false, false, false), v3.path(), &[revision(V3_SHA)]).unwrap();
+    let post = fs::read_to_string(".mcp.json").unwrap();
+    assert!(post.contains("my-mcp") && post.contains("\"d3\""), "{post}");
+}
+
+#[test]
+fn mcp_json_conflicts_only_when_both_sides_changed_a_server() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    let _path = PathGuard::system_only();
+    setup_and_lock(&build_scaffold("v1 ts rules\n"), V1_SHA);
+    add_user_server(Some("my-c7"));
+    let before = fs::read_to_string(".mcp.json").unwrap();
+
+    let v2 = build_mcp_scaffold("c7-v2", "d");
+    run_update(&ts_update(false, false, false, false), v2.path(), &[revision(V2_SHA)]).unwrap();
+    assert_eq!(fs::read_to_string(".mcp.json").unwrap(), before, "conflicts keep the user's file");
+
+    // --force still replaces the whole file.
+    let v3 = build_mcp_scaffold("c7-v3", "d");
+    run_update(&ts_update(true, false, false, false), v3.path(), &[revision(V3_SHA)]).unwrap();
+    let post = fs::read_to_string(".mcp.json").unwrap();
+    assert!(post.contains("c7-v3") && !post.contains("my-mcp"), "{post}");
+}
+
+// ── 10c. settings.local.json merges key by key ──────────────────────────
+
+#[test]
+fn settings_merge_keeps_local_approvals_and_lands_template_changes() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    let _path = PathGuard::system_only();
+    setup_and_lock(&build_scaffold("v1 ts rules\n"), V1_SHA);
+
+    // Claude Code records an approval in the local settings.
+    let mut local: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(SETTINGS_PATH).unwrap()).unwrap();
+    local["permissions"]["allow"] = serde_json::json!(["Bash(cargo test)"]);
+    fs::write(SETTINGS_PATH, serde_json::to_string_pretty(&local).unwrap()).unwrap();
+
+    let v2 = build_scaffold("v1 ts rules\n");
+    v2.with_settings(r#"{"permissions": {"allow": ["Bash(git status)"]}}"#);
+    v2.with_default_hooks(&[("fmt", r#"{"PostToolUse": [{"command": "prettier"}]}"#)]);
+    v2.with_default_mcps(&[
+        ("context7", r#"{"context7": {"type": "http", "url": "c7"}}"#),
+        ("docs", r#"{"docs": {"type": "http", "url": "d"}}"#),
+    ]);
+    run_update(&ts_update(false, false, false, false), v2.path(), &[revision(V2_SHA)]).unwrap();
+
+    let merged: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(SETTINGS_PATH).unwrap()).unwrap();
+    assert_eq!(
+        merged["permissions"]["allow"],
+        serde_json::json!(["Bash(cargo test)", "Bash(git status)"])
+    );
+    assert_eq!(merged["hooks"]["PostToolUse"][0]["command"], "prettier");
+    assert_eq!(merged["enabledMcpjsonServers"], serde_json::json!(["context7", "docs"]));
+}
+
+// ── 10d. Moving hooks to the shared settings.json ───────────────────────
+
+#[test]
+fn shared_settings_merge_into_an_existing_settings_json() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    let _path = PathGuard::system_only();
+    let hooked = |s: Scaffold| {
+        s.with_default_hooks(&[("fmt", r#"{"PostToolUse": [{"command": "prettier"}]}"#)]);
+        s
+    };
+    setup_and_lock(&hooked(build_scaffold("v1 ts rules\n")), V1_SHA);
+    // The team already commits a settings.json of its own.
+    fs::write(SHARED_SETTINGS_PATH, r#"{"env": {"TEAM": "1"}}"#).unwrap();
+
+    let mut update = ts_update(false, false, false, false);
+    update.setup.shared_settings = vec![SettingsPart::Hooks];
+    let v1 = hooked(build_scaffold("v1 ts rules\n"));
+    run_update(&update, v1.path(), &[revision(V1_SHA)]).unwrap();
+
+    let read = |path: &str| -> serde_json::Value {
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    };
+    let shared = read(SHARED_SETTINGS_PATH);
+    assert_eq!(shared["env"]["TEAM"], "1", "the team's keys survive");
+    assert_eq!(shared["hooks"]["PostToolUse"][0]["command"], "prettier");
+    let local = read(SETTINGS_PATH);
+    assert!(local.get("hooks").is_none(), "{local}");
+    assert_eq!(local["enabledMcpjsonServers"], serde_json::json!(["context7"]));
+
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert_eq!(lock.original_command.shared_settings, vec![SettingsPart::Hooks]);
+    assert!(lock.files.contains_key(SHARED_SETTINGS_PATH));
+    assert_eq!(lock.settings[SHARED_SETTINGS_PATH]["hooks"], shared["hooks"]);
+    assert!(lock.settings[SETTINGS_PATH].get("hooks").is_none());
+
+    // --local-settings moves them back; the shared file keeps only the team's keys.
+    let mut update = ts_update(false, false, false, false);
+    update.setup.local_settings = vec![SettingsPart::Hooks];
+    let v2 = hooked(build_scaffold("v1 ts rules\n"));
+    run_update(&update, v2.path(), &[revision(V2_SHA)]).unwrap();
+    assert_eq!(read(SETTINGS_PATH)["hooks"]["PostToolUse"][0]["command"], "prettier");
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert!(lock.original_command.shared_settings.is_empty());
+    assert!(!lock.settings.contains_key(SHARED_SETTINGS_PATH));
+}
+
 // ── 11/12. Stale prompt n (via EOF) leaves file but drops it from lockfile ─
 
 #[test]
@@ -445,7 +619,7 @@ fn stale_no_response_keeps_file_but_drops_from_lockfile() {
     let v2 = build_scaffold("v2 ts rules\n");
 
     // No --prune-stale; stdin is EOF in the test runner so confirm() returns false.
-    run_update(&ts_update(false, false, false, false), v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(false, false, false, false), v2.path(), &[revision(V2_SHA)]).unwrap();
 
     // File remains on disk (user said "n")
     assert!(
@@ -476,7 +650,7 @@ fn prune_stale_deletes_without_prompt() {
 
     let v2 = build_scaffold("v2 ts rules\n");
 
-    run_update(&ts_update(false, false, true, false), v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(false, false, true, false), v2.path(), &[revision(V2_SHA)]).unwrap();
 
     assert!(
         !Path::new("extra-tool.sh").exists(),
@@ -519,7 +693,7 @@ fn prune_stale_preserves_files_when_later_merge_fails() {
     // --merge is required to trigger the Claude merge path; without it,
     // keep-own (the default) would skip the merge and the test couldn't
     // exercise the merge-before-stale ordering.
-    let err = run_update(&ts_update(false, true, true, false), v2.path(), V2_SHA, REPO_URL)
+    let err = run_update(&ts_update(false, true, true, false), v2.path(), &[revision(V2_SHA)])
         .unwrap_err()
         .to_string();
     assert!(err.contains("aborting update"), "claude failure should bubble: {err}");
@@ -551,7 +725,7 @@ fn missing_tracked_file_without_restore_stays_missing() {
 
     let v2 = build_scaffold("v2 ts rules\n");
 
-    run_update(&ts_update(false, false, false, false), v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(false, false, false, false), v2.path(), &[revision(V2_SHA)]).unwrap();
 
     assert!(
         !Path::new(".editorconfig").exists(),
@@ -584,7 +758,13 @@ fn git_hook_update_preserves_executable_bit() {
             ..Default::default()
         };
         let outcome = run_setup(
-            &RenderInputs { setup: &args, sticky_mcp: &[], sticky_hooks: &[] },
+            &RenderInputs {
+                setup: &args,
+                sticky_mcp: &[],
+                sticky_hooks: &[],
+                sticky_permissions: &[],
+                placeholders: &Placeholders::default(),
+            },
             v1.path(),
             Path::new("."),
             true,
@@ -597,11 +777,19 @@ fn git_hook_update_preserves_executable_bit() {
         Lockfile {
             template_repo: REPO_URL.into(),
             template_sha: V1_SHA.into(),
+            template_profile: None,
+            template_ref: None,
+            template_parents: vec![],
+            template_overlays: vec![],
+            packages: Default::default(),
             original_command: OriginalCommand::from_setup(&args),
             resolved: Some(Resolved {
                 mcp: outcome.mcp_snapshottable_stems,
                 hooks: outcome.hooks_snapshottable_stems,
+                permissions: outcome.permissions_snapshottable_stems,
             }),
+            mcp_servers: Default::default(),
+            settings: Default::default(),
             files: manifest,
         }
         .save(Path::new("."))
@@ -620,7 +808,7 @@ fn git_hook_update_preserves_executable_bit() {
     )
     .unwrap();
 
-    run_update(&ts_update(false, false, false, false), v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(false, false, false, false), v2.path(), &[revision(V2_SHA)]).unwrap();
 
     let body = fs::read_to_string(".git/hooks/pre-commit").unwrap();
     assert!(body.contains("v2"), "hook should be updated to v2 content");
@@ -656,7 +844,7 @@ fn file_to_directory_transition_handled_with_prune_stale() {
     fs::write(v2.path().join("tool.sh/inner.sh"), "inner content\n").unwrap();
 
     // --prune-stale removes the old file so the new directory tree can land.
-    run_update(&ts_update(false, false, true, false), v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(false, false, true, false), v2.path(), &[revision(V2_SHA)]).unwrap();
 
     assert!(
         Path::new("tool.sh").is_dir(),
@@ -699,7 +887,7 @@ fn file_to_directory_transition_without_prune_stale_bails_before_merges() {
     let lock_before = fs::read(LOCKFILE_NAME).unwrap();
     let claude_md_before = fs::read_to_string("CLAUDE.md").unwrap();
 
-    let err = run_update(&ts_update(false, false, false, false), v2.path(), V2_SHA, REPO_URL)
+    let err = run_update(&ts_update(false, false, false, false), v2.path(), &[revision(V2_SHA)])
         .unwrap_err()
         .to_string();
     assert!(
@@ -743,7 +931,7 @@ fn directory_to_file_transition_requires_force_and_replaces_dir() {
 
     // Without --force the shape collision must abort cleanly.
     let _path = PathGuard::system_only();
-    let err = run_update(&ts_update(false, false, true, false), v2.path(), V2_SHA, REPO_URL)
+    let err = run_update(&ts_update(false, false, true, false), v2.path(), &[revision(V2_SHA)])
         .unwrap_err()
         .to_string();
     assert!(
@@ -754,7 +942,7 @@ fn directory_to_file_transition_requires_force_and_replaces_dir() {
     assert!(Path::new("tool.sh").is_dir(), "dir untouched on abort");
 
     // With --force, the directory is replaced by the file.
-    run_update(&ts_update(true, false, true, false), v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(true, false, true, false), v2.path(), &[revision(V2_SHA)]).unwrap();
     assert!(Path::new("tool.sh").is_file(), "tool.sh should now be a file");
     assert!(fs::read_to_string("tool.sh").unwrap().contains("v2"));
 }
@@ -788,7 +976,7 @@ fn update_reapplies_gitignore_when_only_fragment_changed() {
     )
     .unwrap();
 
-    run_update(&ts_update(false, false, false, false), v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(false, false, false, false), v2.path(), &[revision(V2_SHA)]).unwrap();
 
     let post = fs::read_to_string(".gitignore").unwrap();
     assert!(post.contains(".claude/"), "default line preserved:\n{post}");
@@ -797,7 +985,7 @@ fn update_reapplies_gitignore_when_only_fragment_changed() {
         "new fragment line must be appended on update:\n{post}"
     );
     // Idempotent — second update adds nothing new.
-    run_update(&ts_update(false, false, false, false), v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(false, false, false, false), v2.path(), &[revision(V2_SHA)]).unwrap();
     let post2 = fs::read_to_string(".gitignore").unwrap();
     assert_eq!(post, post2, "second update must be a no-op for .gitignore");
 }
@@ -835,7 +1023,7 @@ fn update_adds_language_with_only_gitignore_fragment() {
         merge: false,
         only: false,
     };
-    run_update(&update, v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&update, v2.path(), &[revision(V2_SHA)]).unwrap();
 
     let post = fs::read_to_string(".gitignore").unwrap();
     assert!(post.contains("__pycache__/"), "{post}");
@@ -848,6 +1036,117 @@ fn update_adds_language_with_only_gitignore_fragment() {
     assert!(langs.iter().any(|l| l == "python"), "python added: {langs:?}");
 }
 
+#[test]
+fn implied_languages_render_but_lockfile_keeps_typed_languages() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+
+    {
+        let v1 = build_scaffold("v1 ts rules\n");
+        setup_and_lock(&v1, V1_SHA);
+    }
+
+    // v2 declares that typescript pulls in javascript.
+    let v2 = build_scaffold("v1 ts rules\n");
+    fs::write(v2.path().join("claude-md/lang-rules/javascript.md"), "js rules\n").unwrap();
+    fs::write(v2.path().join("clemp-template.yaml"), "implies:\n  typescript: [javascript]\n").unwrap();
+
+    run_update(&ts_update(false, false, false, false), v2.path(), &[revision(V2_SHA)]).unwrap();
+
+    let post = fs::read_to_string("CLAUDE.md").unwrap();
+    assert!(post.contains("js rules"), "implied language must render: {post}");
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert_eq!(lock.original_command.languages, vec!["ts"]);
+}
+
+#[test]
+fn drop_lang_removes_language_outputs_as_stale() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+
+    let python_scaffold = || {
+        let s = build_scaffold("v1 ts rules\n");
+        fs::write(s.path().join("claude-md/lang-rules/python.md"), "py rules\n").unwrap();
+        s.with_commands("python", &[("pytest.md", "run pytest")]);
+        s.with_gitignore_for_lang("python", "__pycache__/\n.claude/\n");
+        s
+    };
+
+    {
+        let v1 = python_scaffold();
+        setup_and_lock(&v1, V1_SHA);
+        let mut add = ts_update(false, false, false, false);
+        add.setup.languages = vec!["python".into()];
+        run_update(&add, v1.path(), &[revision(V1_SHA)]).unwrap();
+    }
+    assert!(fs::read_to_string("CLAUDE.md").unwrap().contains("py rules"));
+    assert!(Path::new(".claude/commands/pytest.md").exists());
+    assert!(fs::read_to_string(".gitignore").unwrap().contains("__pycache__/"));
+
+    let v2 = python_scaffold();
+    let mut drop = ts_update(false, false, true, false);
+    drop.setup.languages = vec![];
+    drop.setup.drop_lang = vec!["py".into()];
+    run_update(&drop, v2.path(), &[revision(V2_SHA)]).unwrap();
+
+    assert!(!fs::read_to_string("CLAUDE.md").unwrap().contains("py rules"));
+    assert!(!Path::new(".claude/commands/pytest.md").exists());
+    let gitignore = fs::read_to_string(".gitignore").unwrap();
+    assert!(!gitignore.contains("__pycache__/"), "{gitignore}");
+    assert!(gitignore.contains(".claude/"), "lines still supplied by default stay: {gitignore}");
+
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert_eq!(lock.original_command.languages, vec!["ts"]);
+    assert_eq!(lock.original_command.drop_lang, vec!["py"]);
+    assert!(!lock.files.contains_key(".claude/commands/pytest.md"));
+}
+
+#[test]
+fn drop_lang_drops_language_hooks_without_stale_prompt() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+
+    let rust_scaffold = || {
+        let s = build_scaffold("v1 ts rules\n");
+        fs::write(s.path().join("claude-md/lang-rules/rust.md"), "rust rules\n").unwrap();
+        s.with_lang_hooks("rust", &[("clippy", r#"{"PostToolUse": [{"command": "cargo clippy"}]}"#)]);
+        s
+    };
+
+    {
+        let v1 = rust_scaffold();
+        setup_and_lock(&v1, V1_SHA);
+        let mut add = ts_update(false, false, false, false);
+        add.setup.languages = vec!["rust".into()];
+        run_update(&add, v1.path(), &[revision(V1_SHA)]).unwrap();
+    }
+    assert!(fs::read_to_string(".claude/settings.local.json").unwrap().contains("cargo clippy"));
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert!(!lock.resolved.unwrap().hooks.contains(&"clippy".to_string()));
+
+    // No --prune-stale and stdin is not a terminal: a stale prompt would bail.
+    let v2 = rust_scaffold();
+    let mut drop = ts_update(false, false, false, false);
+    drop.setup.languages = vec![];
+    drop.setup.drop_lang = vec!["rust".into()];
+    run_update(&drop, v2.path(), &[revision(V2_SHA)]).unwrap();
+
+    assert!(!fs::read_to_string(".claude/settings.local.json").unwrap().contains("cargo clippy"));
+}
+
+#[test]
+fn drop_lang_rejects_unknown_language() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    let v1 = build_scaffold("v1 ts rules\n");
+    setup_and_lock(&v1, V1_SHA);
+
+    let mut drop = ts_update(false, false, false, false);
+    drop.setup.drop_lang = vec!["cobol".into()];
+    let err = run_update(&drop, v1.path(), &[revision(V2_SHA)]).unwrap_err().to_string();
+    assert!(err.contains("--drop-lang 'cobol'"), "got: {err}");
+}
+
 // ── Snapshot / sticky reproducibility coverage ──────────────────────────
 
 /// Set up v1 with `extra_named_mcps` additionally present at `mcp/<stem>.json`,
@@ -859,7 +1158,13 @@ fn setup_and_lock_with_named_mcps(
 ) {
     s.with_named_mcps(named);
     let outcome = run_setup(
-        &RenderInputs { setup: args, sticky_mcp: &[], sticky_hooks: &[] },
+        &RenderInputs {
+            setup: args,
+            sticky_mcp: &[],
+            sticky_hooks: &[],
+            sticky_permissions: &[],
+            placeholders: &Placeholders::default(),
+        },
         s.path(),
         Path::new("."),
         true,
@@ -871,11 +1176,19 @@ fn setup_and_lock_with_named_mcps(
     Lockfile {
         template_repo: REPO_URL.into(),
         template_sha: V1_SHA.into(),
+        template_profile: None,
+        template_ref: None,
+        template_parents: vec![],
+        template_overlays: vec![],
+        packages: Default::default(),
         original_command: OriginalCommand::from_setup(args),
         resolved: Some(Resolved {
             mcp: outcome.mcp_snapshottable_stems,
             hooks: outcome.hooks_snapshottable_stems,
+            permissions: outcome.permissions_snapshottable_stems,
         }),
+        mcp_servers: Default::default(),
+        settings: Default::default(),
         files: manifest,
     }
     .save(Path::new("."))
@@ -918,7 +1231,7 @@ fn prune_stale_drops_opt_in_from_original_command_and_snapshot() {
     // v2 removes foo from the template entirely.
     let v2 = build_scaffold("v2 ts rules\n");
 
-    run_update(&ts_update(false, false, true, false), v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(false, false, true, false), v2.path(), &[revision(V2_SHA)]).unwrap();
 
     let lock_post = Lockfile::load(Path::new(".")).unwrap().unwrap();
     assert!(
@@ -963,7 +1276,7 @@ fn fresh_mcp_flag_rejected_when_stem_only_exists_under_language_dir() {
         only: false,
     };
 
-    let err = run_update(&update, v2.path(), V2_SHA, REPO_URL)
+    let err = run_update(&update, v2.path(), &[revision(V2_SHA)])
         .unwrap_err()
         .to_string();
     assert!(
@@ -1001,7 +1314,7 @@ fn old_lockfile_without_resolved_bypasses_fast_path_and_writes_snapshot() {
 
     // Same SHA, same args, no restore flag. Fast-path would short-circuit
     // unless `snapshot_missing` forces a full pass.
-    run_update(&ts_update(false, false, false, false), v1.path(), V1_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(false, false, false, false), v1.path(), &[revision(V1_SHA)]).unwrap();
 
     let after = Lockfile::load(Path::new(".")).unwrap().unwrap();
     let resolved = after
@@ -1043,7 +1356,7 @@ fn sticky_opt_in_preserved_when_contributor_moves_root_to_default() {
     let v2 = build_scaffold("v2 ts rules\n");
     v2.with_default_mcps(&[("extra", r#"{"extra": {"url": "e2"}}"#)]);
 
-    run_update(&ts_update(false, false, false, false), v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(false, false, false, false), v2.path(), &[revision(V2_SHA)]).unwrap();
 
     let mcp_post = fs::read_to_string(".mcp.json").unwrap();
     assert!(
@@ -1072,7 +1385,13 @@ fn setup_and_lock_with_named_hooks(
 ) {
     s.with_named_hooks(named);
     let outcome = run_setup(
-        &RenderInputs { setup: args, sticky_mcp: &[], sticky_hooks: &[] },
+        &RenderInputs {
+            setup: args,
+            sticky_mcp: &[],
+            sticky_hooks: &[],
+            sticky_permissions: &[],
+            placeholders: &Placeholders::default(),
+        },
         s.path(),
         Path::new("."),
         true,
@@ -1084,11 +1403,19 @@ fn setup_and_lock_with_named_hooks(
     Lockfile {
         template_repo: REPO_URL.into(),
         template_sha: V1_SHA.into(),
+        template_profile: None,
+        template_ref: None,
+        template_parents: vec![],
+        template_overlays: vec![],
+        packages: Default::default(),
         original_command: OriginalCommand::from_setup(args),
         resolved: Some(Resolved {
             mcp: outcome.mcp_snapshottable_stems,
             hooks: outcome.hooks_snapshottable_stems,
+            permissions: outcome.permissions_snapshottable_stems,
         }),
+        mcp_servers: Default::default(),
+        settings: Default::default(),
         files: manifest,
     }
     .save(Path::new("."))
@@ -1125,7 +1452,7 @@ fn update_with_drop_mcp_excludes_default_contributor() {
         merge: false,
         only: false,
     };
-    run_update(&update, v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&update, v2.path(), &[revision(V2_SHA)]).unwrap();
 
     let post = fs::read_to_string(".mcp.json").unwrap();
     assert!(
@@ -1163,7 +1490,13 @@ fn update_mcp_flag_undrops_previously_dropped_default_contributor() {
             ..Default::default()
         };
         let outcome = run_setup(
-            &RenderInputs { setup: &args, sticky_mcp: &[], sticky_hooks: &[] },
+            &RenderInputs {
+                setup: &args,
+                sticky_mcp: &[],
+                sticky_hooks: &[],
+                sticky_permissions: &[],
+                placeholders: &Placeholders::default(),
+            },
             v1.path(),
             Path::new("."),
             true,
@@ -1175,11 +1508,19 @@ fn update_mcp_flag_undrops_previously_dropped_default_contributor() {
         Lockfile {
             template_repo: REPO_URL.into(),
             template_sha: V1_SHA.into(),
+            template_profile: None,
+            template_ref: None,
+            template_parents: vec![],
+            template_overlays: vec![],
+            packages: Default::default(),
             original_command: OriginalCommand::from_setup(&args),
             resolved: Some(Resolved {
                 mcp: outcome.mcp_snapshottable_stems,
                 hooks: outcome.hooks_snapshottable_stems,
+                permissions: outcome.permissions_snapshottable_stems,
             }),
+            mcp_servers: Default::default(),
+            settings: Default::default(),
             files: manifest,
         }
         .save(Path::new("."))
@@ -1203,7 +1544,7 @@ fn update_mcp_flag_undrops_previously_dropped_default_contributor() {
         merge: false,
         only: false,
     };
-    run_update(&update, v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&update, v2.path(), &[revision(V2_SHA)]).unwrap();
 
     let post = fs::read_to_string(".mcp.json").unwrap();
     assert!(
@@ -1271,7 +1612,7 @@ fn prune_stale_drops_hook_opt_in_from_original_command_and_snapshot() {
     // v2 removes hooks/notify.json entirely.
     let v2 = build_scaffold("v2 ts rules\n");
 
-    run_update(&ts_update(false, false, true, false), v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(false, false, true, false), v2.path(), &[revision(V2_SHA)]).unwrap();
 
     let lock_post = Lockfile::load(Path::new(".")).unwrap().unwrap();
     assert!(
@@ -1325,7 +1666,7 @@ fn sticky_hook_opt_in_preserved_when_contributor_moves_root_to_default() {
         r#"{"PreToolUse": [{"type":"command","command":"echo v2"}]}"#,
     )]);
 
-    run_update(&ts_update(false, false, false, false), v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(false, false, false, false), v2.path(), &[revision(V2_SHA)]).unwrap();
 
     let settings_post =
         fs::read_to_string(".claude/settings.local.json").unwrap();
@@ -1383,7 +1724,7 @@ fn sticky_default_hook_preserved_when_relocated_to_root() {
         r#"{"PreToolUse": [{"type":"command","command":"echo v2"}]}"#,
     )]);
 
-    run_update(&ts_update(false, false, false, false), v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(false, false, false, false), v2.path(), &[revision(V2_SHA)]).unwrap();
 
     let settings_post =
         fs::read_to_string(".claude/settings.local.json").unwrap();
@@ -1429,7 +1770,7 @@ fn sticky_default_contributor_preserved_when_relocated_to_root() {
     let v2 = build_scaffold("v2 ts rules\n");
     v2.with_named_mcps(&[("widget", r#"{"widget": {"url": "w2"}}"#)]);
 
-    run_update(&ts_update(false, false, false, false), v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&ts_update(false, false, false, false), v2.path(), &[revision(V2_SHA)]).unwrap();
 
     let mcp_post = fs::read_to_string(".mcp.json").unwrap();
     assert!(
@@ -1473,7 +1814,7 @@ fn only_adds_mcp_without_syncing_template_changes() {
         merge: false,
         only: false,
     };
-    run_update(&update, v1.path(), V1_SHA, REPO_URL).unwrap();
+    run_update(&update, v1.path(), &[revision(V1_SHA)]).unwrap();
 
     // MCP was added.
     let mcp_post = fs::read_to_string(".mcp.json").unwrap();
@@ -1520,7 +1861,7 @@ fn only_adds_hooks_without_syncing_template_changes() {
         merge: false,
         only: false,
     };
-    run_update(&update, v1.path(), V1_SHA, REPO_URL).unwrap();
+    run_update(&update, v1.path(), &[revision(V1_SHA)]).unwrap();
 
     let settings = fs::read_to_string(".claude/settings.local.json").unwrap();
     assert!(
@@ -1568,7 +1909,7 @@ fn normal_update_syncs_template_changes_unlike_only() {
         merge: false,
         only: false,
     };
-    run_update(&update, v2.path(), V2_SHA, REPO_URL).unwrap();
+    run_update(&update, v2.path(), &[revision(V2_SHA)]).unwrap();
 
     // MCP landed.
     let mcp_post = fs::read_to_string(".mcp.json").unwrap();
@@ -1604,7 +1945,7 @@ fn only_with_no_additive_args_is_noop() {
         merge: false,
         only: false,
     };
-    run_update(&update, v1.path(), V1_SHA, REPO_URL).unwrap();
+    run_update(&update, v1.path(), &[revision(V1_SHA)]).unwrap();
 
     assert_eq!(fs::read("CLAUDE.md").unwrap(), claude_md_before);
     assert_eq!(
@@ -1613,3 +1954,180 @@ fn only_with_no_additive_args_is_noop() {
         "lockfile must not be re-written on no-op --only"
     );
 }
+
+// ── Template profiles ──────────────────────────────────────────────────
+
+#[test]
+fn template_profile_switch_bypasses_fast_path_and_is_recorded() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+
+    let v1 = build_scaffold("v1 ts rules\n");
+    setup_and_lock(&v1, V1_SHA);
+
+    // Same SHA and command, but a different profile: must not short-circuit.
+    let mut update = ts_update(false, false, false, false);
+    update.setup.template = vec!["experimental".into()];
+    let mut rev = revision(V1_SHA);
+    rev.profile = Some("experimental".into());
+    run_update(&update, v1.path(), &[rev]).unwrap();
+
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert_eq!(lock.template_profile.as_deref(), Some("experimental"));
+
+    // A later plain update keeps the recorded profile.
+    run_update(&ts_update(false, false, false, false), v1.path(), &[revision(V2_SHA)]).unwrap();
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert_eq!(lock.template_profile.as_deref(), Some("experimental"));
+}
+
+#[test]
+fn ref_flag_is_recorded_and_followed_by_later_updates() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+
+    let v1 = build_scaffold("v1 ts rules\n");
+    setup_and_lock(&v1, V1_SHA);
+
+    let mut update = ts_update(false, false, false, false);
+    update.setup.git_ref = Some("stable".into());
+    run_update(&update, v1.path(), &[revision(V1_SHA)]).unwrap();
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert_eq!(lock.template_ref.as_deref(), Some("stable"));
+
+    // Plain update: the recorded ref survives.
+    let v2 = build_scaffold("v2 ts rules\n");
+    run_update(&ts_update(false, false, false, false), v2.path(), &[revision(V2_SHA)]).unwrap();
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert_eq!(lock.template_ref.as_deref(), Some("stable"));
+    assert_eq!(lock.template_sha, V2_SHA);
+}
+
+// ── Template inheritance ───────────────────────────────────────────────
+
+#[test]
+fn parent_template_change_bypasses_fast_path_and_is_recorded() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+
+    let v1 = build_scaffold("v1 ts rules\n");
+    setup_and_lock(&v1, V1_SHA);
+
+    // Child SHA unchanged, but the tree now comes with a (new) parent.
+    let mut rev = revision(V1_SHA);
+    rev.parents = vec![LockedSource {
+        repo: "test://example/base.git".into(),
+        git_ref: Some("v3".into()),
+        sha: V2_SHA.into(),
+    }];
+    let v2 = build_scaffold("inherited ts rules\n");
+    run_update(&ts_update(false, false, false, false), v2.path(), &[rev.clone()]).unwrap();
+
+    assert!(fs::read_to_string("CLAUDE.md").unwrap().contains("inherited ts rules"));
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert_eq!(lock.template_parents, rev.parents);
+
+    // Same child + same parents: fast path again.
+    let lock_before = fs::read(LOCKFILE_NAME).unwrap();
+    run_update(&ts_update(false, false, false, false), v2.path(), &[rev.clone()]).unwrap();
+    assert_eq!(lock_before, fs::read(LOCKFILE_NAME).unwrap());
+}
+
+// ── Stacked templates ──────────────────────────────────────────────────
+
+#[test]
+fn stacked_template_change_bypasses_fast_path_and_is_recorded() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+
+    let v1 = build_scaffold("v1 ts rules\n");
+    setup_and_lock(&v1, V1_SHA);
+
+    // Base unchanged, but a team template is now stacked on top.
+    let team = TemplateRevision {
+        profile: Some("team".into()),
+        repo: "test://example/team.git".into(),
+        sha: V2_SHA.into(),
+        ..Default::default()
+    };
+    let stack = [revision(V1_SHA), team.clone()];
+    let v2 = build_scaffold("team ts rules\n");
+    run_update(&ts_update(false, false, false, false), v2.path(), &stack).unwrap();
+
+    assert!(fs::read_to_string("CLAUDE.md").unwrap().contains("team ts rules"));
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert_eq!(lock.template_sha, V1_SHA);
+    assert_eq!(lock.template_overlays, vec![team]);
+    assert_eq!(lock.sources(), stack.to_vec());
+
+    // Same stack: fast path.
+    let lock_before = fs::read(LOCKFILE_NAME).unwrap();
+    run_update(&ts_update(false, false, false, false), v2.path(), &stack).unwrap();
+    assert_eq!(lock_before, fs::read(LOCKFILE_NAME).unwrap());
+
+    // Dropping the overlay again is a template change too.
+    run_update(&ts_update(false, false, false, false), v1.path(), &[revision(V1_SHA)]).unwrap();
+    assert!(fs::read_to_string("CLAUDE.md").unwrap().contains("v1 ts rules"));
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert!(lock.template_overlays.is_empty());
+}
+
+// ── Project config ─────────────────────────────────────────────────────
+
+#[test]
+fn project_config_additions_apply_on_plain_update() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+
+    let v1 = build_scaffold("v1 ts rules\n");
+    v1.with_named_mcps(&[("postgres", r#"{"postgres": {"url": "pg"}}"#)]);
+    setup_and_lock(&v1, V1_SHA);
+    assert!(!fs::read_to_string(".mcp.json").unwrap().contains("postgres"));
+
+    // Same SHA, no CLI args: the checked-in desired state alone must defeat
+    // the fast path.
+    fs::write(PROJECT_CONFIG_NAME, "mcp: [postgres]\n").unwrap();
+    run_update(&ts_update(false, false, false, false), v1.path(), &[revision(V1_SHA)]).unwrap();
+
+    assert!(fs::read_to_string(".mcp.json").unwrap().contains("postgres"));
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert_eq!(lock.original_command.mcp, vec!["postgres"]);
+    assert!(!lock.files.contains_key(PROJECT_CONFIG_NAME), "clemp never manages .clemp.yaml");
+}
+
+#[test]
+fn persisted_drop_survives_a_project_config_listing_the_item() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+
+    let v1 = build_scaffold("v1 ts rules\n");
+    v1.with_named_mcps(&[("postgres", r#"{"postgres": {"url": "pg"}}"#)]);
+    fs::write(PROJECT_CONFIG_NAME, "mcp: [postgres]\n").unwrap();
+    setup_and_lock(&v1, V1_SHA);
+    run_update(&ts_update(false, false, false, false), v1.path(), &[revision(V1_SHA)]).unwrap();
+    assert!(fs::read_to_string(".mcp.json").unwrap().contains("postgres"));
+
+    let mut drop = ts_update(false, false, false, false);
+    drop.setup.drop_mcp = vec!["postgres".into()];
+    run_update(&drop, v1.path(), &[revision(V1_SHA)]).unwrap();
+    assert!(!fs::read_to_string(".mcp.json").unwrap().contains("postgres"));
+
+    // A plain update, and one against a new template, keep the drop even
+    // though `.clemp.yaml` still lists the server.
+    let lock_before = fs::read(LOCKFILE_NAME).unwrap();
+    run_update(&ts_update(false, false, false, false), v1.path(), &[revision(V1_SHA)]).unwrap();
+    assert_eq!(lock_before, fs::read(LOCKFILE_NAME).unwrap(), "nothing to do");
+    let v2 = build_scaffold("v2 ts rules\n");
+    v2.with_named_mcps(&[("postgres", r#"{"postgres": {"url": "pg"}}"#)]);
+    run_update(&ts_update(false, false, false, false), v2.path(), &[revision(V2_SHA)]).unwrap();
+    assert!(!fs::read_to_string(".mcp.json").unwrap().contains("postgres"));
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert_eq!(lock.original_command.drop_mcp, vec!["postgres"]);
+    assert!(lock.original_command.mcp.is_empty());
+
+    // Passing it again restores it.
+    let mut restore = ts_update(false, false, false, false);
+    restore.setup.mcp = vec!["postgres".into()];
+    run_update(&restore, v2.path(), &[revision(V2_SHA)]).unwrap();
+    assert!(fs::read_to_string(".mcp.json").unwrap().contains("postgres"));
+}
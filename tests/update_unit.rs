@@ -6,9 +6,11 @@ mod common;
 
 use clap::Parser;
 use clemp::{
-    classify_update_path, compute_manifest, hash_bytes, lockfile_key, merge_with_claude,
+    classify_update_path, compute_manifest, hash_bytes, lockfile_key, merge_mcp_servers,
+    merge_with_claude,
     normalize_setup_args, reject_add_drop_overlap, run_setup, Cli, CliCommand, Lockfile,
-    OriginalCommand, RenderInputs, SetupArgs, UpdateClass,
+    LanguageAliases, OriginalCommand, PackageLock, Placeholders, RenderInputs, Resolved, SettingsPart,
+    SetupArgs, TemplateManifest, TemplateRevision, UpdateClass,
 };
 use common::{CwdGuard, PathGuard, Scaffold};
 use std::collections::BTreeMap;
@@ -26,9 +28,16 @@ fn merge_additive_unions_vectors_without_duplicates() {
         mcp: vec!["context7".into()],
         commands: vec!["review".into()],
         githooks: vec!["pre-push".into()],
+        permissions: vec!["git".into()],
         drop_mcp: vec![],
         drop_hooks: vec![],
+        drop_permissions: vec![],
+        drop_lang: vec![],
+        tags: vec![],
+        drop_tags: vec![],
         clarg: Some("default".into()),
+        shared_settings: vec![SettingsPart::Hooks, SettingsPart::Mcp],
+        local_settings: vec![],
     };
     let b = OriginalCommand {
         languages: vec!["typescript".into(), "python".into()],
@@ -36,17 +45,28 @@ fn merge_additive_unions_vectors_without_duplicates() {
         mcp: vec![],
         commands: vec!["review".into(), "deploy".into()],
         githooks: vec!["commit-msg".into()],
+        permissions: vec!["cargo".into(), "git".into()],
         drop_mcp: vec![],
         drop_hooks: vec![],
+        drop_permissions: vec![],
+        drop_lang: vec![],
+        tags: vec![],
+        drop_tags: vec![],
         clarg: None,
+        shared_settings: vec![SettingsPart::Permissions],
+        local_settings: vec![SettingsPart::Mcp],
     };
-    a.merge_additive(&b).unwrap();
+    a.merge_additive(&b, &LanguageAliases::default()).unwrap();
 
     assert_eq!(a.languages, vec!["typescript", "python"]);
     assert_eq!(a.hooks, vec!["sound", "lint"]);
     assert_eq!(a.mcp, vec!["context7"]);
     assert_eq!(a.commands, vec!["review", "deploy"]);
     assert_eq!(a.githooks, vec!["pre-push", "commit-msg"]);
+    assert_eq!(a.permissions, vec!["git", "cargo"]);
+    // The newer --local-settings moves mcp back out of the shared file.
+    assert_eq!(a.shared_settings, vec![SettingsPart::Hooks, SettingsPart::Permissions]);
+    assert_eq!(a.local_settings, vec![SettingsPart::Mcp]);
     // None on the right keeps the existing clarg.
     assert_eq!(a.clarg.as_deref(), Some("default"));
 }
@@ -61,7 +81,7 @@ fn merge_additive_replaces_clarg_only_when_other_set() {
         clarg: Some("strict".into()),
         ..Default::default()
     };
-    a.merge_additive(&b).unwrap();
+    a.merge_additive(&b, &LanguageAliases::default()).unwrap();
     assert_eq!(a.clarg.as_deref(), Some("strict"));
 }
 
@@ -75,7 +95,7 @@ fn merge_additive_clarg_unset_on_other_preserves_existing() {
         clarg: None,
         ..Default::default()
     };
-    a.merge_additive(&b).unwrap();
+    a.merge_additive(&b, &LanguageAliases::default()).unwrap();
     assert_eq!(a.clarg.as_deref(), Some("default"));
 }
 
@@ -131,7 +151,7 @@ fn merge_additive_dedupes_language_aliases() {
         languages: vec!["typescript".into(), "python".into(), "TS".into()],
         ..Default::default()
     };
-    a.merge_additive(&b).unwrap();
+    a.merge_additive(&b, &LanguageAliases::default()).unwrap();
     // "typescript" and "TS" both canonicalize to "typescript" (already present
     // via "ts"), so only "python" should be appended.
     assert_eq!(
@@ -142,6 +162,17 @@ fn merge_additive_dedupes_language_aliases() {
     );
 }
 
+#[test]
+fn merge_additive_dedupes_through_template_aliases() {
+    let manifest: clemp::TemplateManifest =
+        serde_yaml::from_str("aliases:\n  kotlin: [kt]\n").unwrap();
+    let aliases = LanguageAliases::from_manifest(&manifest);
+    let mut a = OriginalCommand { languages: vec!["kotlin".into()], ..Default::default() };
+    let b = OriginalCommand { languages: vec!["kt".into(), "ts".into()], ..Default::default() };
+    a.merge_additive(&b, &aliases).unwrap();
+    assert_eq!(a.languages, vec!["kotlin", "ts"]);
+}
+
 #[test]
 fn merge_additive_dedupes_languages_in_other_side_too() {
     let mut a = OriginalCommand::default();
@@ -149,7 +180,7 @@ fn merge_additive_dedupes_languages_in_other_side_too() {
         languages: vec!["ts".into(), "typescript".into(), "python".into(), "py".into()],
         ..Default::default()
     };
-    a.merge_additive(&b).unwrap();
+    a.merge_additive(&b, &LanguageAliases::default()).unwrap();
     assert_eq!(
         a.languages,
         vec!["ts", "python"],
@@ -167,7 +198,7 @@ fn merge_additive_same_invocation_mcp_add_and_drop_is_rejected() {
         drop_mcp: vec!["context7".into()],
         ..Default::default()
     };
-    let err = a.merge_additive(&b).unwrap_err().to_string();
+    let err = a.merge_additive(&b, &LanguageAliases::default()).unwrap_err().to_string();
     assert!(
         err.contains("context7") && err.contains("--mcp") && err.contains("--drop-mcp"),
         "same-invocation --mcp + --drop-mcp for the same stem must be rejected: {err}"
@@ -182,7 +213,7 @@ fn merge_additive_same_invocation_hooks_add_and_drop_is_rejected() {
         drop_hooks: vec!["sound".into()],
         ..Default::default()
     };
-    let err = a.merge_additive(&b).unwrap_err().to_string();
+    let err = a.merge_additive(&b, &LanguageAliases::default()).unwrap_err().to_string();
     assert!(
         err.contains("sound") && err.contains("--hooks") && err.contains("--drop-hooks"),
         "same-invocation --hooks + --drop-hooks for the same stem must be rejected: {err}"
@@ -202,7 +233,7 @@ fn merge_additive_fresh_add_clears_persisted_drop_mcp() {
         mcp: vec!["context7".into()],
         ..Default::default()
     };
-    a.merge_additive(&b).unwrap();
+    a.merge_additive(&b, &LanguageAliases::default()).unwrap();
     assert_eq!(a.mcp, vec!["context7"]);
     assert!(
         a.drop_mcp.is_empty(),
@@ -222,7 +253,7 @@ fn merge_additive_fresh_drop_clears_persisted_mcp() {
         drop_mcp: vec!["context7".into()],
         ..Default::default()
     };
-    a.merge_additive(&b).unwrap();
+    a.merge_additive(&b, &LanguageAliases::default()).unwrap();
     assert!(
         a.mcp.is_empty(),
         "newer --drop-mcp must clear persisted --mcp, got {:?}",
@@ -242,7 +273,7 @@ fn merge_additive_fresh_add_clears_persisted_drop_hooks_symmetric() {
         hooks: vec!["sound".into()],
         ..Default::default()
     };
-    a.merge_additive(&b).unwrap();
+    a.merge_additive(&b, &LanguageAliases::default()).unwrap();
     assert_eq!(a.hooks, vec!["sound"]);
     assert!(a.drop_hooks.is_empty());
 
@@ -255,11 +286,66 @@ fn merge_additive_fresh_add_clears_persisted_drop_hooks_symmetric() {
         drop_hooks: vec!["sound".into()],
         ..Default::default()
     };
-    a.merge_additive(&b).unwrap();
+    a.merge_additive(&b, &LanguageAliases::default()).unwrap();
     assert!(a.hooks.is_empty());
     assert_eq!(a.drop_hooks, vec!["sound"]);
 }
 
+#[test]
+fn merge_additive_drop_lang_reconciles_by_canonical_name() {
+    let mut a = OriginalCommand {
+        languages: vec!["ts".into(), "python".into()],
+        ..Default::default()
+    };
+    let b = OriginalCommand {
+        drop_lang: vec!["py".into()],
+        ..Default::default()
+    };
+    a.merge_additive(&b, &LanguageAliases::default()).unwrap();
+    assert_eq!(a.languages, vec!["ts"]);
+    assert_eq!(a.drop_lang, vec!["py"]);
+
+    // Re-adding the language clears the persisted drop.
+    let b = OriginalCommand {
+        languages: vec!["python".into()],
+        ..Default::default()
+    };
+    a.merge_additive(&b, &LanguageAliases::default()).unwrap();
+    assert_eq!(a.languages, vec!["ts", "python"]);
+    assert!(a.drop_lang.is_empty());
+}
+
+#[test]
+fn reject_add_drop_overlap_detects_language_overlap() {
+    let cmd = OriginalCommand {
+        languages: vec!["Python".into()],
+        drop_lang: vec!["python".into()],
+        ..Default::default()
+    };
+    let err = reject_add_drop_overlap(&cmd, &LanguageAliases::default()).unwrap_err().to_string();
+    assert!(err.contains("--drop-lang"), "got: {err}");
+}
+
+#[test]
+fn reject_add_drop_overlap_compares_languages_by_canonical_name() {
+    let cmd = OriginalCommand {
+        languages: vec!["ts".into()],
+        drop_lang: vec!["typescript".into()],
+        ..Default::default()
+    };
+    let err = reject_add_drop_overlap(&cmd, &LanguageAliases::default()).unwrap_err().to_string();
+    assert!(err.contains("'ts'") && err.contains("--drop-lang"), "got: {err}");
+
+    let manifest: TemplateManifest = serde_yaml::from_str("aliases:\n  kotlin: [kt]\n").unwrap();
+    let cmd = OriginalCommand {
+        languages: vec!["kt".into()],
+        drop_lang: vec!["kotlin".into()],
+        ..Default::default()
+    };
+    assert!(reject_add_drop_overlap(&cmd, &LanguageAliases::default()).is_ok());
+    assert!(reject_add_drop_overlap(&cmd, &LanguageAliases::from_manifest(&manifest)).is_err());
+}
+
 // ── CLI parsing for drop flags ──────────────────────────────────────────
 
 #[test]
@@ -271,12 +357,15 @@ fn cli_update_drop_flags_parse_comma_and_whitespace() {
         "context7,puppeteer",
         "--drop-hooks",
         "sound",
+        "--drop-lang",
+        "py",
     ])
     .unwrap();
     match cli.command {
         Some(CliCommand::Update(args)) => {
             assert_eq!(args.setup.drop_mcp, vec!["context7", "puppeteer"]);
             assert_eq!(args.setup.drop_hooks, vec!["sound"]);
+            assert_eq!(args.setup.drop_lang, vec!["py"]);
         }
         _ => panic!("expected Update"),
     }
@@ -295,7 +384,7 @@ fn reject_add_drop_overlap_detects_mcp_overlap_on_fresh_setup_command() {
         drop_mcp: vec!["context7".into()],
         ..Default::default()
     };
-    let err = reject_add_drop_overlap(&cmd).unwrap_err().to_string();
+    let err = reject_add_drop_overlap(&cmd, &LanguageAliases::default()).unwrap_err().to_string();
     assert!(
         err.contains("context7") && err.contains("--mcp") && err.contains("--drop-mcp"),
         "overlap guard must flag context7 conflict for initial setup: {err}"
@@ -309,13 +398,27 @@ fn reject_add_drop_overlap_detects_hooks_overlap() {
         drop_hooks: vec!["sound".into()],
         ..Default::default()
     };
-    let err = reject_add_drop_overlap(&cmd).unwrap_err().to_string();
+    let err = reject_add_drop_overlap(&cmd, &LanguageAliases::default()).unwrap_err().to_string();
     assert!(
         err.contains("sound") && err.contains("--hooks") && err.contains("--drop-hooks"),
         "overlap guard must flag hooks conflict: {err}"
     );
 }
 
+#[test]
+fn reject_add_drop_overlap_detects_settings_target_overlap() {
+    let cmd = OriginalCommand {
+        shared_settings: vec![SettingsPart::Hooks, SettingsPart::Clarg],
+        local_settings: vec![SettingsPart::Clarg],
+        ..Default::default()
+    };
+    let err = reject_add_drop_overlap(&cmd, &LanguageAliases::default()).unwrap_err().to_string();
+    assert!(
+        err.contains("'clarg'") && err.contains("--shared-settings") && err.contains("--local-settings"),
+        "overlap guard must flag settings target conflict: {err}"
+    );
+}
+
 #[test]
 fn reject_add_drop_overlap_allows_disjoint_sets() {
     let cmd = OriginalCommand {
@@ -325,7 +428,7 @@ fn reject_add_drop_overlap_allows_disjoint_sets() {
         drop_hooks: vec!["lint".into()],
         ..Default::default()
     };
-    reject_add_drop_overlap(&cmd).unwrap();
+    reject_add_drop_overlap(&cmd, &LanguageAliases::default()).unwrap();
 }
 
 #[test]
@@ -398,7 +501,10 @@ fn cli_update_merge_and_force_conflict() {
 fn cli_list_no_category() {
     let cli = Cli::try_parse_from(["clemp", "list"]).unwrap();
     match cli.command {
-        Some(CliCommand::List { category }) => assert!(category.is_none()),
+        Some(CliCommand::List { category, template, .. }) => {
+            assert!(category.is_none());
+            assert!(template.is_empty());
+        }
         _ => panic!("expected List"),
     }
 }
@@ -407,11 +513,68 @@ fn cli_list_no_category() {
 fn cli_list_with_category() {
     let cli = Cli::try_parse_from(["clemp", "list", "mcp"]).unwrap();
     match cli.command {
-        Some(CliCommand::List { category }) => assert_eq!(category.as_deref(), Some("mcp")),
+        Some(CliCommand::List { category, .. }) => assert_eq!(category.as_deref(), Some("mcp")),
         _ => panic!("expected List"),
     }
 }
 
+#[test]
+fn cli_list_with_template_profile() {
+    let cli = Cli::try_parse_from(["clemp", "list", "hooks", "--template", "experimental"]).unwrap();
+    match cli.command {
+        Some(CliCommand::List { category, template, .. }) => {
+            assert_eq!(category.as_deref(), Some("hooks"));
+            assert_eq!(template, vec!["experimental"]);
+        }
+        _ => panic!("expected List"),
+    }
+}
+
+#[test]
+fn cli_template_flag_on_setup_and_update() {
+    let cli = Cli::try_parse_from(["clemp", "ts", "--template", "org"]).unwrap();
+    assert_eq!(cli.setup.template, vec!["org"]);
+
+    let cli = Cli::try_parse_from(["clemp", "update", "--template", "experimental"]).unwrap();
+    match cli.command {
+        Some(CliCommand::Update(args)) => {
+            assert_eq!(args.setup.template, vec!["experimental"])
+        }
+        _ => panic!("expected Update"),
+    }
+}
+
+#[test]
+fn cli_template_flag_stacks_in_order() {
+    let cli = Cli::try_parse_from(["clemp", "ts", "--template", "org", "--template", "team"]).unwrap();
+    assert_eq!(cli.setup.template, vec!["org", "team"]);
+
+    let cli = Cli::try_parse_from(["clemp", "list", "--template", "org,team,me"]).unwrap();
+    match cli.command {
+        Some(CliCommand::List { template, .. }) => assert_eq!(template, vec!["org", "team", "me"]),
+        _ => panic!("expected List"),
+    }
+}
+
+#[test]
+fn cli_ref_flag_on_setup_and_update() {
+    let cli = Cli::try_parse_from(["clemp", "ts", "--ref", "v1.2.0"]).unwrap();
+    assert_eq!(cli.setup.git_ref.as_deref(), Some("v1.2.0"));
+
+    let cli = Cli::try_parse_from(["clemp", "update", "--ref", "stable"]).unwrap();
+    match cli.command {
+        Some(CliCommand::Update(args)) => assert_eq!(args.setup.git_ref.as_deref(), Some("stable")),
+        _ => panic!("expected Update"),
+    }
+    assert!(Cli::try_parse_from(["clemp", "update", "--only", "--ref", "stable"]).is_err());
+}
+
+#[test]
+fn cli_update_only_and_template_conflict() {
+    let err = Cli::try_parse_from(["clemp", "update", "--only", "--template", "org"]);
+    assert!(err.is_err(), "--only pins the lockfile SHA and can't switch profiles");
+}
+
 #[test]
 fn cli_default_command_is_setup() {
     let cli = Cli::try_parse_from(["clemp", "ts", "--hooks", "sound"]).unwrap();
@@ -451,17 +614,60 @@ fn lockfile_round_trip_preserves_all_fields() {
     let lock = Lockfile {
         template_repo: "https://example.test/template.git".into(),
         template_sha: "deadbeef".into(),
+        template_profile: Some("org".into()),
+        template_ref: Some("v2".into()),
+        template_parents: vec![],
+        template_overlays: vec![TemplateRevision {
+            profile: Some("team".into()),
+            repo: "https://example.test/team.git".into(),
+            sha: "cafef00d".into(),
+            parents: vec![],
+        }],
         original_command: OriginalCommand {
             languages: vec!["typescript".into()],
             hooks: vec!["sound".into()],
             mcp: vec!["context7".into()],
             commands: vec!["review".into()],
             githooks: vec!["pre-push".into()],
+            permissions: vec!["git".into()],
             drop_mcp: vec![],
             drop_hooks: vec![],
+            drop_permissions: vec!["web".into()],
+            drop_lang: vec![],
+            tags: vec![],
+            drop_tags: vec![],
             clarg: Some("default".into()),
+            shared_settings: vec![SettingsPart::Hooks],
+            local_settings: vec![],
         },
         resolved: None,
+        packages: [(
+            "web".to_string(),
+            PackageLock {
+                original_command: OriginalCommand {
+                    languages: vec!["ts".into()],
+                    ..Default::default()
+                },
+                resolved: Some(Resolved::default()),
+            },
+        )]
+        .into_iter()
+        .collect(),
+        mcp_servers: [(
+            ".mcp.json".to_string(),
+            serde_json::json!({"context7": {"type": "http", "url": "${C7_URL}"}})
+                .as_object()
+                .unwrap()
+                .clone(),
+        )]
+        .into_iter()
+        .collect(),
+        settings: [(
+            ".claude/settings.local.json".to_string(),
+            serde_json::json!({"permissions": {"allow": ["Bash(ls)"]}}),
+        )]
+        .into_iter()
+        .collect(),
         files: files.clone(),
     };
 
@@ -470,10 +676,67 @@ fn lockfile_round_trip_preserves_all_fields() {
 
     assert_eq!(loaded.template_repo, lock.template_repo);
     assert_eq!(loaded.template_sha, lock.template_sha);
+    assert_eq!(loaded.template_profile, lock.template_profile);
+    assert_eq!(loaded.template_ref, lock.template_ref);
+    assert_eq!(loaded.template_overlays, lock.template_overlays);
     assert_eq!(loaded.original_command, lock.original_command);
+    assert_eq!(loaded.packages, lock.packages);
+    assert_eq!(loaded.mcp_servers, lock.mcp_servers);
+    assert_eq!(loaded.settings, lock.settings);
     assert_eq!(loaded.files, files);
 }
 
+#[test]
+fn lockfile_without_profile_field_still_loads() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join(".clemp-lock.yaml"),
+        "template-repo: r\ntemplate-sha: s\noriginal-command: {}\nfiles: {}\n",
+    )
+    .unwrap();
+    let loaded = Lockfile::load(dir.path()).unwrap().unwrap();
+    assert!(loaded.template_profile.is_none());
+}
+
+fn lock_with(profile: Option<&str>, git_ref: Option<&str>) -> Lockfile {
+    Lockfile {
+        template_repo: "r".into(),
+        template_sha: "s".into(),
+        template_profile: profile.map(String::from),
+        template_ref: git_ref.map(String::from),
+        template_parents: vec![],
+        template_overlays: vec![],
+        packages: Default::default(),
+        original_command: OriginalCommand::default(),
+        resolved: None,
+        mcp_servers: Default::default(),
+        settings: Default::default(),
+        files: BTreeMap::new(),
+    }
+}
+
+#[test]
+fn followed_ref_keeps_recorded_ref_without_flags() {
+    let lock = lock_with(Some("org"), Some("stable"));
+    assert_eq!(lock.followed_ref(&SetupArgs::default()).as_deref(), Some("stable"));
+}
+
+#[test]
+fn followed_ref_explicit_ref_wins() {
+    let lock = lock_with(Some("org"), Some("stable"));
+    let args = SetupArgs { git_ref: Some("v2.0".into()), ..Default::default() };
+    assert_eq!(lock.followed_ref(&args).as_deref(), Some("v2.0"));
+}
+
+#[test]
+fn followed_ref_dropped_when_switching_profile() {
+    let lock = lock_with(Some("org"), Some("stable"));
+    let same = SetupArgs { template: vec!["org".into()], ..Default::default() };
+    assert_eq!(lock.followed_ref(&same).as_deref(), Some("stable"));
+    let other = SetupArgs { template: vec!["experimental".into()], ..Default::default() };
+    assert_eq!(lock.followed_ref(&other), None);
+}
+
 #[test]
 fn lockfile_load_returns_none_when_missing() {
     let dir = TempDir::new().unwrap();
@@ -503,7 +766,7 @@ fn manifest_scaffold() -> Scaffold {
     let s = Scaffold::new();
     s.with_template("{{ lang_rules }}", &[("typescript.md", "ts")]);
     s.with_settings("{}");
-    s.with_default_mcps(&[("context7", r#"{"context7":{}}"#)]);
+    s.with_default_mcps(&[("context7", r#"{"context7":{"command":"c7"}}"#)]);
     s.with_copied("default", &[(".editorconfig", "root = true")]);
     s.with_gitignore_additions(".claude/\n");
     s
@@ -520,7 +783,13 @@ fn compute_manifest_includes_root_files_overlays_excludes_meta() {
         ..Default::default()
     };
     let outcome = run_setup(
-        &RenderInputs { setup: &args, sticky_mcp: &[], sticky_hooks: &[] },
+        &RenderInputs {
+            setup: &args,
+            sticky_mcp: &[],
+            sticky_hooks: &[],
+            sticky_permissions: &[],
+            placeholders: &Placeholders::default(),
+        },
         s.path(),
         Path::new("."),
         true,
@@ -568,7 +837,13 @@ fn compute_manifest_no_git_hooks_entries_when_no_git_dir() {
         ..Default::default()
     };
     let outcome = run_setup(
-        &RenderInputs { setup: &args, sticky_mcp: &[], sticky_hooks: &[] },
+        &RenderInputs {
+            setup: &args,
+            sticky_mcp: &[],
+            sticky_hooks: &[],
+            sticky_permissions: &[],
+            placeholders: &Placeholders::default(),
+        },
         s.path(),
         Path::new("."),
         true,
@@ -600,7 +875,13 @@ fn compute_manifest_includes_git_hooks_when_installed() {
         ..Default::default()
     };
     let outcome = run_setup(
-        &RenderInputs { setup: &args, sticky_mcp: &[], sticky_hooks: &[] },
+        &RenderInputs {
+            setup: &args,
+            sticky_mcp: &[],
+            sticky_hooks: &[],
+            sticky_permissions: &[],
+            placeholders: &Placeholders::default(),
+        },
         s.path(),
         Path::new("."),
         true,
@@ -622,7 +903,8 @@ fn compute_manifest_includes_git_hooks_when_installed() {
 fn classifier_table() {
     use UpdateClass::*;
     // (label, old, cur, new, cwd_is_dir, expected)
-    let cases: &[(&str, Option<&str>, Option<&str>, &str, bool, UpdateClass)] = &[
+    type Case<'a> = (&'a str, Option<&'a str>, Option<&'a str>, &'a str, bool, UpdateClass);
+    let cases: &[Case] = &[
         ("clean (template moved, user untouched)", Some("a"), Some("a"), "b", false, Clean),
         ("identical (everything matches)",        Some("a"), Some("a"), "a", false, Identical),
         ("new (not tracked, not on disk)",        None,     None,     "b", false, New),
@@ -644,6 +926,35 @@ fn classifier_table() {
     }
 }
 
+// ── merge_mcp_servers ───────────────────────────────────────────────────
+
+#[test]
+fn server_merge_takes_each_sides_changes() {
+    let servers = |v: serde_json::Value| v.as_object().unwrap().clone();
+    let base = servers(serde_json::json!({"c7": {"url": "v1"}, "old": {"url": "o"}, "gone": {"url": "g"}}));
+    // User added `mine` and deleted `gone`; the template updated `c7`,
+    // dropped `old` and added `new`.
+    let ours = servers(serde_json::json!({"c7": {"url": "v1"}, "old": {"url": "o"}, "mine": {"url": "m"}}));
+    let theirs = servers(serde_json::json!({"c7": {"url": "v2"}, "gone": {"url": "g"}, "new": {"url": "n"}}));
+
+    let merged = merge_mcp_servers(&base, &ours, &theirs).unwrap();
+    assert_eq!(
+        serde_json::Value::Object(merged),
+        serde_json::json!({"c7": {"url": "v2"}, "mine": {"url": "m"}, "new": {"url": "n"}})
+    );
+}
+
+#[test]
+fn server_merge_conflicts_only_on_servers_both_sides_changed() {
+    let servers = |v: serde_json::Value| v.as_object().unwrap().clone();
+    let base = servers(serde_json::json!({"c7": {"url": "v1"}, "pg": {"command": "pg"}}));
+    let ours = servers(serde_json::json!({"c7": {"url": "mine"}, "pg": {"command": "pg2"}, "x": {"url": "a"}}));
+    let theirs = servers(serde_json::json!({"c7": {"url": "v2"}, "pg": {"command": "pg2"}, "x": {"url": "b"}}));
+
+    let conflicts = merge_mcp_servers(&base, &ours, &theirs).unwrap_err();
+    assert_eq!(conflicts, vec!["c7", "x"], "identical edits to `pg` agree");
+}
+
 #[test]
 fn classifier_treats_directory_as_distinct_from_missing() {
     // Same hash triple, only `cwd_is_dir` differs.
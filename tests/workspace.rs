@@ -0,0 +1,348 @@
+//! Tests for monorepo mode: `.clemp.yaml` `packages:`, per-package renders
+//! and a single lockfile that `clemp update` refreshes in one pass.
+
+mod common;
+
+use clemp::{
+    apply_package, compute_manifest, load_workspace_packages, overwrite_conflicts, run_update,
+    stage_package, stage_setup, update_gitignore, Lockfile, OriginalCommand, PackageLock,
+    PackageTemplates, ProjectConfig, Placeholders, RenderInputs, Resolved, SetupArgs, StagedSetup,
+    TemplateRevision, UpdateArgs, PROJECT_CONFIG_NAME,
+};
+use common::{CwdGuard, Scaffold};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+const WORKSPACE: &str = "languages: [ts]\n\
+packages:\n  server:\n    languages: [rust]\n    mcp: [postgres]\n  web:\n    languages: [ts]\n";
+
+fn scaffold(version: &str) -> Scaffold {
+    let s = Scaffold::new();
+    s.with_template(
+        &format!("{version}\n{{{{ lang_rules }}}}\n"),
+        &[("typescript.md", "ts rules"), ("rust.md", "rust rules")],
+    );
+    s.with_settings(r#"{"permissions": {"allow": []}}"#);
+    s.with_default_mcps(&[("context7", r#"{"context7": {"url": "c7"}}"#)]);
+    s.with_named_mcps(&[("postgres", r#"{"postgres": {"url": "pg"}}"#)]);
+    s.with_commands("rust", &[("clippy.md", "run clippy")]);
+    s
+}
+
+fn revision(sha: &str) -> TemplateRevision {
+    TemplateRevision {
+        repo: "test://example/template.git".into(),
+        sha: sha.into(),
+        ..Default::default()
+    }
+}
+
+/// Mirror `clemp` setup for a workspace: stage the root and every package,
+/// clear conflicts, write them all, then one lockfile.
+fn try_setup_workspace(s: &Scaffold, sha: &str) -> anyhow::Result<()> {
+    let cwd = Path::new(".");
+    let config = ProjectConfig::load(cwd)?.unwrap();
+    let templates = PackageTemplates::new(s.path(), config.packages.keys())?;
+    let placeholders = Placeholders::default();
+
+    let args = config.command.clone().into_setup();
+    let inputs = RenderInputs {
+        setup: &args,
+        sticky_mcp: &[],
+        sticky_hooks: &[],
+        sticky_permissions: &[],
+        placeholders: &placeholders,
+    };
+    let staged_root = stage_setup(&inputs, s.path(), cwd, true, false)?;
+    let setups: Vec<SetupArgs> = config
+        .packages
+        .values()
+        .map(|c| c.clone().into_setup())
+        .collect();
+    let mut staged_packages = Vec::new();
+    for (name, setup) in config.packages.keys().zip(&setups) {
+        let inputs = RenderInputs {
+            setup,
+            sticky_mcp: &[],
+            sticky_hooks: &[],
+            sticky_permissions: &[],
+            placeholders: &placeholders,
+        };
+        staged_packages.push(stage_package(
+            name,
+            &inputs,
+            &templates.path(name),
+            cwd,
+            true,
+        )?);
+    }
+    let all: Vec<&StagedSetup> = std::iter::once(&staged_root)
+        .chain(&staged_packages)
+        .collect();
+    overwrite_conflicts(&all, false)?;
+
+    let outcome = staged_root.apply()?;
+    let mut files = compute_manifest(&args, &outcome.conditional_subdirs(), s.path(), cwd)?;
+
+    let mut packages = BTreeMap::new();
+    for (((name, command), setup), staged) in
+        config.packages.iter().zip(&setups).zip(staged_packages)
+    {
+        let (outcome, manifest) = apply_package(name, staged, setup)?;
+        files.extend(manifest);
+        packages.insert(
+            name.clone(),
+            PackageLock {
+                original_command: command.clone(),
+                resolved: Some(Resolved {
+                    mcp: outcome.mcp_snapshottable_stems,
+                    hooks: outcome.hooks_snapshottable_stems,
+                    permissions: outcome.permissions_snapshottable_stems,
+                }),
+            },
+        );
+    }
+
+    Lockfile {
+        template_repo: "test://example/template.git".into(),
+        template_sha: sha.into(),
+        template_profile: None,
+        template_ref: None,
+        template_parents: vec![],
+        template_overlays: vec![],
+        original_command: config.command,
+        resolved: Some(Resolved {
+            mcp: outcome.mcp_snapshottable_stems,
+            hooks: outcome.hooks_snapshottable_stems,
+            permissions: outcome.permissions_snapshottable_stems,
+        }),
+        packages,
+        mcp_servers: Default::default(),
+        settings: Default::default(),
+        files,
+    }
+    .save(cwd)
+}
+
+fn setup_workspace(s: &Scaffold, sha: &str) {
+    try_setup_workspace(s, sha).unwrap();
+}
+
+fn update() -> UpdateArgs {
+    UpdateArgs {
+        setup: SetupArgs::default(),
+        prune_stale: false,
+        restore_deleted: false,
+        merge: false,
+        only: false,
+    }
+}
+
+// ── .clemp.yaml packages ────────────────────────────────────────────────
+
+#[test]
+fn packages_parse_alongside_root_command() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join(PROJECT_CONFIG_NAME), WORKSPACE).unwrap();
+
+    let config = ProjectConfig::load(dir.path()).unwrap().unwrap();
+    assert_eq!(config.command.languages, vec!["ts"]);
+    let packages = load_workspace_packages(dir.path()).unwrap();
+    assert_eq!(packages.keys().collect::<Vec<_>>(), ["server", "web"]);
+    assert_eq!(packages["server"].mcp, vec!["postgres"]);
+}
+
+#[test]
+fn package_paths_must_stay_inside_the_project_and_not_nest() {
+    let dir = TempDir::new().unwrap();
+    for (yaml, expected) in [
+        ("packages:\n  ../other: {}\n", "relative path"),
+        ("packages:\n  /abs: {}\n", "relative path"),
+        ("packages:\n  apps: {}\n  apps/web: {}\n", "nested inside package 'apps'"),
+    ] {
+        fs::write(dir.path().join(PROJECT_CONFIG_NAME), yaml).unwrap();
+        let err = format!("{:#}", ProjectConfig::load(dir.path()).unwrap_err());
+        assert!(err.contains(expected), "{yaml}: got {err}");
+    }
+}
+
+#[test]
+fn unknown_keys_in_the_project_config_are_rejected() {
+    let dir = TempDir::new().unwrap();
+    for (yaml, expected) in [
+        ("langauges: [ts]\nmcp: [postgres]\n", "In "),
+        ("packages:\n  web:\n    languages: [ts]\n    tgas: [react]\n", "package 'web'"),
+    ] {
+        fs::write(dir.path().join(PROJECT_CONFIG_NAME), yaml).unwrap();
+        let err = format!("{:#}", ProjectConfig::load(dir.path()).unwrap_err());
+        assert!(err.contains(expected) && err.contains("Unknown key `"), "{yaml}: got {err}");
+    }
+    let err = format!("{:#}", ProjectConfig::load(dir.path()).unwrap_err());
+    assert!(err.ends_with("Unknown key `tgas`"), "got: {err}");
+
+    fs::write(dir.path().join(PROJECT_CONFIG_NAME), "drop-mcp: [x]\nshared-settings: [hooks]\n").unwrap();
+    assert!(ProjectConfig::load(dir.path()).is_ok());
+}
+
+#[test]
+fn project_without_packages_has_empty_workspace() {
+    let dir = TempDir::new().unwrap();
+    assert!(load_workspace_packages(dir.path()).unwrap().is_empty());
+    fs::write(dir.path().join(PROJECT_CONFIG_NAME), "languages: [ts]\n").unwrap();
+    assert!(load_workspace_packages(dir.path()).unwrap().is_empty());
+}
+
+// ── Setup + update ──────────────────────────────────────────────────────
+
+#[test]
+fn each_package_gets_its_own_claude_md_and_claude_dir() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    fs::write(PROJECT_CONFIG_NAME, WORKSPACE).unwrap();
+    let s = scaffold("v1");
+    setup_workspace(&s, "1111");
+
+    let root = fs::read_to_string("CLAUDE.md").unwrap();
+    assert!(root.contains("ts rules") && !root.contains("rust rules"), "{root}");
+    let server = fs::read_to_string("server/CLAUDE.md").unwrap();
+    assert!(server.contains("rust rules") && !server.contains("ts rules"), "{server}");
+    assert!(Path::new("server/.claude/commands/clippy.md").is_file());
+    assert!(!Path::new(".claude/commands/clippy.md").exists(), "rust commands stay in server/");
+    assert!(fs::read_to_string("server/.mcp.json").unwrap().contains("postgres"));
+    assert!(!fs::read_to_string("web/.mcp.json").unwrap().contains("postgres"));
+    assert!(Path::new("web/.claude/settings.local.json").is_file());
+
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert!(lock.files.contains_key("CLAUDE.md"));
+    assert!(lock.files.contains_key("server/CLAUDE.md"));
+    assert!(lock.files.contains_key("web/.mcp.json"));
+    assert_eq!(lock.packages["server"].resolved.as_ref().unwrap().mcp, ["context7", "postgres"]);
+}
+
+#[test]
+fn a_conflict_in_one_package_stops_setup_before_anything_is_written() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    fs::write(PROJECT_CONFIG_NAME, WORKSPACE).unwrap();
+    fs::create_dir("web").unwrap();
+    fs::write("web/CLAUDE.md", "web notes\n").unwrap();
+
+    let err = try_setup_workspace(&scaffold("v1"), "1111").unwrap_err();
+
+    assert!(format!("{err:#}").contains("web/CLAUDE.md"), "{err:#}");
+    assert_eq!(fs::read_to_string("web/CLAUDE.md").unwrap(), "web notes\n");
+    assert!(!Path::new("CLAUDE.md").exists(), "root must not be written");
+    assert!(!Path::new(".claude").exists());
+    assert!(!Path::new(".gitignore").exists());
+    assert!(
+        !Path::new("server").exists(),
+        "other packages must not be written"
+    );
+    assert!(Lockfile::load(Path::new(".")).unwrap().is_none());
+}
+
+#[test]
+fn update_refreshes_every_package_in_one_pass() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    fs::write(PROJECT_CONFIG_NAME, WORKSPACE).unwrap();
+    setup_workspace(&scaffold("v1"), "1111");
+    // A user edit in one package must survive the update.
+    fs::write("web/CLAUDE.md", "my own notes\n").unwrap();
+
+    let v2 = scaffold("v2");
+    run_update(&update(), v2.path(), &[revision("2222")]).unwrap();
+
+    assert!(fs::read_to_string("CLAUDE.md").unwrap().starts_with("v2"));
+    assert!(fs::read_to_string("server/CLAUDE.md").unwrap().starts_with("v2"));
+    assert_eq!(fs::read_to_string("web/CLAUDE.md").unwrap(), "my own notes\n");
+
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert_eq!(lock.template_sha, "2222");
+    assert_eq!(lock.packages.len(), 2);
+    assert!(lock.files["server/CLAUDE.md"] != lock.files["web/CLAUDE.md"]);
+}
+
+#[test]
+fn update_sets_up_packages_added_to_the_workspace() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    fs::write(PROJECT_CONFIG_NAME, "languages: [ts]\n").unwrap();
+    setup_workspace(&scaffold("v1"), "1111");
+    assert!(!Path::new("server").exists());
+
+    fs::write(PROJECT_CONFIG_NAME, WORKSPACE).unwrap();
+    let v1 = scaffold("v1");
+    run_update(&update(), v1.path(), &[revision("1111")]).unwrap();
+
+    assert!(fs::read_to_string("server/CLAUDE.md").unwrap().contains("rust rules"));
+    assert!(fs::read_to_string("web/CLAUDE.md").unwrap().contains("ts rules"));
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert_eq!(
+        lock.packages["server"].original_command,
+        OriginalCommand {
+            languages: vec!["rust".into()],
+            mcp: vec!["postgres".into()],
+            ..Default::default()
+        }
+    );
+
+    // Nothing changed since: the fast path leaves the workspace alone.
+    let before = fs::read_to_string(".clemp-lock.yaml").unwrap();
+    let again = scaffold("v1");
+    run_update(&update(), again.path(), &[revision("1111")]).unwrap();
+    assert_eq!(fs::read_to_string(".clemp-lock.yaml").unwrap(), before);
+}
+
+#[test]
+fn update_treats_packages_removed_from_the_workspace_as_stale() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    fs::write(PROJECT_CONFIG_NAME, WORKSPACE).unwrap();
+    setup_workspace(&scaffold("v1"), "1111");
+    assert!(Path::new("web/CLAUDE.md").is_file());
+
+    fs::write(
+        PROJECT_CONFIG_NAME,
+        "languages: [ts]\npackages:\n  server:\n    languages: [rust]\n    mcp: [postgres]\n",
+    )
+    .unwrap();
+    let v1 = scaffold("v1");
+    run_update(&UpdateArgs { prune_stale: true, ..update() }, v1.path(), &[revision("1111")]).unwrap();
+
+    assert!(!Path::new("web/CLAUDE.md").exists());
+    assert!(!Path::new("web/.mcp.json").exists());
+    assert!(Path::new("server/CLAUDE.md").is_file());
+    let lock = Lockfile::load(Path::new(".")).unwrap().unwrap();
+    assert_eq!(lock.packages.keys().collect::<Vec<_>>(), ["server"]);
+    assert!(!lock.files.keys().any(|path| path.starts_with("web/")));
+}
+
+#[test]
+fn update_removes_stale_gitignore_lines_per_package() {
+    let workdir = TempDir::new().unwrap();
+    let _g = CwdGuard::new(workdir.path());
+    let with_fragment = || {
+        let s = scaffold("v1");
+        s.with_gitignore_for_lang("rust", "target/\n");
+        s
+    };
+    fs::write(PROJECT_CONFIG_NAME, WORKSPACE).unwrap();
+    let s = with_fragment();
+    setup_workspace(&s, "1111");
+    update_gitignore(s.path(), Path::new("server"), &["rust".into()]).unwrap();
+    assert!(fs::read_to_string("server/.gitignore").unwrap().contains("target/"));
+
+    fs::write(
+        PROJECT_CONFIG_NAME,
+        "languages: [ts]\npackages:\n  server:\n    drop-lang: [rust]\n    languages: [ts]\n  web:\n    languages: [ts]\n",
+    )
+    .unwrap();
+    let v2 = with_fragment();
+    run_update(&UpdateArgs { prune_stale: true, ..update() }, v2.path(), &[revision("2222")]).unwrap();
+
+    assert!(!fs::read_to_string("server/.gitignore").unwrap().contains("target/"));
+    assert!(!Path::new("server/.claude/commands/clippy.md").exists());
+}